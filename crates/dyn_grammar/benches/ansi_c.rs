@@ -0,0 +1,132 @@
+//! Builds the automata of the ANSI C grammar, about the size of the grammars of real languages,
+//! and reports how long each algorithm takes and how big its tables are in each encoding. Run
+//! with `cargo bench -p dyn_grammar`
+
+use dyn_grammar::{
+    EnrichedGrammar,
+    automaton::Algorithm,
+    non_terminal::EnrichedNonTerminal,
+    parsing::compressed::CompressedTables,
+    production::EnrichedBaseProduction,
+    symbolic_grammar::SymbolicGrammar,
+    token::{EnrichedToken, Match},
+};
+use itertools::Itertools;
+use std::{
+    rc::Rc,
+    time::{Duration, Instant},
+};
+use syn::Ident;
+
+/// Time the default algorithm may take on this grammar, in release mode
+const LALR_BUDGET: Duration = Duration::from_millis(250);
+/// Time the search for a unifying counterexample of the dangling else may take
+const COUNTEREXAMPLE_BUDGET: Duration = Duration::from_secs(1);
+
+fn ident(name: &str) -> Ident {
+    syn::parse_str(name).expect("not an identifier")
+}
+
+/// Reads `head: body | body ... ;` rules: heads are the non-terminals, every other symbol is a
+/// token. The first head is the start symbol
+fn parse_grammar(source: &str) -> EnrichedGrammar {
+    let rules = source
+        .split(';')
+        .filter_map(|rule| rule.split_once(':'))
+        .map(|(head, alternatives)| {
+            let alternatives = alternatives
+                .split('|')
+                .map(|body| body.split_whitespace().collect_vec())
+                .collect_vec();
+            (head.trim(), alternatives)
+        })
+        .collect_vec();
+    let non_terminals = rules.iter().map(|(head, _)| *head).collect_vec();
+    let tokens = rules
+        .iter()
+        .flat_map(|(_, alternatives)| alternatives.iter().flatten().copied())
+        .filter(|symbol| !non_terminals.contains(symbol))
+        .unique()
+        .collect_vec();
+    let productions = rules
+        .iter()
+        .flat_map(|(head, alternatives)| alternatives.iter().map(move |body| (*head, body)))
+        .enumerate()
+        .map(|(id, (head, body))| {
+            EnrichedBaseProduction::new(
+                ident(&format!("P{id}")),
+                ident(head),
+                body.iter().map(|symbol| ident(symbol)).collect(),
+            )
+        })
+        .collect();
+    EnrichedGrammar::new(
+        None,
+        non_terminals
+            .iter()
+            .map(|nt| EnrichedNonTerminal::new(ident(nt)))
+            .collect(),
+        tokens
+            .iter()
+            .map(|tok| EnrichedToken::new(ident(tok), Match::Literal(tok.to_string())))
+            .collect(),
+        EnrichedNonTerminal::new(ident(non_terminals[0])),
+        productions,
+    )
+}
+
+fn main() {
+    let grammar = Rc::new(parse_grammar(include_str!("ansi_c.grammar")));
+    println!(
+        "ANSI C: {} tokens, {} non-terminals, {} productions",
+        grammar.tokens().len(),
+        grammar.non_terminals().len(),
+        grammar.productions().len()
+    );
+
+    for algorithm in [
+        Algorithm::Lr0,
+        Algorithm::Slr,
+        Algorithm::Lalr,
+        Algorithm::Lr1,
+        Algorithm::MinimalLr1,
+    ] {
+        let start = Instant::now();
+        let automaton = algorithm.compute(SymbolicGrammar::from(grammar.clone()));
+        let tables = automaton.generate_tables();
+        let elapsed = start.elapsed();
+        println!(
+            "{:>13}: {:>5} states, {:>4} conflict(s) in {elapsed:?}",
+            algorithm.to_string(),
+            automaton.states_count(),
+            tables.conflicts.len()
+        );
+        let compressed = CompressedTables::compress(&tables, true);
+        println!(
+            "{:>13}  tables: {} dense entries, {} match arms, {} integers compressed ({} rows)",
+            "",
+            CompressedTables::dense_size(&tables),
+            CompressedTables::match_arms(&tables),
+            compressed.size(),
+            compressed.actions.defaults.len() + compressed.gotos.defaults.len()
+        );
+        if algorithm == Algorithm::Lalr {
+            // The dangling else is the only conflict of the C grammar
+            assert_eq!(tables.conflicts.len(), 1);
+            assert!(
+                elapsed < LALR_BUDGET,
+                "LALR(1) tables took {elapsed:?}, over the {LALR_BUDGET:?} budget"
+            );
+            let start = Instant::now();
+            let counterexample = automaton
+                .counterexample(&tables.conflicts[0], COUNTEREXAMPLE_BUDGET)
+                .expect("no counterexample");
+            println!(
+                "counterexample in {:?}: {}",
+                start.elapsed(),
+                counterexample.describe(automaton.grammar())
+            );
+            assert!(counterexample.unifying);
+        }
+    }
+}
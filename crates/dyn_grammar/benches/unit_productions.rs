@@ -0,0 +1,160 @@
+//! Parses an arithmetic expression with the tables of the arithmetic example, with and without
+//! bypassing its unit productions, and reports how many table lookups each token takes. Run with
+//! `cargo bench -p dyn_grammar --bench unit_productions`
+
+use dyn_grammar::{
+    EnrichedGrammar,
+    automaton::Algorithm,
+    non_terminal::EnrichedNonTerminal,
+    parsing::{
+        action::{EofAction, TokenAction},
+        tables::ParsingTables,
+        unit_productions::UnitChains,
+    },
+    production::EnrichedBaseProduction,
+    symbolic_grammar::SymbolicGrammar,
+    token::{EnrichedToken, Match},
+};
+use itertools::Itertools;
+use std::{rc::Rc, time::Instant};
+use syn::Ident;
+
+fn ident(name: &str) -> Ident {
+    syn::parse_str(name).expect("not an identifier")
+}
+
+/// `Expression -> Term -> Factor` are the unit productions without a semantic action
+fn arithmetic() -> EnrichedGrammar {
+    let productions = [
+        (
+            "Addition",
+            "Expression",
+            &["Expression", "Plus", "Term"][..],
+        ),
+        ("NoAddition", "Expression", &["Term"]),
+        ("Multiplication", "Term", &["Term", "Times", "Factor"]),
+        ("NoMultiplication", "Term", &["Factor"]),
+        (
+            "Parenthesis",
+            "Factor",
+            &["OpenPar", "Expression", "ClosedPar"],
+        ),
+        ("ActualNumber", "Factor", &["Number"]),
+    ];
+    let non_terminals = ["Expression", "Term", "Factor"];
+    let tokens = [
+        ("Number", Match::Regex(r"\d+".to_string())),
+        ("Plus", Match::Literal("+".to_string())),
+        ("Times", Match::Literal("*".to_string())),
+        ("OpenPar", Match::Literal("(".to_string())),
+        ("ClosedPar", Match::Literal(")".to_string())),
+    ];
+    EnrichedGrammar::new(
+        None,
+        non_terminals
+            .iter()
+            .map(|nt| EnrichedNonTerminal::new(ident(nt)))
+            .collect(),
+        tokens
+            .into_iter()
+            .map(|(name, pattern)| EnrichedToken::new(ident(name), pattern))
+            .collect(),
+        EnrichedNonTerminal::new(ident("Expression")),
+        productions
+            .iter()
+            .map(|(name, head, body)| {
+                EnrichedBaseProduction::new(
+                    ident(name),
+                    ident(head),
+                    body.iter().map(|symbol| ident(symbol)).collect(),
+                )
+                .with_default_action(body.len() == 1 && non_terminals.contains(&body[0]))
+            })
+            .collect(),
+    )
+}
+
+/// Runs the LR parsing algorithm on the tokens, following the unit chains of the gotos like the
+/// generated parser does, and counts the action and goto lookups
+fn table_lookups(
+    grammar: &SymbolicGrammar,
+    tables: &ParsingTables,
+    unit_chains: &UnitChains,
+    input: &[usize],
+) -> usize {
+    let mut lookups = 0;
+    let mut tokens = input.iter().peekable();
+    let mut stack = vec![0];
+    // conversions the generated parser would make when following the gotos
+    let mut conversions = 0;
+    loop {
+        let state = *stack.last().unwrap();
+        lookups += 1;
+        let production_id = match tokens.peek() {
+            Some(token) => match tables.token_table[(state, **token)] {
+                Some(TokenAction::Shift(target)) => {
+                    stack.push(target);
+                    tokens.next();
+                    continue;
+                }
+                Some(TokenAction::Reduce(production_id)) => production_id,
+                None => panic!("syntax error"),
+            },
+            None => match tables.eof_table[state] {
+                Some(EofAction::Accept) => break,
+                Some(EofAction::Reduce(production_id)) => production_id,
+                None => panic!("syntax error"),
+            },
+        };
+        let production = grammar.get_production(production_id).unwrap();
+        stack.truncate(stack.len() - production.arity());
+        let state = *stack.last().unwrap();
+        lookups += 1;
+        let target = tables.non_terminal_table[(state, *production.head())].expect("no goto");
+        if let Some(chain) = unit_chains.get(&(state, *production.head())) {
+            conversions += chain.len();
+        }
+        stack.push(target);
+    }
+    std::hint::black_box(conversions);
+    lookups
+}
+
+fn main() {
+    let grammar = Rc::new(arithmetic());
+    let symbolic_grammar = SymbolicGrammar::from(grammar.clone());
+    let automaton = Algorithm::Lalr.compute(SymbolicGrammar::from(grammar.clone()));
+    let tables = automaton.generate_tables();
+    let mut bypassed = automaton.generate_tables();
+    let unit_chains = bypassed.bypass_unit_productions(&symbolic_grammar, |production_id| {
+        grammar.productions()[production_id].default_action()
+    });
+
+    let token = |name: &str| {
+        (0..symbolic_grammar.token_count())
+            .find(|token| symbolic_grammar.token_name(*token) == name)
+            .expect("unknown token")
+    };
+    // `(1 + 2) * 3 + 4 * (5 + 6 * (7 + 8))`, many times over
+    let expression = "OpenPar Number Plus Number ClosedPar Times Number Plus Number Times OpenPar \
+        Number Plus Number Times OpenPar Number Plus Number ClosedPar ClosedPar";
+    let input = vec![expression; 20_000]
+        .join(" Plus ")
+        .split_whitespace()
+        .map(token)
+        .collect_vec();
+
+    for (name, tables, unit_chains) in [
+        ("unit productions kept", &tables, &UnitChains::new()),
+        ("unit productions bypassed", &bypassed, &unit_chains),
+    ] {
+        let start = Instant::now();
+        let lookups = table_lookups(&symbolic_grammar, tables, unit_chains, &input);
+        println!(
+            "{name:>25}: {:.2} lookups per token ({lookups} for {} tokens) in {:?}",
+            lookups as f64 / input.len() as f64,
+            input.len(),
+            start.elapsed()
+        );
+    }
+}
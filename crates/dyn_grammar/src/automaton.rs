@@ -0,0 +1,392 @@
+use crate::{
+    EnrichedGrammar,
+    counterexample::{self, Counterexample},
+    dot,
+    lalr::LalrAutomaton,
+    lr0::Lr0Automaton,
+    lr1::Lr1Automaton,
+    minimal_lr1::MinimalLr1Automaton,
+    parsing::{
+        action::{EofAction, TokenAction},
+        conflict::{Conflict, ConflictKind, ConflictLookahead},
+        tables::{EofTable, NonTerminalTable, ParsingTables, TokenTable, TransitionTables},
+    },
+    precedence::{Associativity, Precedence},
+    report,
+    slr::SlrAutomaton,
+    symbolic_grammar::{SymbolicGrammar, SymbolicSymbol, SymbolicToken},
+};
+use itertools::Itertools;
+use std::{
+    cmp::Ordering, collections::HashSet, fmt::Display, rc::Rc, str::FromStr, time::Duration,
+};
+
+/// Construction algorithm used to build the automaton of a grammar
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Algorithm {
+    Lr0,
+    Slr,
+    #[default]
+    Lalr,
+    Lr1,
+    MinimalLr1,
+}
+
+impl Algorithm {
+    pub fn compute(self, grammar: SymbolicGrammar) -> Box<dyn Automaton> {
+        match self {
+            Self::Lr0 => Box::new(Lr0Automaton::compute(grammar)),
+            Self::Slr => Box::new(SlrAutomaton::compute(grammar)),
+            Self::Lalr => Box::new(LalrAutomaton::compute(grammar)),
+            Self::Lr1 => Box::new(Lr1Automaton::compute(grammar)),
+            Self::MinimalLr1 => Box::new(MinimalLr1Automaton::compute(grammar)),
+        }
+    }
+
+    /// Builds the LR(0), SLR(1) and LALR(1) automata of the grammar and tells, for each of them,
+    /// whether the grammar is in the class and which conflicts precedence does not solve
+    pub fn comparison(grammar: &Rc<EnrichedGrammar>) -> String {
+        let mut res = String::new();
+        for algorithm in [Self::Lr0, Self::Slr, Self::Lalr] {
+            let automaton = algorithm.compute(SymbolicGrammar::from(grammar.clone()));
+            let conflicts = automaton.generate_tables().conflicts;
+            if conflicts.is_empty() {
+                res += &format!("{algorithm}: no conflict, the grammar is {algorithm}\n");
+            } else {
+                res += &format!(
+                    "{algorithm}: {} conflict(s), the grammar is not {algorithm}\n",
+                    conflicts.len()
+                );
+                for conflict in conflicts.iter() {
+                    res += &format!("    {}\n", automaton.describe_conflict(conflict));
+                }
+            }
+        }
+        res
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lr0" => Ok(Self::Lr0),
+            "slr" | "slr1" => Ok(Self::Slr),
+            "lalr" | "lalr1" => Ok(Self::Lalr),
+            "lr1" => Ok(Self::Lr1),
+            "minimal-lr1" => Ok(Self::MinimalLr1),
+            other => Err(format!(
+                "unknown algorithm \"{other}\", expected one of \"lr0\", \"slr\", \"lalr\", \"lr1\", \"minimal-lr1\""
+            )),
+        }
+    }
+}
+
+impl Display for Algorithm {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Lr0 => write!(f, "LR(0)"),
+            Self::Slr => write!(f, "SLR(1)"),
+            Self::Lalr => write!(f, "LALR(1)"),
+            Self::Lr1 => write!(f, "LR(1)"),
+            Self::MinimalLr1 => write!(f, "minimal LR(1)"),
+        }
+    }
+}
+
+/// A production with the marker somewhere in its body, without any lookahead
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ItemCore {
+    pub production_id: usize,
+    pub marker_position: usize,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct LookAhead {
+    pub tokens: HashSet<SymbolicToken>,
+    pub can_eof_follow: bool,
+}
+
+impl Display for LookAhead {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut tokens = self.tokens.iter().collect_vec();
+        tokens.sort();
+        write!(
+            f,
+            "{{{}}}",
+            tokens
+                .into_iter()
+                .map(|tok| format!("`{tok}`"))
+                .chain(self.can_eof_follow.then_some("$".to_string()))
+                .format(", ")
+        )
+    }
+}
+
+/// An LR automaton, whatever the algorithm that built it, from which parsing tables are generated
+pub trait Automaton {
+    fn grammar(&self) -> &SymbolicGrammar;
+
+    fn transitions(&self) -> &TransitionTables;
+
+    fn states_count(&self) -> usize;
+
+    fn kernel(&self, state: usize) -> Vec<ItemCore>;
+
+    /// Productions that can be reduced in the state, each with the tokens allowing the reduction
+    fn reductions(&self, state: usize) -> Vec<(usize, LookAhead)>;
+
+    fn generate_tables(&self) -> ParsingTables {
+        let grammar = self.grammar();
+        let mut token_table = TokenTable::new(grammar.token_count());
+        let mut eof_table = EofTable::new();
+        let mut goto_table = NonTerminalTable::new(grammar.non_terminal_count());
+        let mut conflicts = Vec::new();
+
+        for (state_id, (token_transitions, non_terminal_transitions)) in
+            self.transitions().iter().enumerate()
+        {
+            token_table.add_state();
+            eof_table.add_state();
+
+            for (production_id, lookahead) in self
+                .reductions(state_id)
+                .into_iter()
+                .sorted_by_key(|(production_id, _)| *production_id)
+            {
+                for token in lookahead.tokens.into_iter().sorted() {
+                    let entry = &mut token_table[(state_id, token)];
+                    match entry {
+                        Some(TokenAction::Reduce(reduce)) => conflicts.push(Conflict::new(
+                            state_id,
+                            ConflictLookahead::Token(token),
+                            ConflictKind::ReduceReduce {
+                                reduce: *reduce,
+                                other_reduce: production_id,
+                            },
+                        )),
+                        _ => *entry = Some(TokenAction::Reduce(production_id)),
+                    }
+                }
+                if lookahead.can_eof_follow {
+                    let entry = &mut eof_table[state_id];
+                    match entry {
+                        Some(EofAction::Reduce(reduce)) => conflicts.push(Conflict::new(
+                            state_id,
+                            ConflictLookahead::Eof,
+                            ConflictKind::ReduceReduce {
+                                reduce: *reduce,
+                                other_reduce: production_id,
+                            },
+                        )),
+                        _ if production_id == usize::MAX => *entry = Some(EofAction::Accept),
+                        _ => *entry = Some(EofAction::Reduce(production_id)),
+                    }
+                }
+            }
+
+            for (token, target) in token_transitions.iter().enumerate() {
+                let Some(target) = target else {
+                    continue;
+                };
+                let entry = &mut token_table[(state_id, token)];
+                if let Some(TokenAction::Reduce(production_id)) = entry {
+                    match resolve_shift_reduce(self, token, *target, *production_id) {
+                        Some(TokenAction::Reduce(_)) => continue,
+                        Some(TokenAction::Shift(_)) => {}
+                        None => conflicts.push(Conflict::new(
+                            state_id,
+                            ConflictLookahead::Token(token),
+                            ConflictKind::ShiftReduce {
+                                shift: *target,
+                                reduce: *production_id,
+                            },
+                        )),
+                    }
+                }
+                *entry = Some(TokenAction::Shift(*target));
+            }
+
+            goto_table.add_state();
+            for (non_terminal, target) in non_terminal_transitions.iter().enumerate() {
+                goto_table[(state_id, non_terminal)] = *target;
+            }
+        }
+
+        ParsingTables::new(token_table, eof_table, goto_table, conflicts)
+    }
+
+    fn item_string(&self, item: &ItemCore) -> String {
+        let grammar = self.grammar();
+        let production = grammar.get_production(item.production_id).unwrap();
+        let (before_marker, after_marker) = production.body().split_at(item.marker_position);
+        format!(
+            "{} -> {}·{}",
+            grammar.non_terminal_name(*production.head()),
+            before_marker
+                .iter()
+                .map(|symbol| grammar.symbol_name(symbol))
+                .format(" "),
+            after_marker
+                .iter()
+                .map(|symbol| grammar.symbol_name(symbol))
+                .format(" "),
+        )
+    }
+
+    fn production_string(&self, production_id: usize) -> String {
+        let grammar = self.grammar();
+        let production = grammar.get_production(production_id).unwrap();
+        format!(
+            "{} ({} -> {})",
+            grammar.production_name(production_id),
+            grammar.non_terminal_name(*production.head()),
+            production
+                .body()
+                .iter()
+                .map(|symbol| grammar.symbol_name(symbol))
+                .format(" "),
+        )
+    }
+
+    /// Productions involved in the conflict: the reduced ones and the ones being shifted through
+    fn conflict_productions(&self, conflict: &Conflict) -> Vec<usize> {
+        match conflict.kind() {
+            ConflictKind::ShiftReduce { shift, reduce } => std::iter::once(*reduce)
+                .chain(
+                    self.kernel(*shift)
+                        .into_iter()
+                        .map(|item| item.production_id)
+                        .sorted(),
+                )
+                .filter(|production_id| *production_id != usize::MAX)
+                .dedup()
+                .collect(),
+            ConflictKind::ReduceReduce {
+                reduce,
+                other_reduce,
+            } => [*reduce, *other_reduce]
+                .into_iter()
+                .filter(|production_id| *production_id != usize::MAX)
+                .collect(),
+        }
+    }
+
+    fn describe_conflict(&self, conflict: &Conflict) -> String {
+        let lookahead = match conflict.lookahead() {
+            ConflictLookahead::Token(token) => format!("`{}`", self.grammar().token_name(*token)),
+            ConflictLookahead::Eof => "end of input".to_string(),
+        };
+        let actions = match conflict.kind() {
+            ConflictKind::ShiftReduce { shift, reduce } => format!(
+                "shift/reduce conflict on {lookahead}: shift to state {shift} or reduce {}",
+                self.production_string(*reduce)
+            ),
+            ConflictKind::ReduceReduce {
+                reduce,
+                other_reduce,
+            } => format!(
+                "reduce/reduce conflict on {lookahead}: reduce {} or reduce {}",
+                self.production_string(*reduce),
+                self.production_string(*other_reduce)
+            ),
+        };
+        format!(
+            "{actions}, in state {} with kernel [{}]",
+            conflict.state(),
+            self.kernel(conflict.state())
+                .iter()
+                .map(|item| self.item_string(item))
+                .sorted()
+                .format(", ")
+        )
+    }
+
+    /// Description of every state, with its items, actions and conflicts, see [`report::report`]
+    fn report(&self) -> String {
+        report::report(self)
+    }
+
+    /// Graphviz description of the states and transitions, see [`dot::to_dot`]
+    fn to_dot(&self) -> String {
+        dot::to_dot(self)
+    }
+
+    /// Derivations showing why the conflict arises, see [`counterexample::find`]
+    fn counterexample(&self, conflict: &Conflict, time_limit: Duration) -> Option<Counterexample> {
+        counterexample::find(self, conflict, time_limit)
+    }
+}
+
+/// Items of a state: its kernel and the items of the productions of every non-terminal pointed at,
+/// recursively
+pub(crate) fn closure(grammar: &SymbolicGrammar, kernel: Vec<ItemCore>) -> Vec<ItemCore> {
+    let mut seen: HashSet<_> = kernel.iter().copied().collect();
+    let mut res = kernel;
+    let mut position = 0;
+    while position < res.len() {
+        let item = res[position];
+        let body = grammar.get_production(item.production_id).unwrap().body();
+        if let Some(SymbolicSymbol::NonTerminal(non_terminal)) = body.get(item.marker_position) {
+            for production in grammar.get_productions_with_head(*non_terminal) {
+                let item = ItemCore {
+                    production_id: production.id(),
+                    marker_position: 0,
+                };
+                if seen.insert(item) {
+                    res.push(item);
+                }
+            }
+        }
+        position += 1;
+    }
+    res
+}
+
+/// Precedence of shifting `token` into `target`: the token's own if declared, else taken from the
+/// productions whose items are moved past the shifted token
+pub(crate) fn shift_precedence<A: Automaton + ?Sized>(
+    automaton: &A,
+    token: SymbolicToken,
+    target: usize,
+) -> Option<Precedence> {
+    automaton.grammar().token_precedence(token).or_else(|| {
+        automaton
+            .kernel(target)
+            .into_iter()
+            .filter_map(|item| {
+                automaton
+                    .grammar()
+                    .get_production(item.production_id)
+                    .unwrap()
+                    .precedence()
+            })
+            .max_by_key(Precedence::level)
+    })
+}
+
+/// Solves a shift/reduce conflict the way yacc does: the higher precedence wins and, on a tie,
+/// left associativity reduces while right associativity shifts. Returns `None` if the
+/// annotations are not enough to pick an action
+pub(crate) fn resolve_shift_reduce<A: Automaton + ?Sized>(
+    automaton: &A,
+    token: SymbolicToken,
+    target: usize,
+    production_id: usize,
+) -> Option<TokenAction> {
+    let reduce_precedence = automaton
+        .grammar()
+        .get_production(production_id)
+        .unwrap()
+        .precedence()?;
+    let shift_precedence = shift_precedence(automaton, token, target)?;
+    match reduce_precedence.level().cmp(&shift_precedence.level()) {
+        Ordering::Greater => Some(TokenAction::Reduce(production_id)),
+        Ordering::Less => Some(TokenAction::Shift(target)),
+        Ordering::Equal => match reduce_precedence.associativity()? {
+            Associativity::Left => Some(TokenAction::Reduce(production_id)),
+            Associativity::Right => Some(TokenAction::Shift(target)),
+        },
+    }
+}
@@ -0,0 +1,710 @@
+//! Counterexamples explaining conflicts, in the spirit of bison's `-Wcounterexamples`. A
+//! unifying counterexample is a sentential form with two derivations, one for each action of the
+//! conflict, proving the grammar ambiguous. When the time bound runs out before one is found, or
+//! the grammar is not ambiguous there, each action gets its own sentential form instead, derived
+//! from the start symbol with the conflict lookahead right after the conflict point.
+
+use crate::{
+    automaton::{Automaton, ItemCore, closure},
+    parsing::conflict::{Conflict, ConflictKind, ConflictLookahead},
+    symbolic_grammar::{SymbolicGrammar, SymbolicNonTerminal, SymbolicSymbol},
+};
+use itertools::Itertools;
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, HashMap, HashSet, VecDeque},
+    time::{Duration, Instant},
+};
+
+/// Longest left context and deepest stack of open productions the unifying search explores
+const MAX_LEFT: usize = 12;
+const MAX_DEPTH: usize = 12;
+/// Configurations the unifying search may visit, whatever the time bound
+const MAX_CONFIGURATIONS: usize = 200_000;
+
+/// Derivation tree of a counterexample, whose leaves are symbols left unexpanded
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Derivation {
+    Symbol(SymbolicSymbol),
+    /// Where the parser has to choose between the actions of the conflict
+    Dot,
+    Node(SymbolicNonTerminal, Vec<Derivation>),
+}
+
+impl Derivation {
+    fn leaves<'a>(&'a self, res: &mut Vec<&'a Derivation>) {
+        match self {
+            Self::Node(_, children) => children.iter().for_each(|child| child.leaves(res)),
+            leaf => res.push(leaf),
+        }
+    }
+
+    /// The sentential form derived, with `•` at the conflict point
+    pub fn example(&self, grammar: &SymbolicGrammar) -> String {
+        let mut leaves = Vec::new();
+        self.leaves(&mut leaves);
+        leaves
+            .into_iter()
+            .map(|leaf| leaf.to_string(grammar))
+            .join(" ")
+    }
+
+    /// The derivation, every node written `Head(children)`
+    pub fn to_string(&self, grammar: &SymbolicGrammar) -> String {
+        match self {
+            Self::Symbol(symbol) => grammar.symbol_name(symbol),
+            Self::Dot => "•".to_string(),
+            Self::Node(head, children) => format!(
+                "{}({})",
+                grammar.non_terminal_name(*head),
+                children
+                    .iter()
+                    .map(|child| child.to_string(grammar))
+                    .join(" ")
+            ),
+        }
+    }
+}
+
+/// Two derivations through the conflict point, one for each action of the conflict
+#[derive(Clone, Debug)]
+pub struct Counterexample {
+    /// Whether both derivations have the same sentential form, the grammar being ambiguous
+    pub unifying: bool,
+    /// Whether the search for a unifying counterexample ran out of time
+    pub timed_out: bool,
+    /// The action each derivation takes, in the user's names
+    pub actions: [String; 2],
+    pub derivations: [Derivation; 2],
+}
+
+impl Counterexample {
+    pub fn describe(&self, grammar: &SymbolicGrammar) -> String {
+        let [first, second] = &self.derivations;
+        if self.unifying {
+            format!(
+                "ambiguous input `{}`:\n  {}: {}\n  {}: {}",
+                first.example(grammar),
+                self.actions[0],
+                first.to_string(grammar),
+                self.actions[1],
+                second.to_string(grammar),
+            )
+        } else {
+            format!(
+                "no ambiguous input found{}, the actions apply to different inputs:\n  {}: `{}` as {}\n  {}: `{}` as {}",
+                if self.timed_out {
+                    " before the time limit"
+                } else {
+                    ""
+                },
+                self.actions[0],
+                first.example(grammar),
+                first.to_string(grammar),
+                self.actions[1],
+                second.example(grammar),
+                second.to_string(grammar),
+            )
+        }
+    }
+}
+
+fn pointed_symbol<'a>(grammar: &'a SymbolicGrammar, item: &ItemCore) -> Option<&'a SymbolicSymbol> {
+    grammar
+        .get_production(item.production_id)
+        .expect("production not found")
+        .body()
+        .get(item.marker_position)
+}
+
+/// Searches a counterexample for the conflict, spending at most `time_limit` on the search for a
+/// unifying one. Conflicts involving the accepting production have none
+pub fn find<A: Automaton + ?Sized>(
+    automaton: &A,
+    conflict: &Conflict,
+    time_limit: Duration,
+) -> Option<Counterexample> {
+    let deadline = Instant::now() + time_limit;
+    let grammar = automaton.grammar();
+    let eof = grammar.token_count();
+    let lookahead = match conflict.lookahead() {
+        ConflictLookahead::Token(token) => *token,
+        ConflictLookahead::Eof => eof,
+    };
+    let reduce_item = |production_id: usize| ItemCore {
+        production_id,
+        marker_position: grammar.get_production(production_id).unwrap().arity(),
+    };
+    let reduce_action =
+        |production_id: usize| format!("reduce {}", grammar.production_name(production_id));
+
+    let (first, seconds, actions) = match conflict.kind() {
+        ConflictKind::ShiftReduce { reduce, .. } => {
+            let shifts = closure(grammar, automaton.kernel(conflict.state()))
+                .into_iter()
+                .filter(|item| {
+                    pointed_symbol(grammar, item) == Some(&SymbolicSymbol::Token(lookahead))
+                })
+                .collect_vec();
+            let shift_action = format!("shift {}", grammar.token_name(lookahead));
+            (*reduce, shifts, [reduce_action(*reduce), shift_action])
+        }
+        ConflictKind::ReduceReduce {
+            reduce,
+            other_reduce,
+        } => (
+            *reduce,
+            vec![reduce_item(*other_reduce)],
+            [reduce_action(*reduce), reduce_action(*other_reduce)],
+        ),
+    };
+    if first == usize::MAX || seconds.iter().any(|item| item.production_id == usize::MAX) {
+        return None;
+    }
+    let first = reduce_item(first);
+
+    let mut timed_out = false;
+    for second in seconds.iter() {
+        match UnifyingSearch::new(grammar, lookahead, deadline).run(first, *second) {
+            SearchResult::Found(derivations) => {
+                return Some(Counterexample {
+                    unifying: true,
+                    timed_out: false,
+                    actions,
+                    derivations,
+                });
+            }
+            SearchResult::Exhausted => {}
+            SearchResult::TimedOut => {
+                timed_out = true;
+                break;
+            }
+        }
+    }
+
+    let required = |item: &ItemCore| pointed_symbol(grammar, item).is_none().then_some(lookahead);
+    let first_derivation =
+        derivation_from_start(automaton, conflict.state(), first, required(&first))?;
+    let second_derivation = seconds.iter().find_map(|second| {
+        derivation_from_start(automaton, conflict.state(), *second, required(second))
+    })?;
+    Some(Counterexample {
+        unifying: false,
+        timed_out,
+        actions,
+        derivations: [first_derivation, second_derivation],
+    })
+}
+
+/// A production being derived, with the derivations of the symbols before the marker
+#[derive(Clone)]
+struct OpenItem {
+    production_id: usize,
+    marker_position: usize,
+    children: Vec<Derivation>,
+}
+
+/// What the rest of the search depends on: the open items, the head once done, the left context
+type Signature = (
+    Vec<(usize, usize)>,
+    Option<SymbolicNonTerminal>,
+    Vec<SymbolicSymbol>,
+);
+
+/// One of the two derivations of the unifying search, grown from a conflicting item: forward by
+/// reading the symbols after the conflict point, backward by embedding its finished derivation
+/// in a bigger production
+#[derive(Clone)]
+struct Side {
+    /// Open productions, the innermost last
+    stack: Vec<OpenItem>,
+    /// The derivation once no production is left open, with its head
+    done: Option<(SymbolicNonTerminal, Derivation)>,
+    /// Symbols left of the conflict point, the closest first
+    left: Vec<SymbolicSymbol>,
+}
+
+impl Side {
+    fn new(grammar: &SymbolicGrammar, item: ItemCore) -> Self {
+        let before =
+            &grammar.get_production(item.production_id).unwrap().body()[..item.marker_position];
+        let mut side = Self {
+            stack: vec![OpenItem {
+                production_id: item.production_id,
+                marker_position: item.marker_position,
+                children: before
+                    .iter()
+                    .cloned()
+                    .map(Derivation::Symbol)
+                    .chain([Derivation::Dot])
+                    .collect(),
+            }],
+            done: None,
+            left: before.iter().rev().cloned().collect(),
+        };
+        side.reduce(grammar);
+        side
+    }
+
+    fn next_symbol<'a>(&self, grammar: &'a SymbolicGrammar) -> Option<&'a SymbolicSymbol> {
+        let top = self.stack.last()?;
+        grammar
+            .get_production(top.production_id)
+            .unwrap()
+            .body()
+            .get(top.marker_position)
+    }
+
+    /// Closes the complete productions at the top of the stack
+    fn reduce(&mut self, grammar: &SymbolicGrammar) {
+        while let Some(top) = self.stack.last() {
+            let production = grammar.get_production(top.production_id).unwrap();
+            if top.marker_position < production.arity() {
+                return;
+            }
+            let top = self.stack.pop().unwrap();
+            let node = Derivation::Node(*production.head(), top.children);
+            match self.stack.last_mut() {
+                Some(parent) => {
+                    parent.children.push(node);
+                    parent.marker_position += 1;
+                }
+                None => self.done = Some((*production.head(), node)),
+            }
+        }
+    }
+
+    fn shift(&mut self, grammar: &SymbolicGrammar, symbol: SymbolicSymbol) {
+        let top = self.stack.last_mut().unwrap();
+        top.children.push(Derivation::Symbol(symbol));
+        top.marker_position += 1;
+        self.reduce(grammar);
+    }
+
+    fn expand(&mut self, grammar: &SymbolicGrammar, production_id: usize) {
+        self.stack.push(OpenItem {
+            production_id,
+            marker_position: 0,
+            children: Vec::new(),
+        });
+        self.reduce(grammar);
+    }
+
+    /// Makes the finished derivation the symbol at `position` of the production
+    fn embed(&mut self, grammar: &SymbolicGrammar, production_id: usize, position: usize) {
+        let (_, node) = self.done.take().unwrap();
+        let before = &grammar.get_production(production_id).unwrap().body()[..position];
+        self.left.extend(before.iter().rev().cloned());
+        self.stack.push(OpenItem {
+            production_id,
+            marker_position: position + 1,
+            children: before
+                .iter()
+                .cloned()
+                .map(Derivation::Symbol)
+                .chain([node])
+                .collect(),
+        });
+        self.reduce(grammar);
+    }
+
+    fn signature(&self) -> Signature {
+        (
+            self.stack
+                .iter()
+                .map(|item| (item.production_id, item.marker_position))
+                .collect(),
+            self.done.as_ref().map(|(head, _)| *head),
+            self.left.clone(),
+        )
+    }
+}
+
+#[derive(Clone)]
+struct Configuration {
+    sides: [Side; 2],
+    /// Whether the lookahead of the conflict has been read after the conflict point
+    shifted: bool,
+    cost: usize,
+}
+
+impl Configuration {
+    /// The left contexts can still become equal: one is the beginning of the other
+    fn compatible(&self) -> bool {
+        let [first, second] = &self.sides;
+        let length = first.left.len().min(second.left.len());
+        first.left[..length] == second.left[..length]
+            && first.left.len().max(second.left.len()) <= MAX_LEFT
+            && first.stack.len().max(second.stack.len()) <= MAX_DEPTH
+    }
+}
+
+enum SearchResult {
+    Found([Derivation; 2]),
+    Exhausted,
+    TimedOut,
+}
+
+/// Uniform-cost search of two derivations of the same sentential form, starting from the two
+/// conflicting items and reading the same symbols after the conflict point
+struct UnifyingSearch<'a> {
+    grammar: &'a SymbolicGrammar,
+    /// Token read right after the conflict point, `token_count` for the end of input
+    lookahead: usize,
+    deadline: Instant,
+    configurations: Vec<Configuration>,
+    queue: BinaryHeap<Reverse<(usize, usize)>>,
+    visited: HashSet<([Signature; 2], bool)>,
+}
+
+impl<'a> UnifyingSearch<'a> {
+    fn new(grammar: &'a SymbolicGrammar, lookahead: usize, deadline: Instant) -> Self {
+        Self {
+            grammar,
+            lookahead,
+            deadline,
+            configurations: Vec::new(),
+            queue: BinaryHeap::new(),
+            visited: HashSet::new(),
+        }
+    }
+
+    fn push(&mut self, configuration: Configuration) {
+        if !configuration.compatible() {
+            return;
+        }
+        let signatures = configuration.sides.each_ref().map(Side::signature);
+        if !self.visited.insert((signatures, configuration.shifted)) {
+            return;
+        }
+        self.queue
+            .push(Reverse((configuration.cost, self.configurations.len())));
+        self.configurations.push(configuration);
+    }
+
+    fn run(mut self, first: ItemCore, second: ItemCore) -> SearchResult {
+        self.push(Configuration {
+            sides: [
+                Side::new(self.grammar, first),
+                Side::new(self.grammar, second),
+            ],
+            shifted: false,
+            cost: 0,
+        });
+        while let Some(Reverse((_, id))) = self.queue.pop() {
+            if Instant::now() > self.deadline {
+                return SearchResult::TimedOut;
+            }
+            if self.configurations.len() > MAX_CONFIGURATIONS {
+                return SearchResult::Exhausted;
+            }
+            let configuration = self.configurations[id].clone();
+            if let Some(derivations) = self.successors(configuration) {
+                return SearchResult::Found(derivations);
+            }
+        }
+        SearchResult::Exhausted
+    }
+
+    /// Pushes the configurations following `configuration`, or returns its derivations if they
+    /// unify
+    fn successors(&mut self, configuration: Configuration) -> Option<[Derivation; 2]> {
+        let grammar = self.grammar;
+        let [first, second] = &configuration.sides;
+        match (&first.done, &second.done) {
+            (Some((first_head, first_derivation)), Some((second_head, second_derivation))) => {
+                if first_head == second_head && first.left == second.left {
+                    return Some([first_derivation.clone(), second_derivation.clone()]);
+                }
+                match first.left.len().cmp(&second.left.len()) {
+                    std::cmp::Ordering::Less => self.embed(&configuration, 0),
+                    std::cmp::Ordering::Greater => self.embed(&configuration, 1),
+                    std::cmp::Ordering::Equal => {
+                        self.embed(&configuration, 0);
+                        self.embed(&configuration, 1);
+                    }
+                }
+            }
+            (Some(_), None) => self.embed(&configuration, 0),
+            (None, Some(_)) => self.embed(&configuration, 1),
+            (None, None) => {
+                let symbols = configuration
+                    .sides
+                    .each_ref()
+                    .map(|side| side.next_symbol(grammar).unwrap().clone());
+                let first_read = !configuration.shifted;
+                if symbols[0] == symbols[1]
+                    && (!first_read || symbols[0] == SymbolicSymbol::Token(self.lookahead))
+                {
+                    let mut next = configuration.clone();
+                    for side in next.sides.iter_mut() {
+                        side.shift(grammar, symbols[0].clone());
+                    }
+                    next.shifted = true;
+                    next.cost += 1;
+                    self.push(next);
+                    return None;
+                }
+                for side in 0..2 {
+                    let SymbolicSymbol::NonTerminal(non_terminal) = &symbols[side] else {
+                        continue;
+                    };
+                    // The token the expansion has to start with, if any
+                    let target = if first_read {
+                        Some(self.lookahead)
+                    } else {
+                        match &symbols[1 - side] {
+                            SymbolicSymbol::Token(token) => Some(*token),
+                            SymbolicSymbol::NonTerminal(_) => None,
+                        }
+                    };
+                    for production in grammar.get_productions_with_head(*non_terminal) {
+                        let firsts = grammar.first_of_sequence(production.body());
+                        if let Some(target) = target
+                            && !firsts.nullable
+                            && !firsts.tokens.contains(&target)
+                        {
+                            continue;
+                        }
+                        let mut next = configuration.clone();
+                        next.sides[side].expand(grammar, production.id());
+                        next.cost += 1;
+                        self.push(next);
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Pushes the configurations where the finished derivation of `side` is embedded in any
+    /// production using its head
+    fn embed(&mut self, configuration: &Configuration, side: usize) {
+        let grammar = self.grammar;
+        let (head, _) = configuration.sides[side].done.as_ref().unwrap();
+        for production in grammar.productions() {
+            for (position, symbol) in production.body().iter().enumerate() {
+                if symbol != &SymbolicSymbol::NonTerminal(*head) {
+                    continue;
+                }
+                let mut next = configuration.clone();
+                next.sides[side].embed(grammar, production.id(), position);
+                next.cost += production.arity();
+                self.push(next);
+            }
+        }
+    }
+}
+
+/// Shortest derivation from the start symbol reaching `item` in `state`, followed by `lookahead`
+/// when given, found backward on the graph of the automaton's items
+fn derivation_from_start<A: Automaton + ?Sized>(
+    automaton: &A,
+    state: usize,
+    item: ItemCore,
+    lookahead: Option<usize>,
+) -> Option<Derivation> {
+    let grammar = automaton.grammar();
+    let eof = grammar.token_count();
+    let closures = (0..automaton.states_count())
+        .map(|state| closure(grammar, automaton.kernel(state)))
+        .collect_vec();
+    let mut predecessors = vec![Vec::new(); automaton.states_count()];
+    for (source, (token_transitions, non_terminal_transitions)) in
+        automaton.transitions().iter().enumerate()
+    {
+        let token_transitions = token_transitions
+            .iter()
+            .enumerate()
+            .map(|(token, target)| (SymbolicSymbol::Token(token), target));
+        let non_terminal_transitions = non_terminal_transitions
+            .iter()
+            .enumerate()
+            .map(|(nt, target)| (SymbolicSymbol::NonTerminal(nt), target));
+        for (symbol, target) in token_transitions.chain(non_terminal_transitions) {
+            if let Some(target) = target {
+                predecessors[*target].push((source, symbol));
+            }
+        }
+    }
+
+    type Node = (usize, ItemCore, Option<usize>);
+    let goal = |(state, item, lookahead): &Node| {
+        *state == 0
+            && item.production_id == usize::MAX
+            && item.marker_position == 0
+            && lookahead.is_none_or(|lookahead| lookahead == eof)
+    };
+    let start: Node = (state, item, lookahead);
+    // Next node toward `start` of every visited node
+    let mut next: HashMap<Node, Node> = HashMap::new();
+    let mut queue = VecDeque::from([start]);
+    let mut visited = HashSet::from([start]);
+    let mut found = None;
+    while let Some(node) = queue.pop_front() {
+        if goal(&node) {
+            found = Some(node);
+            break;
+        }
+        let (state, item, lookahead) = node;
+        let mut neighbours = Vec::new();
+        if item.marker_position > 0 {
+            let previous = ItemCore {
+                marker_position: item.marker_position - 1,
+                ..item
+            };
+            let symbol = pointed_symbol(grammar, &previous).unwrap();
+            for (source, transition) in predecessors[state].iter() {
+                if transition == symbol && closures[*source].contains(&previous) {
+                    neighbours.push((*source, previous, lookahead));
+                }
+            }
+        } else {
+            let head = *grammar.get_production(item.production_id).unwrap().head();
+            for parent in closures[state].iter() {
+                if pointed_symbol(grammar, parent) != Some(&SymbolicSymbol::NonTerminal(head)) {
+                    continue;
+                }
+                let rest = &grammar.get_production(parent.production_id).unwrap().body()
+                    [parent.marker_position + 1..];
+                let firsts = grammar.first_of_sequence(rest);
+                let lookahead = match lookahead {
+                    None => None,
+                    Some(token) if token != eof && firsts.tokens.contains(&token) => None,
+                    Some(token) if firsts.nullable => Some(token),
+                    Some(_) => continue,
+                };
+                neighbours.push((state, *parent, lookahead));
+            }
+        }
+        for neighbour in neighbours {
+            if visited.insert(neighbour) {
+                next.insert(neighbour, node);
+                queue.push_back(neighbour);
+            }
+        }
+    }
+
+    // Replays the path from the start symbol, opening a production at each closure step
+    let mut node = found?;
+    let mut stack = vec![OpenItem {
+        production_id: usize::MAX,
+        marker_position: 0,
+        children: Vec::new(),
+    }];
+    while let Some(following) = next.get(&node) {
+        let top = stack.last_mut().unwrap();
+        if following.1.marker_position == 0 {
+            // The derivation of the new production takes the place of the symbol
+            top.marker_position += 1;
+            stack.push(OpenItem {
+                production_id: following.1.production_id,
+                marker_position: 0,
+                children: Vec::new(),
+            });
+        } else {
+            let symbol = pointed_symbol(grammar, &node.1).unwrap().clone();
+            top.children.push(Derivation::Symbol(symbol));
+            top.marker_position += 1;
+        }
+        node = *following;
+    }
+    stack.last_mut().unwrap().children.push(Derivation::Dot);
+    while let Some(top) = stack.pop() {
+        let production = grammar.get_production(top.production_id).unwrap();
+        let mut children = top.children;
+        children.extend(
+            production.body()[top.marker_position..]
+                .iter()
+                .cloned()
+                .map(Derivation::Symbol),
+        );
+        match stack.last_mut() {
+            Some(parent) => parent
+                .children
+                .push(Derivation::Node(*production.head(), children)),
+            None => return children.into_iter().next(),
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::find;
+    use crate::{
+        automaton::Automaton,
+        lalr::LalrAutomaton,
+        symbolic_grammar::SymbolicGrammar,
+        test_grammars::{grammar, lr1_not_lalr},
+    };
+    use std::time::Duration;
+
+    /// Sentential forms of the counterexamples of every conflict of the LALR automaton
+    fn counterexamples(grammar: SymbolicGrammar) -> Vec<(bool, [String; 2])> {
+        let automaton = LalrAutomaton::compute(grammar);
+        automaton
+            .generate_tables()
+            .conflicts
+            .iter()
+            .map(|conflict| {
+                let counterexample =
+                    find(&automaton, conflict, Duration::from_secs(5)).expect("no counterexample");
+                (
+                    counterexample.unifying,
+                    counterexample
+                        .derivations
+                        .map(|derivation| derivation.to_string(automaton.grammar())),
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn ambiguous_operator_has_unifying_counterexample() {
+        let grammar = grammar("E", &[("E", &["E", "plus", "E"]), ("E", &["id"])]);
+        assert_eq!(
+            counterexamples(grammar),
+            [(
+                true,
+                [
+                    "E(E(E plus E •) plus E)".to_string(),
+                    "E(E plus E(E • plus E))".to_string()
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn dangling_else_has_unifying_counterexample() {
+        let grammar = grammar(
+            "S",
+            &[
+                ("S", &["IF", "S"]),
+                ("S", &["IF", "S", "ELSE", "S"]),
+                ("S", &["x"]),
+            ],
+        );
+        assert_eq!(
+            counterexamples(grammar),
+            [(
+                true,
+                [
+                    "S(IF S(IF S •) ELSE S)".to_string(),
+                    "S(IF S(IF S • ELSE S))".to_string()
+                ]
+            )]
+        );
+    }
+
+    #[test]
+    fn merged_lookaheads_have_non_unifying_counterexamples() {
+        let examples = counterexamples(lr1_not_lalr());
+        assert_eq!(examples.len(), 2);
+        assert!(examples.iter().all(|(unifying, _)| !unifying));
+        assert!(examples.contains(&(
+            false,
+            ["S(a E(e •) c)".to_string(), "S(b F(e •) c)".to_string()]
+        )));
+    }
+}
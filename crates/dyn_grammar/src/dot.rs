@@ -0,0 +1,99 @@
+//! Graphviz export of an automaton: one node per state listing its items and lookaheads, one
+//! labelled edge per transition, and the states with unresolved conflicts highlighted
+
+use crate::{automaton::Automaton, report::state_items};
+use itertools::Itertools;
+use std::fmt::Write;
+
+/// Quotes a label line for DOT, left-justified
+fn escape(line: &str) -> String {
+    line.replace('\\', "\\\\").replace('"', "\\\"") + "\\l"
+}
+
+pub fn to_dot<A: Automaton + ?Sized>(automaton: &A) -> String {
+    let grammar = automaton.grammar();
+    let conflicts = automaton.generate_tables().conflicts;
+    let mut res = String::new();
+    writeln!(res, "digraph automaton {{").unwrap();
+    writeln!(res, "    rankdir=LR;").unwrap();
+    writeln!(res, "    node [shape=box, fontname=\"monospace\"];").unwrap();
+
+    for state in 0..automaton.states_count() {
+        let label = std::iter::once(format!("State {state}"))
+            .chain(state_items(automaton, state))
+            .map(|line| escape(&line))
+            .join("");
+        let state_conflicts = conflicts
+            .iter()
+            .filter(|conflict| conflict.state() == state)
+            .map(|conflict| automaton.describe_conflict(conflict))
+            .collect_vec();
+        if state_conflicts.is_empty() {
+            writeln!(res, "    {state} [label=\"{label}\"];").unwrap();
+        } else {
+            writeln!(
+                res,
+                "    {state} [label=\"{label}\", color=red, style=filled, fillcolor=\"#ffdddd\", tooltip=\"{}\"];",
+                state_conflicts
+                    .iter()
+                    .map(|conflict| conflict.replace('\\', "\\\\").replace('"', "\\\""))
+                    .join("\\n")
+            )
+            .unwrap();
+        }
+    }
+
+    for (state, (token_transitions, non_terminal_transitions)) in
+        automaton.transitions().iter().enumerate()
+    {
+        for (token, target) in token_transitions.iter().enumerate() {
+            if let Some(target) = target {
+                writeln!(
+                    res,
+                    "    {state} -> {target} [label=\"{}\"];",
+                    grammar.token_name(token)
+                )
+                .unwrap();
+            }
+        }
+        for (non_terminal, target) in non_terminal_transitions.iter().enumerate() {
+            if let Some(target) = target {
+                writeln!(
+                    res,
+                    "    {state} -> {target} [label=\"{}\", style=dashed];",
+                    grammar.non_terminal_name(non_terminal)
+                )
+                .unwrap();
+            }
+        }
+    }
+    writeln!(res, "}}").unwrap();
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::to_dot;
+    use crate::{automaton::Automaton, lalr::LalrAutomaton, test_grammars::grammar};
+
+    #[test]
+    fn dot_of_an_ambiguous_grammar() {
+        let automaton =
+            LalrAutomaton::compute(grammar("E", &[("E", &["E", "plus", "E"]), ("E", &["id"])]));
+        let dot = to_dot(&automaton);
+        assert!(dot.starts_with("digraph automaton {\n"));
+        assert!(dot.ends_with("}\n"));
+        assert!(dot.contains(
+            "    0 [label=\"State 0\\l$accept -> ·E\\lE -> ·E plus E\\lE -> ·id\\l\"];\n"
+        ));
+        assert_eq!(
+            dot.matches("[label=\"State ").count(),
+            automaton.states_count()
+        );
+        assert_eq!(dot.matches("color=red").count(), 1);
+        assert!(dot.contains("E -> E plus E·  [plus, $end]\\l"));
+        assert!(dot.contains("    0 -> "));
+        assert!(dot.contains(" [label=\"plus\"];\n"));
+        assert!(dot.contains(" [label=\"E\", style=dashed];\n"));
+    }
+}
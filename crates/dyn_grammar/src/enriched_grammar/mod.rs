@@ -1,5 +1,6 @@
 pub mod enriched_symbol;
 pub mod non_terminal;
+pub mod precedence;
 pub mod production;
 pub mod token;
 
@@ -11,7 +12,7 @@ use syn::Ident;
 use crate::{
     non_terminal::EnrichedNonTerminal,
     production::{EnrichedBaseProduction, EnrichedProduction},
-    token::EnrichedToken,
+    token::{EnrichedToken, Match},
 };
 
 #[derive(Debug)]
@@ -68,6 +69,13 @@ impl EnrichedGrammar {
         self.tokens().iter().position(|val| val.ident() == token)
     }
 
+    /// Id of the token shifted to recover from syntax errors, if the grammar declares one
+    pub fn error_token_id(&self) -> Option<usize> {
+        self.tokens()
+            .iter()
+            .position(|val| *val.match_string() == Match::Error)
+    }
+
     pub fn non_terminal_id(&self, non_terminal: &Ident) -> Option<usize> {
         self.non_terminals()
             .iter()
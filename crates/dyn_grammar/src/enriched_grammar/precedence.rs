@@ -0,0 +1,49 @@
+use std::fmt::Display;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Associativity {
+    Left,
+    Right,
+}
+
+impl Display for Associativity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Left => write!(f, "left"),
+            Self::Right => write!(f, "right"),
+        }
+    }
+}
+
+/// Precedence level of a production, higher levels bind tighter
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct Precedence {
+    level: usize,
+    associativity: Option<Associativity>,
+}
+
+impl Precedence {
+    pub fn new(level: usize, associativity: Option<Associativity>) -> Self {
+        Self {
+            level,
+            associativity,
+        }
+    }
+
+    pub fn level(&self) -> usize {
+        self.level
+    }
+
+    pub fn associativity(&self) -> Option<Associativity> {
+        self.associativity
+    }
+}
+
+impl Display for Precedence {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.associativity {
+            Some(associativity) => write!(f, "{} {associativity}", self.level),
+            None => write!(f, "{}", self.level),
+        }
+    }
+}
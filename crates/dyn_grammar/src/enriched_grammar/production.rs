@@ -1,22 +1,48 @@
-use itertools::Itertools;
-use std::fmt::Display;
-use syn::Ident;
 use crate::{
-    enriched_symbol::EnrichedSymbol,
-    non_terminal::EnrichedNonTerminal,
+    enriched_symbol::EnrichedSymbol, non_terminal::EnrichedNonTerminal, precedence::Precedence,
     token::EnrichedToken,
 };
+use itertools::Itertools;
+use std::fmt::Display;
+use syn::Ident;
 
 #[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub struct EnrichedBaseProduction {
     ident: Ident,
     head: Ident,
     body: Vec<Ident>,
+    precedence: Option<Precedence>,
+    /// Token whose precedence the production takes, like yacc's `%prec`
+    precedence_token: Option<Ident>,
+    /// Declared without a semantic action: the head is the body converted with `into()`
+    default_action: bool,
 }
 
 impl EnrichedBaseProduction {
     pub fn new(ident: Ident, head: Ident, body: Vec<Ident>) -> Self {
-        Self { ident, head, body }
+        Self {
+            ident,
+            head,
+            body,
+            precedence: None,
+            precedence_token: None,
+            default_action: false,
+        }
+    }
+
+    pub fn with_precedence(mut self, precedence: Option<Precedence>) -> Self {
+        self.precedence = precedence;
+        self
+    }
+
+    pub fn with_precedence_token(mut self, precedence_token: Option<Ident>) -> Self {
+        self.precedence_token = precedence_token;
+        self
+    }
+
+    pub fn with_default_action(mut self, default_action: bool) -> Self {
+        self.default_action = default_action;
+        self
     }
 
     pub fn ident(&self) -> &Ident {
@@ -35,11 +61,37 @@ impl EnrichedBaseProduction {
         &self.body
     }
 
+    pub fn precedence(&self) -> Option<Precedence> {
+        self.precedence
+    }
+
+    pub fn precedence_token(&self) -> Option<&Ident> {
+        self.precedence_token.as_ref()
+    }
+
+    /// The declared precedence, else the one of the precedence token, else the one of the
+    /// rightmost token of the body, as yacc does
+    fn resolved_precedence(&self, tokens: &[EnrichedToken]) -> Option<Precedence> {
+        let token = |ident: &Ident| tokens.iter().find(|tok| tok.ident() == ident);
+        if self.precedence.is_some() {
+            return self.precedence;
+        }
+        if let Some(precedence_token) = &self.precedence_token {
+            return token(precedence_token).and_then(EnrichedToken::precedence);
+        }
+        self.body
+            .iter()
+            .rev()
+            .find_map(token)
+            .and_then(EnrichedToken::precedence)
+    }
+
     pub fn into_production(
         self,
         tokens: &[EnrichedToken],
         non_terminals: &[EnrichedNonTerminal],
     ) -> EnrichedProduction {
+        let precedence = self.resolved_precedence(tokens);
         EnrichedProduction::new(
             self.ident,
             self.head,
@@ -59,7 +111,9 @@ impl EnrichedBaseProduction {
                         .expect("ident is neither a non terminal nor a token")
                 })
                 .collect(),
+            precedence,
         )
+        .with_default_action(self.default_action)
     }
 }
 
@@ -80,6 +134,8 @@ pub struct EnrichedProduction {
     ident: Ident,
     head: Ident,
     body: Vec<EnrichedSymbol>,
+    precedence: Option<Precedence>,
+    default_action: bool,
 }
 
 impl Display for EnrichedProduction {
@@ -95,8 +151,24 @@ impl Display for EnrichedProduction {
 }
 
 impl EnrichedProduction {
-    pub fn new(ident: Ident, head: Ident, body: Vec<EnrichedSymbol>) -> Self {
-        Self { ident, head, body }
+    pub fn new(
+        ident: Ident,
+        head: Ident,
+        body: Vec<EnrichedSymbol>,
+        precedence: Option<Precedence>,
+    ) -> Self {
+        Self {
+            ident,
+            head,
+            body,
+            precedence,
+            default_action: false,
+        }
+    }
+
+    pub fn with_default_action(mut self, default_action: bool) -> Self {
+        self.default_action = default_action;
+        self
     }
 
     pub fn ident(&self) -> &Ident {
@@ -114,4 +186,13 @@ impl EnrichedProduction {
     pub fn body(&self) -> &Vec<EnrichedSymbol> {
         &self.body
     }
+    pub fn precedence(&self) -> Option<Precedence> {
+        self.precedence
+    }
+
+    /// Whether the production was declared without a semantic action, its head being its body
+    /// converted with `into()`
+    pub fn default_action(&self) -> bool {
+        self.default_action
+    }
 }
@@ -1,3 +1,4 @@
+use crate::precedence::Precedence;
 use std::fmt::Display;
 use syn::Ident;
 
@@ -5,6 +6,9 @@ use syn::Ident;
 pub enum Match {
     Literal(String),
     Regex(String),
+    /// The error token of the productions recovering from syntax errors, shifted by the parser
+    /// instead of lexed
+    Error,
 }
 
 impl Display for Match {
@@ -12,6 +16,7 @@ impl Display for Match {
         match self {
             Self::Literal(lit) => write!(f, "\"{lit}\""),
             Self::Regex(reg) => write!(f, "/{reg}/"),
+            Self::Error => write!(f, "error"),
         }
     }
 }
@@ -20,11 +25,22 @@ impl Display for Match {
 pub struct EnrichedToken {
     ident: Ident,
     match_string: Match,
+    /// Precedence given to shifting the token and to the productions it ends
+    precedence: Option<Precedence>,
 }
 
 impl EnrichedToken {
     pub fn new(ident: Ident, match_string: Match) -> Self {
-        Self { ident, match_string }
+        Self {
+            ident,
+            match_string,
+            precedence: None,
+        }
+    }
+
+    pub fn with_precedence(mut self, precedence: Option<Precedence>) -> Self {
+        self.precedence = precedence;
+        self
     }
 
     pub fn ident(&self) -> &Ident {
@@ -34,6 +50,10 @@ impl EnrichedToken {
     pub fn match_string(&self) -> &Match {
         &self.match_string
     }
+
+    pub fn precedence(&self) -> Option<Precedence> {
+        self.precedence
+    }
 }
 
 impl Display for EnrichedToken {
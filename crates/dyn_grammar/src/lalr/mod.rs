@@ -1,398 +1,270 @@
-#![allow(clippy::mutable_key_type)]
-
 use crate::{
-    parsing::{
-        action::{EofAction, TokenAction},
-        tables::{EofTable, NonTerminalTable, TokenTable, TransitionTables},
-    },
-    symbolic_grammar::{SymbolicGrammar, SymbolicSymbol, SymbolicToken},
+    automaton::{Automaton, ItemCore, LookAhead},
+    lr0::Lr0Automaton,
+    parsing::tables::TransitionTables,
+    symbolic_grammar::{SymbolicGrammar, SymbolicSymbol},
 };
 use itertools::Itertools;
-use std::{cell::RefCell, collections::HashSet, fmt::Display, hash::Hash, rc::Rc};
+use std::{collections::HashMap, fmt::Display};
 
+/// Set of tokens as a bit set, the end of input being the token after the last one
 #[derive(Clone)]
-struct LookAhead {
-    tokens: HashSet<SymbolicToken>,
-    can_eof_follow: bool,
-}
+pub(crate) struct TokenSet(Vec<u64>);
 
-impl Display for LookAhead {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let mut tokens = self.tokens.iter().collect_vec();
-        tokens.sort();
-        write!(
-            f,
-            "{{{}}}",
-            tokens
-                .into_iter()
-                .map(|tok| format!("`{tok}`"))
-                .chain(self.can_eof_follow.then_some("$".to_string()))
-                .format(", ")
-        )
+impl TokenSet {
+    pub(crate) fn new(size: usize) -> Self {
+        Self(vec![0; size.div_ceil(64)])
     }
-}
 
-#[derive(Clone)]
-struct LookAheadNodeRef(Rc<RefCell<LookAheadNode>>);
-
-impl LookAheadNodeRef {
-    pub fn initial_lookahead_node(counter: &mut usize) -> LookAheadNodeRef {
-        Self::new(
-            counter,
-            LookAhead {
-                tokens: HashSet::new(),
-                can_eof_follow: true,
-            },
-            Vec::new(),
-        )
+    pub(crate) fn insert(&mut self, token: usize) {
+        self.0[token / 64] |= 1 << (token % 64);
     }
 
-    pub fn new(
-        counter: &mut usize,
-        natural_lookahead: LookAhead,
-        dependencies: Vec<LookAheadNodeRef>,
-    ) -> Self {
-        let node_id = *counter;
-        *counter += 1;
-        Self(Rc::new(RefCell::new(LookAheadNode {
-            node_id,
-            natural_lookahead,
-            dependencies,
-        })))
+    fn contains(&self, token: usize) -> bool {
+        self.0[token / 64] & (1 << (token % 64)) != 0
     }
 
-    fn compute_lookahead_helper(&self, visited: &mut HashSet<usize>) -> LookAhead {
-        // TODO: not so simple, graph could have cycles
-        let borrow = self.0.borrow();
-        let mut res = borrow.natural_lookahead.clone();
-        if visited.contains(&borrow.node_id) {
-            return res;
-        }
-        visited.insert(borrow.node_id);
-        for dep in borrow.dependencies.iter() {
-            let lh = dep.compute_lookahead_helper(visited);
-            res.tokens.extend(lh.tokens);
-            res.can_eof_follow |= lh.can_eof_follow;
+    /// Adds the tokens of `other`, returns whether the set grew
+    pub(crate) fn union_with(&mut self, other: &TokenSet) -> bool {
+        let mut grew = false;
+        for (word, other_word) in self.0.iter_mut().zip(other.0.iter()) {
+            grew |= other_word & !*word != 0;
+            *word |= other_word;
         }
-        res
+        grew
     }
 
-    pub fn compute_lookahead(&self) -> LookAhead {
-        self.compute_lookahead_helper(&mut HashSet::new())
+    pub(crate) fn is_subset(&self, other: &TokenSet) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(word, other_word)| word & !other_word == 0)
     }
 
-    pub fn add_dependency(&self, dependency: LookAheadNodeRef) {
-        self.0.borrow_mut().dependencies.push(dependency);
+    pub(crate) fn intersects(&self, other: &TokenSet) -> bool {
+        self.0
+            .iter()
+            .zip(other.0.iter())
+            .any(|(word, other_word)| word & other_word != 0)
     }
-}
-
-struct LookAheadNode {
-    node_id: usize,
-    natural_lookahead: LookAhead,
-    dependencies: Vec<LookAheadNodeRef>,
-}
-
-#[derive(Clone)]
-struct LalrItem {
-    production_id: usize,
-    marker_position: usize,
-    lookahead_node: LookAheadNodeRef,
-}
 
-impl Hash for LalrItem {
-    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
-        self.production_id.hash(state);
-        self.marker_position.hash(state);
+    pub(crate) fn to_lookahead(&self, eof: usize) -> LookAhead {
+        LookAhead {
+            tokens: (0..eof).filter(|token| self.contains(*token)).collect(),
+            can_eof_follow: self.contains(eof),
+        }
     }
 }
 
-impl PartialEq for LalrItem {
-    fn eq(&self, other: &Self) -> bool {
-        self.production_id == other.production_id && self.marker_position == other.marker_position
+/// `sets[into] ∪= sets[from]`
+fn union(sets: &mut [TokenSet], into: usize, from: usize) {
+    if into == from {
+        return;
     }
+    let (into, from) = if into < from {
+        let (left, right) = sets.split_at_mut(from);
+        (&mut left[into], &right[0])
+    } else {
+        let (left, right) = sets.split_at_mut(into);
+        (&mut right[0], &left[from])
+    };
+    into.union_with(from);
 }
 
-impl Eq for LalrItem {}
-
-impl LalrItem {
-    pub fn new(production_id: usize, lookahead_node: LookAheadNodeRef) -> Self {
-        Self {
-            production_id,
-            marker_position: 0,
-            lookahead_node,
+/// The digraph algorithm of DeRemer and Pennello: grows every set into
+/// `F(x) = sets(x) ∪ ⋃{F(y) | x R y}`, solving each strongly connected component of the relation
+/// at once. Recursion is unrolled so that long chains of the relation don't overflow the stack
+fn digraph(relation: &[Vec<usize>], mut sets: Vec<TokenSet>) -> Vec<TokenSet> {
+    const DONE: usize = usize::MAX;
+    let mut depths = vec![0; relation.len()];
+    let mut stack = Vec::new();
+    // (node, next edge to follow, depth of the node)
+    let mut calls: Vec<(usize, usize, usize)> = Vec::new();
+
+    for root in 0..relation.len() {
+        if depths[root] != 0 {
+            continue;
         }
-    }
-
-    pub fn pointed_symbol(&self, grammar: &SymbolicGrammar) -> Option<SymbolicSymbol> {
-        grammar
-            .get_production(self.production_id)
-            .expect("production not found")
-            .body()
-            .get(self.marker_position)
-            .cloned()
-    }
-
-    pub fn move_marker(&mut self) {
-        self.marker_position += 1;
-    }
-
-    fn is_reducing(&self, grammar: &SymbolicGrammar) -> bool {
-        self.marker_position == grammar.get_production(self.production_id).unwrap().arity()
-    }
-}
-
-struct LalrState {
-    kernel: HashSet<LalrItem>,
-    marked: bool,
-    epsilon_items: HashSet<LalrItem>,
-}
+        stack.push(root);
+        depths[root] = stack.len();
+        calls.push((root, 0, stack.len()));
+
+        while let Some((x, edge, depth)) = calls.last_mut() {
+            let (x, depth) = (*x, *depth);
+            if let Some(&y) = relation[x].get(*edge) {
+                *edge += 1;
+                if depths[y] == 0 {
+                    stack.push(y);
+                    depths[y] = stack.len();
+                    calls.push((y, 0, stack.len()));
+                } else {
+                    depths[x] = depths[x].min(depths[y]);
+                    union(&mut sets, x, y);
+                }
+                continue;
+            }
 
-impl PartialEq for LalrState {
-    fn eq(&self, other: &Self) -> bool {
-        self.kernel == other.kernel
+            calls.pop();
+            if depths[x] == depth {
+                // x is the root of a strongly connected component, whose nodes share its set
+                while let Some(top) = stack.pop() {
+                    depths[top] = DONE;
+                    if top == x {
+                        break;
+                    }
+                    sets[top] = sets[x].clone();
+                }
+            }
+            if let Some((parent, ..)) = calls.last() {
+                depths[*parent] = depths[*parent].min(depths[x]);
+                union(&mut sets, *parent, x);
+            }
+        }
     }
+    sets
 }
 
-impl LalrState {
-    fn new(kernel: HashSet<LalrItem>) -> Self {
-        Self {
-            kernel,
-            marked: false,
-            epsilon_items: HashSet::new(),
+/// Lookaheads of every reduction of the LR(0) automaton, following DeRemer and Pennello,
+/// "Efficient Computation of LALR(1) Look-Ahead Sets". The relations are defined on the
+/// non-terminal transitions `(p, A)` of the automaton:
+/// - `(p, A)` directly reads the tokens shifted from `goto(p, A)`,
+/// - `(p, A) reads (r, C)` if `r = goto(p, A)` and `C` is nullable,
+/// - `(p, A) includes (p', B)` if `B -> β A γ`, `γ` is nullable and `p'` reaches `p` reading `β`,
+/// - `(q, A -> ω) lookback (p, A)` if `p` reaches `q` reading `ω`.
+fn lalr_lookaheads(lr0: &Lr0Automaton) -> Vec<Vec<(usize, LookAhead)>> {
+    let grammar = lr0.grammar();
+    let transitions = lr0.transitions();
+    let eof = grammar.token_count();
+    let mut goto_ids = HashMap::new();
+    let mut gotos = Vec::new();
+    for state in 0..lr0.states_count() {
+        for non_terminal in 0..grammar.non_terminal_count() {
+            if let Some(target) = transitions.non_terminal_transition(state, non_terminal) {
+                goto_ids.insert((state, non_terminal), gotos.len());
+                gotos.push((state, non_terminal, target));
+            }
         }
     }
 
-    fn closure(&self, counter: &mut usize, grammar: &SymbolicGrammar) -> HashSet<LalrItem> {
-        let mut stack = self.kernel.clone().into_iter().collect_vec();
-        let mut res = self.kernel.clone();
-
-        while let Some(item) = stack.pop() {
-            if item.is_reducing(grammar) {
-                continue;
+    let accepting_item = ItemCore {
+        production_id: usize::MAX,
+        marker_position: 1,
+    };
+    let direct_reads = gotos
+        .iter()
+        .map(|(_, _, target)| {
+            let mut set = TokenSet::new(eof + 1);
+            for token in 0..eof {
+                if transitions.token_transition(*target, token).is_some() {
+                    set.insert(token);
+                }
             }
-
-            let Some(SymbolicSymbol::NonTerminal(non_terminal)) = item.pointed_symbol(grammar)
-            else {
-                continue;
-            };
-
-            let item_production = grammar
-                .get_production(item.production_id)
-                .expect("production not found!");
-
-            let beta = &item_production.body()[item.marker_position + 1..];
-
-            let firsts = grammar.first_set(beta);
-            let natural_lookahead = LookAhead {
-                tokens: firsts.tokens,
-                can_eof_follow: false,
-            };
-            let dependencies = if firsts.nullable {
-                vec![item.lookahead_node]
-            } else {
-                Vec::new()
-            };
-            let lookahead_node = LookAheadNodeRef::new(counter, natural_lookahead, dependencies);
-
-            for new_item in grammar
-                .get_productions_with_head(non_terminal)
-                .into_iter()
-                .map(|prod| LalrItem::new(prod.id(), lookahead_node.clone()))
-            {
-                match res.get(&new_item) {
-                    Some(item) => {
-                        item.lookahead_node.add_dependency(lookahead_node.clone());
-                    }
-                    None => {
-                        stack.push(new_item.clone());
-                        res.insert(new_item);
+            if lr0.kernel(*target).contains(&accepting_item) {
+                set.insert(eof);
+            }
+            set
+        })
+        .collect();
+    let reads = gotos
+        .iter()
+        .map(|(_, _, target)| {
+            (0..grammar.non_terminal_count())
+                .filter(|non_terminal| grammar.nullable(*non_terminal))
+                .filter_map(|non_terminal| goto_ids.get(&(*target, non_terminal)).copied())
+                .collect_vec()
+        })
+        .collect_vec();
+    let read_sets = digraph(&reads, direct_reads);
+
+    let mut includes = vec![Vec::new(); gotos.len()];
+    let mut lookbacks: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+    for (goto_id, (state, head, _)) in gotos.iter().enumerate() {
+        for production in grammar.get_productions_with_head(*head) {
+            let body = production.body();
+            // Whether the symbols after each position can all be empty
+            let mut nullable_suffix = vec![true; body.len() + 1];
+            for (position, symbol) in body.iter().enumerate().rev() {
+                nullable_suffix[position] = nullable_suffix[position + 1]
+                    && matches!(symbol, SymbolicSymbol::NonTerminal(nt) if grammar.nullable(*nt));
+            }
+            let mut current = *state;
+            for (position, symbol) in body.iter().enumerate() {
+                let next = match symbol {
+                    SymbolicSymbol::Token(token) => transitions.token_transition(current, *token),
+                    SymbolicSymbol::NonTerminal(non_terminal) => {
+                        if nullable_suffix[position + 1] {
+                            includes[goto_ids[&(current, *non_terminal)]].push(goto_id);
+                        }
+                        transitions.non_terminal_transition(current, *non_terminal)
                     }
-                }
+                };
+                current = next.expect("production body not readable in the LR(0) automaton");
             }
+            lookbacks
+                .entry((current, production.id()))
+                .or_default()
+                .push(goto_id);
         }
-        res
     }
+    let follow_sets = digraph(&includes, read_sets);
+
+    (0..lr0.states_count())
+        .map(|state| {
+            lr0.reducing_productions(state)
+                .iter()
+                .map(|production_id| {
+                    let mut set = TokenSet::new(eof + 1);
+                    if *production_id == usize::MAX {
+                        set.insert(eof);
+                    }
+                    for goto_id in lookbacks
+                        .get(&(state, *production_id))
+                        .into_iter()
+                        .flatten()
+                    {
+                        set.union_with(&follow_sets[*goto_id]);
+                    }
+                    (*production_id, set.to_lookahead(eof))
+                })
+                .collect()
+        })
+        .collect()
 }
 
+/// LALR(1) automaton: the LR(0) automaton, with the lookaheads of its reductions computed once by
+/// the DeRemer–Pennello relations
 pub struct LalrAutomaton {
-    grammar: SymbolicGrammar,
-    states: Vec<LalrState>,
-    transitions: TransitionTables,
+    lr0: Lr0Automaton,
+    reductions: Vec<Vec<(usize, LookAhead)>>,
 }
 
 impl LalrAutomaton {
     pub fn compute(grammar: SymbolicGrammar) -> Self {
-        let mut automaton = Self {
-            grammar,
-            states: Vec::new(),
-            transitions: TransitionTables::new(),
-        };
-        automaton.populate();
-        automaton
+        let lr0 = Lr0Automaton::compute(grammar);
+        let reductions = lalr_lookaheads(&lr0);
+        Self { lr0, reductions }
     }
+}
 
-    pub fn populate(&mut self) {
-        let mut counter = 0;
-        let first_state = LalrState::new(HashSet::from_iter([LalrItem::new(
-            usize::MAX,
-            LookAheadNodeRef::initial_lookahead_node(&mut counter),
-        )]));
-        self.add_state(first_state);
-
-        while let Some(state) = self.states.iter_mut().find(|state| !state.marked) {
-            state.marked = true;
-            let closure = state.closure(&mut counter, &self.grammar);
-            for eps_item in closure.iter().filter(|item| {
-                self.grammar
-                    .get_production(item.production_id)
-                    .unwrap()
-                    .arity()
-                    == 0
-            }) {
-                state.epsilon_items.insert(eps_item.clone());
-            }
-            let mut token_transitions = vec![HashSet::new(); self.grammar.token_count()];
-            let mut non_terminal_transitions =
-                vec![HashSet::new(); self.grammar.non_terminal_count()];
-            for (symbol, mut item) in closure.into_iter().filter_map(|item| {
-                (!item.is_reducing(&self.grammar))
-                    .then(|| (item.pointed_symbol(&self.grammar).unwrap(), item))
-            }) {
-                item.move_marker();
-                match symbol {
-                    SymbolicSymbol::Token(tok) => {
-                        token_transitions[tok].insert(item);
-                    }
-                    SymbolicSymbol::NonTerminal(nt) => {
-                        non_terminal_transitions[nt].insert(item);
-                    }
-                }
-            }
-            let token_transitions = token_transitions
-                .into_iter()
-                .map(|kernel| (!kernel.is_empty()).then(|| LalrState::new(kernel)))
-                .collect::<Vec<_>>();
-            let non_terminal_transitions = non_terminal_transitions
-                .into_iter()
-                .map(|kernel| (!kernel.is_empty()).then(|| LalrState::new(kernel)))
-                .collect::<Vec<_>>();
-            let token_transitions = token_transitions
-                .into_iter()
-                .map(|target_state| {
-                    target_state.map(|target_state| {
-                        match self.states.iter().position(|state| state == &target_state) {
-                            Some(i) => {
-                                for new_item in target_state.kernel.iter() {
-                                    self.states[i]
-                                        .kernel
-                                        .get(new_item)
-                                        .unwrap()
-                                        .lookahead_node
-                                        .add_dependency(new_item.lookahead_node.clone());
-                                }
-                                i
-                            }
-                            None => {
-                                let state_id = self.states.len();
-                                self.add_state(target_state);
-                                state_id
-                            }
-                        }
-                    })
-                })
-                .collect::<Vec<_>>();
-            let non_terminal_transitions = non_terminal_transitions
-                .into_iter()
-                .map(|target_state| {
-                    target_state.map(|target_state| {
-                        match self.states.iter().position(|state| state == &target_state) {
-                            Some(i) => i,
-                            None => {
-                                let state_id = self.states.len();
-                                self.add_state(target_state);
-                                state_id
-                            }
-                        }
-                    })
-                })
-                .collect::<Vec<_>>();
-            self.transitions
-                .add_transitions(token_transitions, non_terminal_transitions);
-        }
+impl Automaton for LalrAutomaton {
+    fn grammar(&self) -> &SymbolicGrammar {
+        self.lr0.grammar()
     }
 
-    fn add_state(&mut self, state: LalrState) {
-        self.states.push(state);
+    fn transitions(&self) -> &TransitionTables {
+        self.lr0.transitions()
     }
 
-    pub fn states_count(&self) -> usize {
-        self.states.len()
+    fn states_count(&self) -> usize {
+        self.lr0.states_count()
     }
 
-    pub fn generate_tables(&self) -> (TokenTable, EofTable, NonTerminalTable) {
-        let mut token_table = TokenTable::new(self.grammar.token_count());
-        let mut eof_table = EofTable::new();
-        let mut goto_table = NonTerminalTable::new(self.grammar.non_terminal_count());
-
-        for ((state_id, state), (token_transitions, non_terminal_transitions)) in
-            self.states.iter().enumerate().zip(self.transitions.iter())
-        {
-            token_table.add_state();
-            eof_table.add_state();
-
-            for reducing_item in state
-                .kernel
-                .iter()
-                .filter(|item| item.is_reducing(&self.grammar))
-                .chain(state.epsilon_items.iter())
-            {
-                let lookahead = reducing_item.lookahead_node.compute_lookahead();
-                for token in lookahead.tokens.into_iter() {
-                    let action = TokenAction::Reduce(reducing_item.production_id);
-                    let entry = &mut token_table[(state_id, token)];
-                    if let Some(reduce) = entry.take() {
-                        eprintln!("reduce/reduce conflict");
-                        eprintln!("current reduce: {reduce:?}");
-                        eprintln!("new reduce: {action:?}");
-                    }
-                    *entry = Some(action);
-                }
-                if lookahead.can_eof_follow {
-                    let action = if reducing_item.production_id == usize::MAX {
-                        EofAction::Accept
-                    } else {
-                        EofAction::Reduce(reducing_item.production_id)
-                    };
-                    let entry = &mut eof_table[state_id];
-                    if let Some(reduce) = entry.take() {
-                        eprintln!("reduce/reduce conflict");
-                        eprintln!("current reduce: {reduce:?}");
-                        eprintln!("new reduce: {action:?}");
-                    }
-                    *entry = Some(action);
-                }
-            }
-
-            for (token, target) in token_transitions.iter().enumerate() {
-                let Some(target) = target else {
-                    continue;
-                };
-                let entry = &mut token_table[(state_id, token)];
-                if let Some(reduce) = entry.take() {
-                    eprintln!("shift/reduce conflict");
-                    eprintln!("shift: {:?}", TokenAction::Shift(*target));
-                    eprintln!("reduce: {reduce:?}");
-                }
-                *entry = Some(TokenAction::Shift(*target));
-            }
-
-            goto_table.add_state();
-            for (non_terminal, target) in non_terminal_transitions.iter().enumerate() {
-                goto_table[(state_id, non_terminal)] = *target;
-            }
-        }
+    fn kernel(&self, state: usize) -> Vec<ItemCore> {
+        self.lr0.kernel(state)
+    }
 
-        (token_table, eof_table, goto_table)
+    fn reductions(&self, state: usize) -> Vec<(usize, LookAhead)> {
+        self.reductions[state].clone()
     }
 }
 
@@ -404,30 +276,153 @@ impl From<SymbolicGrammar> for LalrAutomaton {
 
 impl Display for LalrAutomaton {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "SlrAutomaton:")?;
+        writeln!(f, "LalrAutomaton:")?;
         writeln!(f, "States:")?;
-        for (state_id, state) in self.states.iter().enumerate() {
+        for (state_id, reductions) in self.reductions.iter().enumerate() {
             writeln!(
                 f,
-                "{state_id}: {{{}}}",
-                state
-                    .kernel
+                "{state_id}: {{{}}}, reductions: {{{}}}",
+                self.kernel(state_id)
+                    .iter()
+                    .map(|item| self.item_string(item))
+                    .format(", "),
+                reductions
                     .iter()
-                    .map(|item| {
-                        let production = self.grammar.get_production(item.production_id).unwrap();
-                        let (before_marker, after_marker) =
-                            production.body().split_at(item.marker_position);
-                        format!(
-                            "{} -> {}·{}, {}",
-                            production.head(),
-                            before_marker.iter().format(" "),
-                            after_marker.iter().format(" "),
-                            item.lookahead_node.compute_lookahead(),
-                        )
-                    })
+                    .map(|(production_id, lookahead)| format!(
+                        "{} on {lookahead}",
+                        self.grammar().production_name(*production_id)
+                    ))
                     .format(", ")
             )?;
         }
-        write!(f, "{}", self.transitions)
+        write!(f, "{}", self.transitions())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::LalrAutomaton;
+    use crate::{
+        automaton::{Automaton, ItemCore},
+        lr1::Lr1Automaton,
+        symbolic_grammar::SymbolicGrammar,
+        test_grammars::{expressions, grammar, lr1_not_lalr},
+    };
+    use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+    type Lookaheads = BTreeMap<(usize, usize), (BTreeSet<usize>, bool)>;
+
+    /// Lookaheads of the reductions of each LALR state, by state and production
+    fn lalr_lookaheads(grammar: SymbolicGrammar) -> (Lookaheads, HashMap<Vec<ItemCore>, usize>) {
+        let automaton = LalrAutomaton::compute(grammar);
+        let mut lookaheads = Lookaheads::new();
+        let mut states = HashMap::new();
+        for state in 0..automaton.states_count() {
+            states.insert(automaton.kernel(state), state);
+            for (production_id, lookahead) in automaton.reductions(state) {
+                let entry = lookaheads.entry((state, production_id)).or_default();
+                entry.0.extend(lookahead.tokens);
+                entry.1 |= lookahead.can_eof_follow;
+            }
+        }
+        (lookaheads, states)
+    }
+
+    /// Canonical LR(1) lookaheads, merged over the states sharing a kernel: the definition of the
+    /// LALR(1) lookaheads
+    fn merged_lr1_lookaheads(
+        grammar: SymbolicGrammar,
+        lalr_states: &HashMap<Vec<ItemCore>, usize>,
+    ) -> Lookaheads {
+        let automaton = Lr1Automaton::compute(grammar);
+        let mut lookaheads = Lookaheads::new();
+        for state in 0..automaton.states_count() {
+            let lalr_state = lalr_states[&automaton.kernel(state)];
+            for (production_id, lookahead) in automaton.reductions(state) {
+                let entry = lookaheads.entry((lalr_state, production_id)).or_default();
+                entry.0.extend(lookahead.tokens);
+                entry.1 |= lookahead.can_eof_follow;
+            }
+        }
+        lookaheads
+    }
+
+    fn assert_lr1_lookaheads(make_grammar: fn() -> SymbolicGrammar) {
+        let (lalr, states) = lalr_lookaheads(make_grammar());
+        assert_eq!(lalr, merged_lr1_lookaheads(make_grammar(), &states));
+    }
+
+    #[test]
+    fn lalr_lookaheads_match_lr1_on_recursive_grammars() {
+        assert_lr1_lookaheads(expressions);
+        assert_lr1_lookaheads(lr1_not_lalr);
+        // `L` and `R` depend on each other through the includes relation
+        assert_lr1_lookaheads(|| {
+            grammar(
+                "S",
+                &[
+                    ("S", &["L", "eq", "R"]),
+                    ("S", &["R"]),
+                    ("L", &["star", "R"]),
+                    ("L", &["id"]),
+                    ("R", &["L"]),
+                ],
+            )
+        });
+    }
+
+    #[test]
+    fn lalr_lookaheads_match_lr1_through_nullable_symbols() {
+        // Lookaheads are read across empty `A` and `B`, and loop through the left recursion of `A`
+        assert_lr1_lookaheads(|| {
+            grammar(
+                "S",
+                &[
+                    ("S", &["A", "B", "c"]),
+                    ("S", &["B", "A", "d"]),
+                    ("A", &["A", "B", "a"]),
+                    ("A", &[]),
+                    ("B", &["b", "B"]),
+                    ("B", &[]),
+                ],
+            )
+        });
+        // Every symbol of the cycle `X -> Y`, `Y -> Z`, `Z -> X` is nullable
+        assert_lr1_lookaheads(|| {
+            grammar(
+                "S",
+                &[
+                    ("S", &["X", "S", "x"]),
+                    ("S", &["y"]),
+                    ("X", &["Y", "x"]),
+                    ("X", &["Y"]),
+                    ("Y", &["Z"]),
+                    ("Y", &["y", "Z"]),
+                    ("Z", &["X"]),
+                    ("Z", &[]),
+                ],
+            )
+        });
+    }
+
+    #[test]
+    fn lalr_lookaheads_match_lr1_on_nested_lists() {
+        assert_lr1_lookaheads(|| {
+            grammar(
+                "Value",
+                &[
+                    ("Value", &["lbrace", "Members", "rbrace"]),
+                    ("Value", &["lbracket", "Elements", "rbracket"]),
+                    ("Value", &["string"]),
+                    ("Members", &["Member", "comma", "Members"]),
+                    ("Members", &["Member"]),
+                    ("Members", &[]),
+                    ("Member", &["string", "colon", "Value"]),
+                    ("Elements", &["Value", "comma", "Elements"]),
+                    ("Elements", &["Value"]),
+                    ("Elements", &[]),
+                ],
+            )
+        });
     }
 }
@@ -1,6 +1,21 @@
+pub mod automaton;
 pub mod enriched_grammar;
 pub mod symbolic_grammar;
 pub mod parsing;
+pub mod lr0;
+pub mod slr;
 pub mod lalr;
+pub mod lr1;
+pub mod minimal_lr1;
+pub mod counterexample;
+pub mod report;
+pub mod dot;
+pub mod railroad;
+
+#[cfg(test)]
+mod test_grammars;
 
 pub use enriched_grammar::*;
+
+/// Version of the crate, telling apart tables generated by different releases
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,259 @@
+use crate::{
+    automaton::{Automaton, ItemCore, LookAhead},
+    parsing::tables::TransitionTables,
+    symbolic_grammar::{SymbolicGrammar, SymbolicSymbol},
+};
+use std::collections::HashMap;
+
+/// LR(0) items numbered production after production, so that item ids sort like the items and
+/// moving the marker adds one to the id. The accepting production comes last
+struct ItemIds {
+    items: Vec<ItemCore>,
+    /// Symbol after the marker of each item
+    pointed_symbols: Vec<Option<SymbolicSymbol>>,
+    /// Id of the item with the marker at the start, by production
+    first_items: Vec<usize>,
+}
+
+impl ItemIds {
+    fn new(grammar: &SymbolicGrammar) -> Self {
+        let mut ids = Self {
+            items: Vec::new(),
+            pointed_symbols: Vec::new(),
+            first_items: Vec::new(),
+        };
+        let accepting = grammar.get_production(usize::MAX).unwrap();
+        for production in grammar.productions().iter().chain([accepting]) {
+            ids.first_items.push(ids.items.len());
+            for marker_position in 0..=production.arity() {
+                ids.items.push(ItemCore {
+                    production_id: production.id(),
+                    marker_position,
+                });
+                ids.pointed_symbols
+                    .push(production.body().get(marker_position).cloned());
+            }
+        }
+        ids
+    }
+
+    fn first_item(&self, production_id: usize) -> usize {
+        if production_id == usize::MAX {
+            *self.first_items.last().unwrap()
+        } else {
+            self.first_items[production_id]
+        }
+    }
+}
+
+struct Lr0State {
+    /// Sorted item ids
+    kernel: Vec<usize>,
+    /// Productions reduced in the state: the completed kernel items and the empty productions of
+    /// the closure
+    reducing: Vec<usize>,
+}
+
+/// LR(0) automaton: the canonical collection of LR(0) item sets, reducing whatever the next token
+/// is. It has the states of the SLR(1) and LALR(1) automata, and is mostly useful for comparison
+pub struct Lr0Automaton {
+    grammar: SymbolicGrammar,
+    items: ItemIds,
+    states: Vec<Lr0State>,
+    transitions: TransitionTables,
+}
+
+impl Lr0Automaton {
+    pub fn compute(grammar: SymbolicGrammar) -> Self {
+        let mut automaton = Self {
+            items: ItemIds::new(&grammar),
+            grammar,
+            states: Vec::new(),
+            transitions: TransitionTables::new(),
+        };
+        automaton.populate();
+        automaton
+    }
+
+    fn populate(&mut self) {
+        let mut state_ids = HashMap::new();
+        let first_kernel = vec![self.items.first_item(usize::MAX)];
+        state_ids.insert(first_kernel.clone(), 0);
+        self.states.push(Lr0State {
+            kernel: first_kernel,
+            reducing: Vec::new(),
+        });
+
+        // Last state whose closure reached each non-terminal, to add its productions only once
+        let mut expanded = vec![usize::MAX; self.grammar.non_terminal_count()];
+        let mut token_kernels = vec![Vec::new(); self.grammar.token_count()];
+        let mut non_terminal_kernels = vec![Vec::new(); self.grammar.non_terminal_count()];
+        let mut state_id = 0;
+        while state_id < self.states.len() {
+            let mut closure = self.states[state_id].kernel.clone();
+            let mut position = 0;
+            while position < closure.len() {
+                if let Some(SymbolicSymbol::NonTerminal(non_terminal)) =
+                    &self.items.pointed_symbols[closure[position]]
+                    && expanded[*non_terminal] != state_id
+                {
+                    expanded[*non_terminal] = state_id;
+                    closure.extend(
+                        self.grammar
+                            .get_productions_with_head(*non_terminal)
+                            .into_iter()
+                            .map(|production| self.items.first_item(production.id())),
+                    );
+                }
+                position += 1;
+            }
+
+            let mut reducing = Vec::new();
+            for item in closure {
+                match &self.items.pointed_symbols[item] {
+                    Some(SymbolicSymbol::Token(token)) => token_kernels[*token].push(item + 1),
+                    Some(SymbolicSymbol::NonTerminal(non_terminal)) => {
+                        non_terminal_kernels[*non_terminal].push(item + 1)
+                    }
+                    None => reducing.push(self.items.items[item].production_id),
+                }
+            }
+            self.states[state_id].reducing = reducing;
+
+            let token_transitions = token_kernels
+                .iter_mut()
+                .map(|kernel| self.intern_state(&mut state_ids, std::mem::take(kernel)))
+                .collect();
+            let non_terminal_transitions = non_terminal_kernels
+                .iter_mut()
+                .map(|kernel| self.intern_state(&mut state_ids, std::mem::take(kernel)))
+                .collect();
+            self.transitions
+                .add_transitions(token_transitions, non_terminal_transitions);
+
+            state_id += 1;
+        }
+    }
+
+    fn intern_state(
+        &mut self,
+        state_ids: &mut HashMap<Vec<usize>, usize>,
+        mut kernel: Vec<usize>,
+    ) -> Option<usize> {
+        if kernel.is_empty() {
+            return None;
+        }
+        kernel.sort_unstable();
+        let next_id = self.states.len();
+        let state_id = *state_ids.entry(kernel.clone()).or_insert(next_id);
+        if state_id == next_id {
+            self.states.push(Lr0State {
+                kernel,
+                reducing: Vec::new(),
+            });
+        }
+        Some(state_id)
+    }
+
+    /// Productions reduced in the state, without any lookahead
+    pub fn reducing_productions(&self, state: usize) -> &[usize] {
+        &self.states[state].reducing
+    }
+}
+
+impl Automaton for Lr0Automaton {
+    fn grammar(&self) -> &SymbolicGrammar {
+        &self.grammar
+    }
+
+    fn transitions(&self) -> &TransitionTables {
+        &self.transitions
+    }
+
+    fn states_count(&self) -> usize {
+        self.states.len()
+    }
+
+    fn kernel(&self, state: usize) -> Vec<ItemCore> {
+        self.states[state]
+            .kernel
+            .iter()
+            .map(|item| self.items.items[*item])
+            .collect()
+    }
+
+    fn reductions(&self, state: usize) -> Vec<(usize, LookAhead)> {
+        self.reducing_productions(state)
+            .iter()
+            .map(|production_id| {
+                // Accepting only makes sense at the end of input
+                let lookahead = if *production_id == usize::MAX {
+                    LookAhead {
+                        tokens: Default::default(),
+                        can_eof_follow: true,
+                    }
+                } else {
+                    LookAhead {
+                        tokens: (0..self.grammar.token_count()).collect(),
+                        can_eof_follow: true,
+                    }
+                };
+                (*production_id, lookahead)
+            })
+            .collect()
+    }
+}
+
+impl From<SymbolicGrammar> for Lr0Automaton {
+    fn from(value: SymbolicGrammar) -> Self {
+        Self::compute(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lr0Automaton;
+    use crate::{
+        automaton::Automaton,
+        lalr::LalrAutomaton,
+        test_grammars::{accepts, expressions, grammar},
+    };
+
+    #[test]
+    fn lr0_has_the_lalr_states() {
+        let lalr_states = LalrAutomaton::compute(expressions()).states_count();
+        assert_eq!(
+            Lr0Automaton::compute(expressions()).states_count(),
+            lalr_states
+        );
+    }
+
+    #[test]
+    fn lr0_conflicts_on_expressions() {
+        let tables = Lr0Automaton::compute(expressions()).generate_tables();
+        assert!(!tables.conflicts.is_empty());
+        assert!(
+            tables
+                .conflicts
+                .iter()
+                .all(|conflict| conflict.is_shift_reduce())
+        );
+    }
+
+    #[test]
+    fn lr0_accepts_lr0_grammar() {
+        let automaton = Lr0Automaton::compute(grammar(
+            "S",
+            &[("S", &["lparen", "S", "rparen"]), ("S", &["x"])],
+        ));
+        let tables = automaton.generate_tables();
+        assert!(tables.conflicts.is_empty());
+        let grammar = automaton.grammar();
+        assert!(accepts(
+            grammar,
+            &tables,
+            &["lparen", "lparen", "x", "rparen", "rparen"]
+        ));
+        assert!(!accepts(grammar, &tables, &["lparen", "x"]));
+    }
+}
@@ -0,0 +1,279 @@
+use crate::{
+    automaton::{Automaton, ItemCore, LookAhead},
+    parsing::tables::TransitionTables,
+    symbolic_grammar::{SymbolicGrammar, SymbolicSymbol, SymbolicToken},
+};
+use itertools::Itertools;
+use std::collections::{BTreeSet, HashMap};
+
+#[derive(Clone, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct Lr1Item {
+    production_id: usize,
+    marker_position: usize,
+    /// `None` stands for the end of input
+    lookahead: Option<SymbolicToken>,
+}
+
+impl Lr1Item {
+    fn new(production_id: usize, lookahead: Option<SymbolicToken>) -> Self {
+        Self {
+            production_id,
+            marker_position: 0,
+            lookahead,
+        }
+    }
+
+    fn pointed_symbol<'a>(&self, grammar: &'a SymbolicGrammar) -> Option<&'a SymbolicSymbol> {
+        grammar
+            .get_production(self.production_id)
+            .expect("production not found")
+            .body()
+            .get(self.marker_position)
+    }
+
+    fn moved_marker(&self) -> Self {
+        Self {
+            marker_position: self.marker_position + 1,
+            ..self.clone()
+        }
+    }
+
+    fn is_reducing(&self, grammar: &SymbolicGrammar) -> bool {
+        self.marker_position == grammar.get_production(self.production_id).unwrap().arity()
+    }
+}
+
+struct Lr1State {
+    kernel: BTreeSet<Lr1Item>,
+    epsilon_items: BTreeSet<Lr1Item>,
+}
+
+/// Canonical LR(1) automaton: states are never merged, so it accepts every LR(1) grammar at the
+/// cost of (many) more states than the LALR(1) one
+pub struct Lr1Automaton {
+    grammar: SymbolicGrammar,
+    states: Vec<Lr1State>,
+    transitions: TransitionTables,
+}
+
+impl Lr1Automaton {
+    pub fn compute(grammar: SymbolicGrammar) -> Self {
+        let mut automaton = Self {
+            grammar,
+            states: Vec::new(),
+            transitions: TransitionTables::new(),
+        };
+        automaton.populate();
+        automaton
+    }
+
+    fn populate(&mut self) {
+        let mut state_ids = HashMap::new();
+        let first_kernel = BTreeSet::from([Lr1Item::new(usize::MAX, None)]);
+        state_ids.insert(first_kernel.clone(), 0);
+        self.states.push(Lr1State {
+            kernel: first_kernel,
+            epsilon_items: BTreeSet::new(),
+        });
+
+        let mut state_id = 0;
+        while state_id < self.states.len() {
+            let closure = self.closure(&self.states[state_id].kernel);
+            self.states[state_id].epsilon_items = closure
+                .iter()
+                .filter(|item| {
+                    self.grammar
+                        .get_production(item.production_id)
+                        .unwrap()
+                        .arity()
+                        == 0
+                })
+                .cloned()
+                .collect();
+
+            let mut token_kernels = vec![BTreeSet::new(); self.grammar.token_count()];
+            let mut non_terminal_kernels = vec![BTreeSet::new(); self.grammar.non_terminal_count()];
+            for item in closure.iter() {
+                match item.pointed_symbol(&self.grammar) {
+                    Some(SymbolicSymbol::Token(token)) => {
+                        token_kernels[*token].insert(item.moved_marker());
+                    }
+                    Some(SymbolicSymbol::NonTerminal(non_terminal)) => {
+                        non_terminal_kernels[*non_terminal].insert(item.moved_marker());
+                    }
+                    None => {}
+                }
+            }
+
+            let token_transitions = token_kernels
+                .into_iter()
+                .map(|kernel| self.intern_state(&mut state_ids, kernel))
+                .collect();
+            let non_terminal_transitions = non_terminal_kernels
+                .into_iter()
+                .map(|kernel| self.intern_state(&mut state_ids, kernel))
+                .collect();
+            self.transitions
+                .add_transitions(token_transitions, non_terminal_transitions);
+
+            state_id += 1;
+        }
+    }
+
+    fn intern_state(
+        &mut self,
+        state_ids: &mut HashMap<BTreeSet<Lr1Item>, usize>,
+        kernel: BTreeSet<Lr1Item>,
+    ) -> Option<usize> {
+        if kernel.is_empty() {
+            return None;
+        }
+        let next_id = self.states.len();
+        let state_id = *state_ids.entry(kernel.clone()).or_insert(next_id);
+        if state_id == next_id {
+            self.states.push(Lr1State {
+                kernel,
+                epsilon_items: BTreeSet::new(),
+            });
+        }
+        Some(state_id)
+    }
+
+    fn closure(&self, kernel: &BTreeSet<Lr1Item>) -> BTreeSet<Lr1Item> {
+        let mut stack = kernel.iter().cloned().collect_vec();
+        let mut res = kernel.clone();
+
+        while let Some(item) = stack.pop() {
+            let Some(SymbolicSymbol::NonTerminal(non_terminal)) =
+                item.pointed_symbol(&self.grammar)
+            else {
+                continue;
+            };
+
+            let beta = &self
+                .grammar
+                .get_production(item.production_id)
+                .unwrap()
+                .body()[item.marker_position + 1..];
+            let firsts = self.grammar.first_of_sequence(beta);
+            let lookaheads = firsts
+                .tokens
+                .into_iter()
+                .map(Some)
+                .chain(firsts.nullable.then_some(item.lookahead))
+                .collect_vec();
+
+            for prod in self.grammar.get_productions_with_head(*non_terminal) {
+                for lookahead in lookaheads.iter() {
+                    let new_item = Lr1Item::new(prod.id(), *lookahead);
+                    if !res.contains(&new_item) {
+                        stack.push(new_item.clone());
+                        res.insert(new_item);
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+impl Automaton for Lr1Automaton {
+    fn grammar(&self) -> &SymbolicGrammar {
+        &self.grammar
+    }
+
+    fn transitions(&self) -> &TransitionTables {
+        &self.transitions
+    }
+
+    fn states_count(&self) -> usize {
+        self.states.len()
+    }
+
+    fn kernel(&self, state: usize) -> Vec<ItemCore> {
+        self.states[state]
+            .kernel
+            .iter()
+            .map(|item| ItemCore {
+                production_id: item.production_id,
+                marker_position: item.marker_position,
+            })
+            .dedup()
+            .collect()
+    }
+
+    fn reductions(&self, state: usize) -> Vec<(usize, LookAhead)> {
+        let state = &self.states[state];
+        let mut reductions: HashMap<usize, LookAhead> = HashMap::new();
+        for item in state
+            .kernel
+            .iter()
+            .filter(|item| item.is_reducing(&self.grammar))
+            .chain(state.epsilon_items.iter())
+        {
+            let lookahead = reductions.entry(item.production_id).or_default();
+            match item.lookahead {
+                Some(token) => {
+                    lookahead.tokens.insert(token);
+                }
+                None => lookahead.can_eof_follow = true,
+            }
+        }
+        reductions.into_iter().collect()
+    }
+}
+
+impl From<SymbolicGrammar> for Lr1Automaton {
+    fn from(value: SymbolicGrammar) -> Self {
+        Self::compute(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lr1Automaton;
+    use crate::{
+        automaton::Automaton,
+        lalr::LalrAutomaton,
+        test_grammars::{accepts, lr1_not_lalr},
+    };
+
+    #[test]
+    fn lalr_merging_causes_reduce_reduce_conflicts() {
+        let tables = LalrAutomaton::compute(lr1_not_lalr()).generate_tables();
+        assert!(!tables.conflicts.is_empty());
+        assert!(
+            tables
+                .conflicts
+                .iter()
+                .all(|conflict| !conflict.is_shift_reduce())
+        );
+    }
+
+    #[test]
+    fn lr1_accepts_grammar_that_is_not_lalr() {
+        let automaton = Lr1Automaton::compute(lr1_not_lalr());
+        let tables = automaton.generate_tables();
+        assert!(tables.conflicts.is_empty());
+
+        let grammar = automaton.grammar();
+        for input in [
+            ["a", "e", "c"],
+            ["a", "e", "d"],
+            ["b", "e", "c"],
+            ["b", "e", "d"],
+        ] {
+            assert!(accepts(grammar, &tables, &input), "{input:?} rejected");
+        }
+        for input in [&["a", "e"][..], &["a", "e", "c", "c"], &["e", "c"]] {
+            assert!(!accepts(grammar, &tables, input), "{input:?} accepted");
+        }
+    }
+
+    #[test]
+    fn lr1_splits_the_merged_lalr_state() {
+        let lalr_states = LalrAutomaton::compute(lr1_not_lalr()).states_count();
+        let lr1_states = Lr1Automaton::compute(lr1_not_lalr()).states_count();
+        assert_eq!(lr1_states, lalr_states + 1);
+    }
+}
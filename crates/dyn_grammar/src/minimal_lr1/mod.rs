@@ -0,0 +1,371 @@
+use crate::{
+    automaton::{Automaton, ItemCore, LookAhead},
+    lalr::TokenSet,
+    parsing::tables::TransitionTables,
+    symbolic_grammar::{SymbolicGrammar, SymbolicSymbol},
+};
+use itertools::Itertools;
+use std::collections::{BTreeMap, HashMap, VecDeque, btree_map::Entry};
+
+/// Items of a state with the lookahead of each, the end of input being the token after the last
+type Items = BTreeMap<ItemCore, TokenSet>;
+
+struct MinimalLr1State {
+    kernel: Vec<ItemCore>,
+    reductions: Vec<(usize, LookAhead)>,
+}
+
+/// Minimal LR(1) automaton, built with Pager's practical general method: LR(1) states are built
+/// one at a time, and a new state is merged into an existing state with the same kernel when they
+/// are weakly compatible, that is when the merge can't create a reduce/reduce conflict that none
+/// of them has. It is as small as the LALR(1) automaton for LALR(1) grammars and accepts every
+/// LR(1) grammar, without building the canonical LR(1) automaton
+pub struct MinimalLr1Automaton {
+    grammar: SymbolicGrammar,
+    states: Vec<MinimalLr1State>,
+    transitions: TransitionTables,
+}
+
+/// Builds the states, remembering the first set of what follows the marker of each item
+struct Builder<'a> {
+    grammar: &'a SymbolicGrammar,
+    eof: usize,
+    firsts: HashMap<ItemCore, (TokenSet, bool)>,
+}
+
+impl Builder<'_> {
+    /// Tokens that can start what follows the non-terminal after the marker, and whether it can
+    /// be empty
+    fn firsts(&mut self, item: ItemCore) -> &(TokenSet, bool) {
+        self.firsts.entry(item).or_insert_with(|| {
+            let production = self.grammar.get_production(item.production_id).unwrap();
+            let firsts = self
+                .grammar
+                .first_of_sequence(&production.body()[item.marker_position + 1..]);
+            let mut tokens = TokenSet::new(self.eof + 1);
+            for token in firsts.tokens {
+                tokens.insert(token);
+            }
+            (tokens, firsts.nullable)
+        })
+    }
+
+    /// Closes a kernel, spreading the lookaheads to the items it adds
+    fn closure(&mut self, kernel: &[ItemCore], lookaheads: &[TokenSet]) -> Items {
+        let mut items: Items = kernel
+            .iter()
+            .copied()
+            .zip(lookaheads.iter().cloned())
+            .collect();
+        let mut stack = kernel.to_vec();
+        while let Some(item) = stack.pop() {
+            let production = self.grammar.get_production(item.production_id).unwrap();
+            let Some(SymbolicSymbol::NonTerminal(non_terminal)) =
+                production.body().get(item.marker_position)
+            else {
+                continue;
+            };
+            let (tokens, nullable) = self.firsts(item);
+            let mut lookahead = tokens.clone();
+            if *nullable {
+                lookahead.union_with(&items[&item]);
+            }
+            for production in self.grammar.get_productions_with_head(*non_terminal) {
+                let added = ItemCore {
+                    production_id: production.id(),
+                    marker_position: 0,
+                };
+                match items.entry(added) {
+                    Entry::Vacant(entry) => {
+                        entry.insert(lookahead.clone());
+                        stack.push(added);
+                    }
+                    Entry::Occupied(mut entry) => {
+                        if entry.get_mut().union_with(&lookahead) {
+                            stack.push(added);
+                        }
+                    }
+                }
+            }
+        }
+        items
+    }
+
+    /// Kernels reached from a state, keyed by the symbol read, tokens first then non-terminals
+    fn successors(
+        &mut self,
+        kernel: &[ItemCore],
+        lookaheads: &[TokenSet],
+    ) -> BTreeMap<usize, Items> {
+        let token_count = self.grammar.token_count();
+        let mut successors: BTreeMap<usize, Items> = BTreeMap::new();
+        for (item, lookahead) in self.closure(kernel, lookaheads) {
+            let production = self.grammar.get_production(item.production_id).unwrap();
+            let symbol = match production.body().get(item.marker_position) {
+                Some(SymbolicSymbol::Token(token)) => *token,
+                Some(SymbolicSymbol::NonTerminal(non_terminal)) => token_count + non_terminal,
+                None => continue,
+            };
+            let moved = ItemCore {
+                production_id: item.production_id,
+                marker_position: item.marker_position + 1,
+            };
+            match successors.entry(symbol).or_default().entry(moved) {
+                Entry::Vacant(entry) => {
+                    entry.insert(lookahead);
+                }
+                Entry::Occupied(mut entry) => {
+                    entry.get_mut().union_with(&lookahead);
+                }
+            }
+        }
+        successors
+    }
+
+    fn reductions(
+        &mut self,
+        kernel: &[ItemCore],
+        lookaheads: &[TokenSet],
+    ) -> Vec<(usize, LookAhead)> {
+        self.closure(kernel, lookaheads)
+            .into_iter()
+            .filter(|(item, _)| {
+                let production = self.grammar.get_production(item.production_id).unwrap();
+                item.marker_position == production.arity()
+            })
+            .map(|(item, lookahead)| (item.production_id, lookahead.to_lookahead(self.eof)))
+            .collect()
+    }
+}
+
+/// Pager's weak compatibility: merging the lookaheads of two states with the same kernel can only
+/// make two items share a lookahead if they already share one in either state
+fn weakly_compatible(left: &[TokenSet], right: &[TokenSet]) -> bool {
+    (0..left.len()).tuple_combinations().all(|(i, j)| {
+        !(left[i].intersects(&right[j]) || left[j].intersects(&right[i]))
+            || left[i].intersects(&left[j])
+            || right[i].intersects(&right[j])
+    })
+}
+
+impl MinimalLr1Automaton {
+    pub fn compute(grammar: SymbolicGrammar) -> Self {
+        let token_count = grammar.token_count();
+        let symbol_count = token_count + grammar.non_terminal_count();
+        let mut builder = Builder {
+            grammar: &grammar,
+            eof: token_count,
+            firsts: HashMap::new(),
+        };
+
+        let start = ItemCore {
+            production_id: usize::MAX,
+            marker_position: 0,
+        };
+        let mut end_of_input = TokenSet::new(token_count + 1);
+        end_of_input.insert(token_count);
+        let mut kernels = vec![vec![start]];
+        let mut lookaheads = vec![vec![end_of_input]];
+        let mut successors = vec![vec![None; symbol_count]];
+        let mut states_by_kernel = HashMap::from([(vec![start], vec![0])]);
+
+        // A state is processed again whenever a merge adds lookaheads to it, so that they reach
+        // its successors
+        let mut queue = VecDeque::from([0]);
+        let mut queued = vec![true];
+        while let Some(state) = queue.pop_front() {
+            queued[state] = false;
+            for (symbol, items) in builder.successors(&kernels[state], &lookaheads[state]) {
+                let (kernel, new_lookaheads): (Vec<_>, Vec<_>) = items.into_iter().unzip();
+                // The successor the state had before its lookaheads grew is tried first, so that
+                // the automaton settles on the states it already has
+                let candidates = successors[state][symbol]
+                    .into_iter()
+                    .chain(states_by_kernel.get(&kernel).into_iter().flatten().copied())
+                    .unique()
+                    .collect_vec();
+                let covering = candidates.iter().copied().find(|candidate| {
+                    new_lookaheads
+                        .iter()
+                        .zip(&lookaheads[*candidate])
+                        .all(|(new, old)| new.is_subset(old))
+                });
+                let target =
+                    if let Some(target) = covering {
+                        target
+                    } else if let Some(target) = candidates.iter().copied().find(|candidate| {
+                        weakly_compatible(&lookaheads[*candidate], &new_lookaheads)
+                    }) {
+                        for (old, new) in lookaheads[target].iter_mut().zip(&new_lookaheads) {
+                            old.union_with(new);
+                        }
+                        if !queued[target] {
+                            queued[target] = true;
+                            queue.push_back(target);
+                        }
+                        target
+                    } else {
+                        let target = kernels.len();
+                        states_by_kernel
+                            .entry(kernel.clone())
+                            .or_default()
+                            .push(target);
+                        kernels.push(kernel);
+                        lookaheads.push(new_lookaheads);
+                        successors.push(vec![None; symbol_count]);
+                        queued.push(true);
+                        queue.push_back(target);
+                        target
+                    };
+                successors[state][symbol] = Some(target);
+            }
+        }
+
+        // A state whose lookaheads grew may have moved to another successor, renumber the states
+        // still reachable in breadth-first order
+        let mut state_ids = HashMap::from([(0, 0)]);
+        let mut order = vec![0];
+        let mut next = 0;
+        while let Some(state) = order.get(next).copied() {
+            next += 1;
+            for target in successors[state].iter().flatten() {
+                let next_id = state_ids.len();
+                state_ids.entry(*target).or_insert_with(|| {
+                    order.push(*target);
+                    next_id
+                });
+            }
+        }
+
+        let mut states = Vec::new();
+        let mut transitions = TransitionTables::new();
+        for state in order {
+            states.push(MinimalLr1State {
+                reductions: builder.reductions(&kernels[state], &lookaheads[state]),
+                kernel: kernels[state].clone(),
+            });
+            let mut targets = successors[state]
+                .iter()
+                .map(|target| target.map(|target| state_ids[&target]))
+                .collect_vec();
+            let non_terminal_targets = targets.split_off(token_count);
+            transitions.add_transitions(targets, non_terminal_targets);
+        }
+
+        Self {
+            grammar,
+            states,
+            transitions,
+        }
+    }
+}
+
+impl Automaton for MinimalLr1Automaton {
+    fn grammar(&self) -> &SymbolicGrammar {
+        &self.grammar
+    }
+
+    fn transitions(&self) -> &TransitionTables {
+        &self.transitions
+    }
+
+    fn states_count(&self) -> usize {
+        self.states.len()
+    }
+
+    fn kernel(&self, state: usize) -> Vec<ItemCore> {
+        self.states[state].kernel.clone()
+    }
+
+    fn reductions(&self, state: usize) -> Vec<(usize, LookAhead)> {
+        self.states[state].reductions.clone()
+    }
+}
+
+impl From<SymbolicGrammar> for MinimalLr1Automaton {
+    fn from(value: SymbolicGrammar) -> Self {
+        Self::compute(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MinimalLr1Automaton;
+    use crate::{
+        automaton::Automaton,
+        lalr::LalrAutomaton,
+        lr1::Lr1Automaton,
+        test_grammars::{accepts, expressions, grammar, lr1_not_lalr},
+    };
+
+    #[test]
+    fn minimal_lr1_is_as_small_as_lalr_on_lalr_grammars() {
+        let lalr_states = LalrAutomaton::compute(expressions()).states_count();
+        let lr1_states = Lr1Automaton::compute(expressions()).states_count();
+        let automaton = MinimalLr1Automaton::compute(expressions());
+        assert!(lr1_states > lalr_states);
+        assert_eq!(automaton.states_count(), lalr_states);
+        assert!(automaton.generate_tables().conflicts.is_empty());
+    }
+
+    #[test]
+    fn minimal_lr1_accepts_grammar_that_is_not_lalr() {
+        let automaton = MinimalLr1Automaton::compute(lr1_not_lalr());
+        let tables = automaton.generate_tables();
+        assert!(tables.conflicts.is_empty());
+
+        let grammar = automaton.grammar();
+        for input in [
+            ["a", "e", "c"],
+            ["a", "e", "d"],
+            ["b", "e", "c"],
+            ["b", "e", "d"],
+        ] {
+            assert!(accepts(grammar, &tables, &input), "{input:?} rejected");
+        }
+        for input in [&["a", "e"][..], &["a", "e", "c", "c"], &["e", "c"]] {
+            assert!(!accepts(grammar, &tables, input), "{input:?} accepted");
+        }
+    }
+
+    #[test]
+    fn minimal_lr1_only_splits_the_conflicting_states() {
+        // Expressions in the LR(1)-only contexts: LR(1) duplicates every expression state for
+        // each lookahead, only the `e` state needs to be split
+        let mixed = || {
+            grammar(
+                "S",
+                &[
+                    ("S", &["a", "E", "c"]),
+                    ("S", &["a", "F", "d"]),
+                    ("S", &["b", "F", "c"]),
+                    ("S", &["b", "E", "d"]),
+                    ("S", &["x", "X", "c"]),
+                    ("S", &["y", "X", "d"]),
+                    ("E", &["e"]),
+                    ("F", &["e"]),
+                    ("X", &["X", "plus", "T"]),
+                    ("X", &["T"]),
+                    ("T", &["lparen", "X", "rparen"]),
+                    ("T", &["id"]),
+                ],
+            )
+        };
+        let lalr_states = LalrAutomaton::compute(mixed()).states_count();
+        let lr1_states = Lr1Automaton::compute(mixed()).states_count();
+        let automaton = MinimalLr1Automaton::compute(mixed());
+        assert_eq!(automaton.states_count(), lalr_states + 1);
+        assert!(automaton.states_count() < lr1_states);
+
+        let tables = automaton.generate_tables();
+        assert!(tables.conflicts.is_empty());
+        let grammar = automaton.grammar();
+        assert!(accepts(
+            grammar,
+            &tables,
+            &["x", "lparen", "id", "plus", "id", "rparen", "c"]
+        ));
+        assert!(accepts(grammar, &tables, &["y", "id", "d"]));
+        assert!(!accepts(grammar, &tables, &["y", "id", "c"]));
+    }
+}
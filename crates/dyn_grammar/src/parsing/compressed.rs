@@ -0,0 +1,342 @@
+use std::collections::HashMap;
+
+use crate::parsing::{
+    action::{EofAction, TokenAction},
+    tables::ParsingTables,
+};
+use itertools::Itertools;
+
+/// Entry of the compressed action table, where the end of input is one more column after the
+/// tokens
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum CompressedAction {
+    Shift(usize),
+    Reduce(usize),
+    Accept,
+}
+
+/// Rows of a sparse table overlapped in a single vector, the comb vector: the entry of `row` at
+/// `column` lives at `base[row] + column`, and belongs to the row only if its check is `row`
+#[derive(Debug)]
+pub struct Comb<T> {
+    pub base: Vec<usize>,
+    /// Owning row and value of each slot, `None` for the slots no row uses
+    pub slots: Vec<Option<(usize, T)>>,
+}
+
+impl<T: Copy> Comb<T> {
+    /// Packs the rows first fit, the fullest first since they are the hardest to place
+    fn pack(rows: &[Vec<(usize, T)>]) -> Self {
+        let mut base = vec![0; rows.len()];
+        let mut slots: Vec<Option<(usize, T)>> = Vec::new();
+        for row in (0..rows.len()).sorted_by_key(|row| std::cmp::Reverse(rows[*row].len())) {
+            if rows[row].is_empty() {
+                continue;
+            }
+            let offset = (0..)
+                .find(|offset| {
+                    rows[row].iter().all(|(column, _)| {
+                        slots.get(offset + column).is_none_or(|slot| slot.is_none())
+                    })
+                })
+                .expect("a free offset always exists past the end");
+            for (column, value) in rows[row].iter() {
+                if slots.len() <= offset + column {
+                    slots.resize(offset + column + 1, None);
+                }
+                slots[offset + column] = Some((row, *value));
+            }
+            base[row] = offset;
+        }
+        Self { base, slots }
+    }
+
+    pub fn get(&self, row: usize, column: usize) -> Option<T> {
+        match self.slots.get(self.base[row] + column)? {
+            Some((owner, value)) if *owner == row => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+/// A sparse table whose identical rows are shared between states, each row having a default
+/// entry for the columns it leaves empty
+#[derive(Debug)]
+pub struct CompressedTable<T> {
+    /// Row of each state
+    pub rows: Vec<usize>,
+    pub defaults: Vec<Option<T>>,
+    pub comb: Comb<T>,
+}
+
+impl<T: Copy + Eq + std::hash::Hash> CompressedTable<T> {
+    /// Compresses the dense rows of the states. With `use_defaults`, the most frequent entry of
+    /// each row that `default` accepts is taken out of it and becomes its default
+    fn compress(
+        dense: Vec<Vec<Option<T>>>,
+        use_defaults: bool,
+        default: impl Fn(&T) -> bool,
+    ) -> Self {
+        let mut row_ids = HashMap::new();
+        let mut rows = Vec::new();
+        let mut defaults = Vec::new();
+        let mut sparse_rows = Vec::new();
+        for dense_row in dense {
+            // ties go to the entry found first, for the tables to be the same at every build
+            let row_default = dense_row
+                .iter()
+                .flatten()
+                .filter(|entry| use_defaults && default(entry))
+                .unique()
+                .rev()
+                .max_by_key(|entry| {
+                    dense_row
+                        .iter()
+                        .filter(|e| e.as_ref() == Some(*entry))
+                        .count()
+                })
+                .copied();
+            let sparse_row = dense_row
+                .iter()
+                .enumerate()
+                .filter_map(|(column, entry)| entry.map(|entry| (column, entry)))
+                .filter(|(_, entry)| Some(*entry) != row_default)
+                .collect_vec();
+            let row = *row_ids
+                .entry((sparse_row.clone(), row_default))
+                .or_insert_with(|| {
+                    defaults.push(row_default);
+                    sparse_rows.push(sparse_row);
+                    sparse_rows.len() - 1
+                });
+            rows.push(row);
+        }
+        Self {
+            rows,
+            defaults,
+            comb: Comb::pack(&sparse_rows),
+        }
+    }
+
+    pub fn get(&self, state: usize, column: usize) -> Option<T> {
+        let row = self.rows[state];
+        self.comb.get(row, column).or(self.defaults[row])
+    }
+
+    /// Number of integers the table takes: the row of each state, the default and base of each
+    /// row, and the check and value of each slot
+    pub fn size(&self) -> usize {
+        self.rows.len() + 2 * self.defaults.len() + 2 * self.comb.slots.len()
+    }
+}
+
+/// Parse tables in the compressed encoding: token and end of input actions in one table with a
+/// default reduction per row, and gotos in another with a default target per row
+#[derive(Debug)]
+pub struct CompressedTables {
+    pub tokens_count: usize,
+    pub actions: CompressedTable<CompressedAction>,
+    pub gotos: CompressedTable<usize>,
+}
+
+impl CompressedTables {
+    /// Compresses the tables. Default reductions make the parser reduce on some lookaheads that
+    /// are errors, which are then detected before the next shift, so the parser stays correct:
+    /// they are left out with `default_reductions = false`, for parsers that follow every action
+    pub fn compress(tables: &ParsingTables, default_reductions: bool) -> Self {
+        let tokens_count = tables.token_table.table.first().map_or(0, |row| row.len());
+        let actions = tables
+            .token_table
+            .table
+            .iter()
+            .zip(tables.eof_table.table.iter())
+            .map(|(token_row, eof_action)| {
+                token_row
+                    .iter()
+                    .map(|action| {
+                        action.as_ref().map(|action| match action {
+                            TokenAction::Shift(target) => CompressedAction::Shift(*target),
+                            TokenAction::Reduce(production) => {
+                                CompressedAction::Reduce(*production)
+                            }
+                        })
+                    })
+                    .chain(std::iter::once(eof_action.as_ref().map(
+                        |action| match action {
+                            EofAction::Reduce(production) => CompressedAction::Reduce(*production),
+                            EofAction::Accept => CompressedAction::Accept,
+                        },
+                    )))
+                    .collect_vec()
+            })
+            .collect_vec();
+        Self {
+            tokens_count,
+            actions: CompressedTable::compress(actions, default_reductions, |action| {
+                matches!(action, CompressedAction::Reduce(_))
+            }),
+            // a goto is only looked up after a reduction that leads to it, so any target can be
+            // the default
+            gotos: CompressedTable::compress(tables.non_terminal_table.table.clone(), true, |_| {
+                true
+            }),
+        }
+    }
+
+    pub fn token_action(&self, state: usize, token: usize) -> Option<TokenAction> {
+        match self.actions.get(state, token)? {
+            CompressedAction::Shift(target) => Some(TokenAction::Shift(target)),
+            CompressedAction::Reduce(production) => Some(TokenAction::Reduce(production)),
+            CompressedAction::Accept => None,
+        }
+    }
+
+    pub fn eof_action(&self, state: usize) -> Option<EofAction> {
+        match self.actions.get(state, self.tokens_count)? {
+            CompressedAction::Reduce(production) => Some(EofAction::Reduce(production)),
+            CompressedAction::Accept => Some(EofAction::Accept),
+            CompressedAction::Shift(_) => None,
+        }
+    }
+
+    pub fn goto(&self, state: usize, non_terminal: usize) -> Option<usize> {
+        self.gotos.get(state, non_terminal)
+    }
+
+    /// Number of integers the tables take
+    pub fn size(&self) -> usize {
+        self.actions.size() + self.gotos.size()
+    }
+
+    /// Number of entries of the uncompressed tables, one per state and symbol
+    pub fn dense_size(tables: &ParsingTables) -> usize {
+        let (tokens_and_eof, states) = tables.token_table.dimensions();
+        let (non_terminals, _) = tables.non_terminal_table.dimensions();
+        states * (tokens_and_eof + non_terminals)
+    }
+
+    /// Number of non-empty entries of the uncompressed tables, one match arm each in the match
+    /// encoding
+    pub fn match_arms(tables: &ParsingTables) -> usize {
+        let token_entries = tables.token_table.table.iter().flatten().flatten().count();
+        let eof_entries = tables.eof_table.table.iter().flatten().count();
+        let goto_entries = tables
+            .non_terminal_table
+            .table
+            .iter()
+            .flatten()
+            .flatten()
+            .count();
+        token_entries + eof_entries + goto_entries
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompressedTables;
+    use crate::{
+        automaton::{Algorithm, Automaton},
+        lalr::LalrAutomaton,
+        parsing::action::TokenAction,
+        symbolic_grammar::SymbolicGrammar,
+        test_grammars::{enriched_grammar, grammar},
+    };
+    use std::rc::Rc;
+
+    fn arithmetic() -> SymbolicGrammar {
+        grammar(
+            "E",
+            &[
+                ("E", &["E", "plus", "T"]),
+                ("E", &["T"]),
+                ("T", &["T", "times", "F"]),
+                ("T", &["F"]),
+                ("F", &["lpar", "E", "rpar"]),
+                ("F", &["id"]),
+            ],
+        )
+    }
+
+    #[test]
+    fn entries_without_default_are_unchanged() {
+        let tables = LalrAutomaton::compute(arithmetic()).generate_tables();
+        let compressed = CompressedTables::compress(&tables, false);
+        for (state, row) in tables.token_table.table.iter().enumerate() {
+            for (token, action) in row.iter().enumerate() {
+                assert_eq!(&compressed.token_action(state, token), action);
+            }
+            assert_eq!(compressed.eof_action(state), tables.eof_table[state]);
+        }
+    }
+
+    #[test]
+    fn default_reductions_only_fill_error_entries() {
+        let tables = LalrAutomaton::compute(arithmetic()).generate_tables();
+        let compressed = CompressedTables::compress(&tables, true);
+        for (state, row) in tables.token_table.table.iter().enumerate() {
+            for (token, action) in row.iter().enumerate() {
+                match action {
+                    Some(action) => {
+                        assert_eq!(compressed.token_action(state, token).as_ref(), Some(action))
+                    }
+                    None => assert!(!matches!(
+                        compressed.token_action(state, token),
+                        Some(TokenAction::Shift(_))
+                    )),
+                }
+            }
+            if tables.eof_table[state].is_some() {
+                assert_eq!(compressed.eof_action(state), tables.eof_table[state]);
+            }
+            for (non_terminal, target) in tables.non_terminal_table.table[state].iter().enumerate()
+            {
+                if target.is_some() {
+                    assert_eq!(&compressed.goto(state, non_terminal), target);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn identical_rows_are_shared() {
+        let tables = LalrAutomaton::compute(arithmetic()).generate_tables();
+        let compressed = CompressedTables::compress(&tables, true);
+        // the states reducing a single production on every lookahead are all the empty row
+        let rows = compressed.actions.defaults.len();
+        assert!(rows < compressed.actions.rows.len());
+        assert!(compressed.size() < CompressedTables::dense_size(&tables));
+    }
+
+    #[test]
+    fn every_algorithm_compresses_losslessly() {
+        let grammar = Rc::new(enriched_grammar(
+            "S",
+            &[
+                ("P1", "S", &["a", "A", "d"]),
+                ("P2", "S", &["b", "B", "d"]),
+                ("P3", "S", &["a", "B", "e"]),
+                ("P4", "S", &["b", "A", "e"]),
+                ("P5", "A", &["c"]),
+                ("P6", "B", &["c"]),
+            ],
+        ));
+        for algorithm in [Algorithm::Lalr, Algorithm::Lr1, Algorithm::MinimalLr1] {
+            let tables = algorithm
+                .compute(SymbolicGrammar::from(grammar.clone()))
+                .generate_tables();
+            let compressed = CompressedTables::compress(&tables, false);
+            for (state, row) in tables.token_table.table.iter().enumerate() {
+                for (token, action) in row.iter().enumerate() {
+                    assert_eq!(&compressed.token_action(state, token), action);
+                }
+                for (non_terminal, target) in
+                    tables.non_terminal_table.table[state].iter().enumerate()
+                {
+                    if target.is_some() {
+                        assert_eq!(&compressed.goto(state, non_terminal), target);
+                    }
+                }
+            }
+        }
+    }
+}
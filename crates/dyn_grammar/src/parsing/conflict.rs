@@ -0,0 +1,50 @@
+use crate::symbolic_grammar::SymbolicToken;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConflictLookahead {
+    Token(SymbolicToken),
+    Eof,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// `shift` is the target state, `reduce` the production that would be reduced instead
+    ShiftReduce { shift: usize, reduce: usize },
+    /// `reduce` is the production kept in the table, `other_reduce` the discarded one
+    ReduceReduce { reduce: usize, other_reduce: usize },
+}
+
+/// Two actions competing for the same table entry that precedence and associativity could not
+/// decide between
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Conflict {
+    state: usize,
+    lookahead: ConflictLookahead,
+    kind: ConflictKind,
+}
+
+impl Conflict {
+    pub fn new(state: usize, lookahead: ConflictLookahead, kind: ConflictKind) -> Self {
+        Self {
+            state,
+            lookahead,
+            kind,
+        }
+    }
+
+    pub fn state(&self) -> usize {
+        self.state
+    }
+
+    pub fn lookahead(&self) -> &ConflictLookahead {
+        &self.lookahead
+    }
+
+    pub fn kind(&self) -> &ConflictKind {
+        &self.kind
+    }
+
+    pub fn is_shift_reduce(&self) -> bool {
+        matches!(self.kind, ConflictKind::ShiftReduce { .. })
+    }
+}
@@ -1,2 +1,5 @@
 pub mod action;
+pub mod compressed;
+pub mod conflict;
 pub mod tables;
+pub mod unit_productions;
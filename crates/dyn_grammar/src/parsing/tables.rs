@@ -4,8 +4,9 @@ use std::{
 };
 
 use crate::{
-    non_terminal, parsing::action::{EofAction, TokenAction}, symbolic_grammar::{SymbolicNonTerminal, SymbolicSymbol, SymbolicToken}
+    non_terminal, parsing::{action::{EofAction, TokenAction}, conflict::{Conflict, ConflictKind, ConflictLookahead}}, symbolic_grammar::{SymbolicNonTerminal, SymbolicSymbol, SymbolicToken}
 };
+use itertools::Itertools;
 
 #[derive(Debug)]
 pub struct TokenTable {
@@ -169,6 +170,16 @@ impl Display for NonTerminalTable {
     }
 }
 
+#[derive(Debug)]
+pub struct ParsingTables {
+    pub token_table: TokenTable,
+    pub eof_table: EofTable,
+    pub non_terminal_table: NonTerminalTable,
+    pub conflicts: Vec<Conflict>,
+    /// Production of each state whose only action, whatever the lookahead, is reducing it
+    pub immediate_reductions: Vec<Option<usize>>,
+}
+
 #[derive(Debug, Default)]
 pub struct TransitionTables {
     token_table: Vec<Vec<Option<usize>>>,
@@ -200,6 +211,18 @@ impl TransitionTables {
             .flatten()
     }
 
+    pub fn non_terminal_transition(
+        &self,
+        starting_state: usize,
+        non_terminal: SymbolicNonTerminal,
+    ) -> Option<usize> {
+        self.non_terminal_table
+            .get(starting_state)?
+            .get(non_terminal)
+            .cloned()
+            .flatten()
+    }
+
     pub fn iter(&self) -> impl Iterator<Item = (&Vec<Option<usize>>, &Vec<Option<usize>>)> {
         self.token_table.iter().zip(self.non_terminal_table.iter())
     }
@@ -240,3 +263,237 @@ impl Display for TransitionTables {
         Ok(())
     }
 }
+
+impl ParsingTables {
+    pub fn new(
+        token_table: TokenTable,
+        eof_table: EofTable,
+        non_terminal_table: NonTerminalTable,
+        conflicts: Vec<Conflict>,
+    ) -> Self {
+        let immediate_reductions = token_table
+            .table
+            .iter()
+            .zip(eof_table.table.iter())
+            .map(|(token_row, eof_action)| {
+                let eof_action = eof_action.as_ref().map(|action| match action {
+                    EofAction::Reduce(production_id) => Some(*production_id),
+                    EofAction::Accept => None,
+                });
+                let token_actions = token_row.iter().flatten().map(|action| match action {
+                    TokenAction::Shift(_) => None,
+                    TokenAction::Reduce(production_id) => Some(*production_id),
+                });
+                token_actions
+                    .chain(eof_action)
+                    .unique()
+                    .exactly_one()
+                    .ok()
+                    .flatten()
+            })
+            .collect();
+        Self {
+            token_table,
+            eof_table,
+            non_terminal_table,
+            conflicts,
+            immediate_reductions,
+        }
+    }
+
+    /// Plain text form of the tables, read back by [`ParsingTables::deserialize`]: one line per
+    /// state with its token actions, end of input action and gotos, then one line per conflict
+    pub fn serialize(&self) -> String {
+        let (non_terminals_count, _) = self.non_terminal_table.dimensions();
+        let mut res = format!(
+            "tables {} {} {}\n",
+            self.token_table.table.len(),
+            self.token_table.tokens_count,
+            non_terminals_count
+        );
+        for state in 0..self.token_table.table.len() {
+            let token_actions = self.token_table.table[state].iter().map(|action| match action {
+                Some(TokenAction::Shift(target)) => format!("s{target}"),
+                Some(TokenAction::Reduce(production_id)) => format!("r{production_id}"),
+                None => ".".to_string(),
+            });
+            let eof_action = match self.eof_table[state] {
+                Some(EofAction::Accept) => "a".to_string(),
+                Some(EofAction::Reduce(production_id)) => format!("r{production_id}"),
+                None => ".".to_string(),
+            };
+            let gotos = self.non_terminal_table.table[state]
+                .iter()
+                .map(|target| target.map_or(".".to_string(), |target| target.to_string()));
+            res += &format!(
+                "{} | {eof_action} | {}\n",
+                token_actions.format(" "),
+                gotos.format(" ")
+            );
+        }
+        for conflict in self.conflicts.iter() {
+            let lookahead = match conflict.lookahead() {
+                ConflictLookahead::Token(token) => token.to_string(),
+                ConflictLookahead::Eof => "$".to_string(),
+            };
+            let kind = match conflict.kind() {
+                ConflictKind::ShiftReduce { shift, reduce } => format!("sr {shift} {reduce}"),
+                ConflictKind::ReduceReduce {
+                    reduce,
+                    other_reduce,
+                } => format!("rr {reduce} {other_reduce}"),
+            };
+            res += &format!("conflict {} {lookahead} {kind}\n", conflict.state());
+        }
+        res
+    }
+
+    /// Reads tables written by [`ParsingTables::serialize`], `None` if the text is malformed
+    pub fn deserialize(text: &str) -> Option<Self> {
+        let mut lines = text.lines();
+        let header = lines.next()?.strip_prefix("tables ")?;
+        let [states_count, tokens_count, non_terminals_count] = header
+            .split(' ')
+            .map(|count| count.parse::<usize>().ok())
+            .collect::<Option<Vec<_>>>()?
+            .try_into()
+            .ok()?;
+        let mut token_table = TokenTable::new(tokens_count);
+        let mut eof_table = EofTable::new();
+        let mut non_terminal_table = NonTerminalTable::new(non_terminals_count);
+        let number = |text: &str| text.parse::<usize>().ok();
+
+        for state in 0..states_count {
+            let (token_actions, rest) = lines.next()?.split_once(" | ")?;
+            let (eof_action, gotos) = rest.split_once(" | ")?;
+            token_table.add_state();
+            eof_table.add_state();
+            non_terminal_table.add_state();
+
+            let token_actions = token_actions.split(' ').filter(|entry| !entry.is_empty());
+            if token_actions.clone().count() != tokens_count {
+                return None;
+            }
+            for (token, entry) in token_actions.enumerate() {
+                token_table[(state, token)] = match entry.split_at_checked(1)? {
+                    (".", "") => None,
+                    ("s", target) => Some(TokenAction::Shift(number(target)?)),
+                    ("r", production_id) => Some(TokenAction::Reduce(number(production_id)?)),
+                    _ => return None,
+                };
+            }
+            eof_table[state] = match eof_action.split_at_checked(1)? {
+                (".", "") => None,
+                ("a", "") => Some(EofAction::Accept),
+                ("r", production_id) => Some(EofAction::Reduce(number(production_id)?)),
+                _ => return None,
+            };
+            let gotos = gotos.split(' ').filter(|entry| !entry.is_empty());
+            if gotos.clone().count() != non_terminals_count {
+                return None;
+            }
+            for (non_terminal, entry) in gotos.enumerate() {
+                non_terminal_table[(state, non_terminal)] = match entry {
+                    "." => None,
+                    target => Some(number(target)?),
+                };
+            }
+        }
+
+        let mut conflicts = Vec::new();
+        for line in lines {
+            let fields = line.strip_prefix("conflict ")?.split(' ').collect_vec();
+            let [state, lookahead, kind, first, second] = fields[..] else {
+                return None;
+            };
+            let lookahead = match lookahead {
+                "$" => ConflictLookahead::Eof,
+                token => ConflictLookahead::Token(number(token)?),
+            };
+            let (first, second) = (number(first)?, number(second)?);
+            let kind = match kind {
+                "sr" => ConflictKind::ShiftReduce {
+                    shift: first,
+                    reduce: second,
+                },
+                "rr" => ConflictKind::ReduceReduce {
+                    reduce: first,
+                    other_reduce: second,
+                },
+                _ => return None,
+            };
+            conflicts.push(Conflict::new(number(state)?, lookahead, kind));
+        }
+
+        Some(Self::new(
+            token_table,
+            eof_table,
+            non_terminal_table,
+            conflicts,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParsingTables;
+    use crate::{
+        automaton::Automaton, lalr::LalrAutomaton, parsing::action::TokenAction,
+        test_grammars::grammar,
+    };
+    use itertools::Itertools;
+
+    #[test]
+    fn serialized_tables_read_back_identical() {
+        let automaton =
+            LalrAutomaton::compute(grammar("E", &[("E", &["E", "plus", "E"]), ("E", &["id"])]));
+        let tables = automaton.generate_tables();
+        assert_eq!(tables.conflicts.len(), 1);
+        let text = tables.serialize();
+        let read = ParsingTables::deserialize(&text).unwrap();
+        assert_eq!(read.token_table.table, tables.token_table.table);
+        assert_eq!(read.eof_table.table, tables.eof_table.table);
+        assert_eq!(read.non_terminal_table.table, tables.non_terminal_table.table);
+        assert_eq!(read.conflicts, tables.conflicts);
+        assert_eq!(read.immediate_reductions, tables.immediate_reductions);
+        assert_eq!(read.serialize(), text);
+    }
+
+    #[test]
+    fn states_with_a_single_reduction_reduce_immediately() {
+        let automaton = LalrAutomaton::compute(grammar(
+            "E",
+            &[("E", &["E", "plus", "T"]), ("E", &["T"]), ("T", &["id"])],
+        ));
+        let tables = automaton.generate_tables();
+        // the three states at the end of a production, but not the one that also shifts `plus`
+        let immediate = tables
+            .immediate_reductions
+            .iter()
+            .flatten()
+            .sorted()
+            .collect_vec();
+        assert_eq!(immediate, [&0, &1, &2]);
+        for (state, production_id) in tables.immediate_reductions.iter().enumerate() {
+            let Some(production_id) = production_id else {
+                continue;
+            };
+            assert!(
+                tables.token_table.table[state]
+                    .iter()
+                    .flatten()
+                    .all(|action| *action == TokenAction::Reduce(*production_id))
+            );
+        }
+    }
+
+    #[test]
+    fn truncated_tables_are_rejected() {
+        let automaton =
+            LalrAutomaton::compute(grammar("E", &[("E", &["E", "plus", "E"]), ("E", &["id"])]));
+        let text = automaton.generate_tables().serialize();
+        let truncated = text.lines().take(2).collect::<Vec<_>>().join("\n");
+        assert!(ParsingTables::deserialize(&truncated).is_none());
+        assert!(ParsingTables::deserialize("").is_none());
+    }
+}
@@ -0,0 +1,110 @@
+use std::collections::BTreeMap;
+
+use crate::{
+    parsing::tables::ParsingTables,
+    symbolic_grammar::{SymbolicGrammar, SymbolicNonTerminal, SymbolicSymbol},
+};
+
+/// Unit productions reduced, in order, by the goto of a state on a non-terminal once the tables
+/// bypass them
+pub type UnitChains = BTreeMap<(usize, SymbolicNonTerminal), Vec<usize>>;
+
+impl ParsingTables {
+    /// Rewrites the gotos leading to a state whose only action is reducing a unit production
+    /// `A -> B` to the goto on `A` of the same state, skipping a reduction and a goto, as many
+    /// times as the chain of unit productions goes. Only the productions `bypassable` accepts
+    /// are skipped, their semantic action has to be converting the body into the head, which
+    /// the parser then does when following the goto. The skipped states may still be reached by
+    /// the gotos of other states: they keep their actions
+    pub fn bypass_unit_productions(
+        &mut self,
+        grammar: &SymbolicGrammar,
+        bypassable: impl Fn(usize) -> bool,
+    ) -> UnitChains {
+        let unit_head = |production_id: usize| {
+            let production = grammar.get_production(production_id)?;
+            match production.body()[..] {
+                [SymbolicSymbol::NonTerminal(_)] if bypassable(production_id) => {
+                    Some(*production.head())
+                }
+                _ => None,
+            }
+        };
+        // the chains follow the gotos as they were before any of them was rewritten
+        let gotos = self.non_terminal_table.table.clone();
+        let mut chains = UnitChains::new();
+        for (state, row) in gotos.iter().enumerate() {
+            for (non_terminal, target) in row.iter().enumerate() {
+                let Some(mut target) = *target else {
+                    continue;
+                };
+                let mut chain = Vec::new();
+                // a chain longer than the number of productions loops through the same ones
+                while chain.len() < grammar.productions().len() {
+                    let Some(production_id) = self.immediate_reductions[target] else {
+                        break;
+                    };
+                    let Some(head) = unit_head(production_id) else {
+                        break;
+                    };
+                    let Some(head_target) = gotos[state][head] else {
+                        break;
+                    };
+                    chain.push(production_id);
+                    target = head_target;
+                }
+                if !chain.is_empty() {
+                    self.non_terminal_table[(state, non_terminal)] = Some(target);
+                    chains.insert((state, non_terminal), chain);
+                }
+            }
+        }
+        chains
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        automaton::Automaton,
+        lalr::LalrAutomaton,
+        test_grammars::{accepts, expressions, grammar},
+    };
+
+    #[test]
+    fn unit_chains_are_skipped() {
+        let automaton = LalrAutomaton::compute(expressions());
+        let mut tables = automaton.generate_tables();
+        let chains = tables.bypass_unit_productions(automaton.grammar(), |_| true);
+        // `T -> F` is reduced in a state of its own, `E -> T` shares it with `T -> T·times F`
+        assert!(!chains.is_empty());
+        assert!(chains.values().all(|chain| *chain == [3]));
+        for input in [
+            &["id"][..],
+            &["id", "plus", "id", "times", "id"],
+            &["lparen", "id", "plus", "id", "rparen", "times", "id"],
+        ] {
+            assert!(accepts(automaton.grammar(), &tables, input), "{input:?}");
+        }
+        for input in [&["id", "plus"][..], &["times", "id"], &["lparen", "id"]] {
+            assert!(!accepts(automaton.grammar(), &tables, input), "{input:?}");
+        }
+    }
+
+    #[test]
+    fn productions_with_an_action_are_kept() {
+        let grammar = grammar("A", &[("A", &["B"]), ("B", &["C"]), ("C", &["x"])]);
+        let automaton = LalrAutomaton::compute(grammar);
+        let mut tables = automaton.generate_tables();
+        let chains = tables.bypass_unit_productions(automaton.grammar(), |id| id == 1);
+        assert_eq!(chains.into_values().collect::<Vec<_>>(), [vec![1]]);
+
+        let mut tables = automaton.generate_tables();
+        let chains = tables.bypass_unit_productions(automaton.grammar(), |_| true);
+        assert_eq!(
+            chains.into_values().collect::<Vec<_>>(),
+            [vec![0], vec![1, 0]]
+        );
+        assert!(accepts(automaton.grammar(), &tables, &["x"]));
+    }
+}
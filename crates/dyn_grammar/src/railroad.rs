@@ -0,0 +1,476 @@
+//! Railroad diagrams of a grammar, one SVG per non-terminal. The productions of a non-terminal
+//! are drawn as alternatives, and the helper non-terminals `ebnf!` desugars repetitions,
+//! optionals and alternatives into are folded back into loops, bypasses and branches
+
+use crate::{EnrichedGrammar, enriched_symbol::EnrichedSymbol, token::Match};
+use itertools::Itertools;
+use std::fmt::Write;
+use syn::Ident;
+
+const CHAR_WIDTH: i32 = 8;
+const BOX_PADDING: i32 = 10;
+/// Distance from the line to the top and bottom of a box
+const BOX_HALF_HEIGHT: i32 = 12;
+/// Horizontal space between two symbols, and width of the rails of branches and loops
+const GAP: i32 = 20;
+/// Vertical space between two branches
+const VERTICAL_GAP: i32 = 12;
+const MARGIN: i32 = 10;
+
+const STYLE: &str = "path, line { stroke: black; stroke-width: 2; fill: none; } \
+rect { stroke: black; stroke-width: 2; fill: #ffffdd; } \
+rect.terminal { fill: #ddeeff; } \
+text { font: 14px monospace; text-anchor: middle; }";
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Diagram {
+    Terminal(String),
+    NonTerminal(String),
+    /// The empty sequence is a plain line
+    Sequence(Vec<Diagram>),
+    Choice(Vec<Diagram>),
+    Optional(Box<Diagram>),
+    ZeroOrMore(Box<Diagram>),
+}
+
+/// Width of a diagram, and how far it extends above and below its line
+struct Size {
+    width: i32,
+    up: i32,
+    down: i32,
+}
+
+impl Diagram {
+    fn sequence(mut items: Vec<Diagram>) -> Self {
+        if items.len() == 1 {
+            items.pop().unwrap()
+        } else {
+            Self::Sequence(items)
+        }
+    }
+
+    fn choice(mut alternatives: Vec<Diagram>) -> Self {
+        if alternatives.len() == 1 {
+            alternatives.pop().unwrap()
+        } else {
+            Self::Choice(alternatives)
+        }
+    }
+
+    /// Branches drawn for the diagram when it is one: the bypass of an optional is the line
+    fn branches(&self) -> Option<Vec<&Diagram>> {
+        match self {
+            Self::Choice(alternatives) if !alternatives.is_empty() => {
+                Some(alternatives.iter().collect())
+            }
+            Self::Optional(diagram) => Some(vec![&EMPTY, diagram]),
+            _ => None,
+        }
+    }
+
+    fn size(&self) -> Size {
+        if let Some(branches) = self.branches() {
+            let sizes = branches.iter().map(|branch| branch.size()).collect_vec();
+            let below: i32 = sizes
+                .iter()
+                .tuple_windows()
+                .map(|(previous, next)| previous.down + VERTICAL_GAP + next.up)
+                .sum();
+            return Size {
+                width: sizes.iter().map(|size| size.width).max().unwrap() + 2 * GAP,
+                up: sizes[0].up,
+                down: below + sizes.last().unwrap().down,
+            };
+        }
+        match self {
+            Self::Terminal(label) | Self::NonTerminal(label) => Size {
+                width: CHAR_WIDTH * label.chars().count() as i32 + 2 * BOX_PADDING,
+                up: BOX_HALF_HEIGHT,
+                down: BOX_HALF_HEIGHT,
+            },
+            Self::Sequence(items) => {
+                let sizes = items.iter().map(Diagram::size).collect_vec();
+                Size {
+                    width: sizes.iter().map(|size| size.width).sum::<i32>()
+                        + GAP * (sizes.len().max(1) as i32 - 1),
+                    up: sizes.iter().map(|size| size.up).max().unwrap_or(0),
+                    down: sizes.iter().map(|size| size.down).max().unwrap_or(0),
+                }
+            }
+            Self::ZeroOrMore(diagram) => {
+                let size = diagram.size();
+                // A bypass above the loop
+                Size {
+                    width: size.width + 4 * GAP,
+                    up: size.up + VERTICAL_GAP + GAP / 2,
+                    down: size.down + VERTICAL_GAP,
+                }
+            }
+            Self::Choice(_) | Self::Optional(_) => unreachable!("drawn as branches"),
+        }
+    }
+
+    /// Draws the diagram with its line starting at `(x, y)`
+    fn render(&self, x: i32, y: i32, out: &mut String) {
+        let width = self.size().width;
+        if let Some(branches) = self.branches() {
+            let mut branch_y = y;
+            for (id, branch) in branches.iter().enumerate() {
+                let size = branch.size();
+                if id > 0 {
+                    branch_y += size.up;
+                    let (left, right) = (x + GAP / 2, x + width - GAP / 2);
+                    write!(
+                        out,
+                        r#"<path d="M{x} {y}Q{left} {y} {left} {}L{left} {}Q{left} {branch_y} {} {branch_y}"/>"#,
+                        y + GAP / 2,
+                        branch_y - GAP / 2,
+                        x + GAP,
+                    )
+                    .unwrap();
+                    write!(
+                        out,
+                        r#"<path d="M{} {branch_y}Q{right} {branch_y} {right} {}L{right} {}Q{right} {y} {} {y}"/>"#,
+                        x + width - GAP,
+                        branch_y - GAP / 2,
+                        y + GAP / 2,
+                        x + width,
+                    )
+                    .unwrap();
+                } else {
+                    line(out, x, x + GAP, y);
+                    line(out, x + width - GAP, x + width, y);
+                }
+                branch.render(x + GAP, branch_y, out);
+                line(out, x + GAP + size.width, x + width - GAP, branch_y);
+                branch_y += size.down + VERTICAL_GAP;
+            }
+            return;
+        }
+        match self {
+            Self::Terminal(label) | Self::NonTerminal(label) => {
+                let class = if matches!(self, Self::Terminal(_)) {
+                    r#" class="terminal" rx="10""#
+                } else {
+                    ""
+                };
+                write!(
+                    out,
+                    r#"<rect x="{x}" y="{}" width="{width}" height="{}"{class}/><text x="{}" y="{}">{}</text>"#,
+                    y - BOX_HALF_HEIGHT,
+                    2 * BOX_HALF_HEIGHT,
+                    x + width / 2,
+                    y + 5,
+                    escape(label)
+                )
+                .unwrap();
+            }
+            Self::Sequence(items) => {
+                let mut item_x = x;
+                for (id, item) in items.iter().enumerate() {
+                    if id > 0 {
+                        line(out, item_x, item_x + GAP, y);
+                        item_x += GAP;
+                    }
+                    item.render(item_x, y, out);
+                    item_x += item.size().width;
+                }
+            }
+            Self::ZeroOrMore(diagram) => {
+                let size = diagram.size();
+                let (start, end) = (x + 2 * GAP, x + 2 * GAP + size.width);
+                let (left, right) = (start - GAP / 2, end + GAP / 2);
+                // The bypass, above
+                let top = y - size.up - VERTICAL_GAP;
+                write!(
+                    out,
+                    r#"<path d="M{x} {y}Q{} {y} {} {}L{} {}Q{} {top} {} {top}L{} {top}Q{} {top} {} {}L{} {}Q{} {y} {} {y}"/>"#,
+                    x + GAP / 2,
+                    x + GAP / 2,
+                    y - GAP / 2,
+                    x + GAP / 2,
+                    top + GAP / 2,
+                    x + GAP / 2,
+                    x + GAP,
+                    x + width - GAP,
+                    x + width - GAP / 2,
+                    x + width - GAP / 2,
+                    top + GAP / 2,
+                    x + width - GAP / 2,
+                    y - GAP / 2,
+                    x + width - GAP / 2,
+                    x + width,
+                )
+                .unwrap();
+                line(out, x, start, y);
+                diagram.render(start, y, out);
+                line(out, end, x + width, y);
+                // The loop back, below
+                let bottom = y + size.down + VERTICAL_GAP;
+                write!(
+                    out,
+                    r#"<path d="M{end} {y}Q{right} {y} {right} {}L{right} {}Q{right} {bottom} {end} {bottom}L{start} {bottom}Q{left} {bottom} {left} {}L{left} {}Q{left} {y} {start} {y}"/>"#,
+                    y + GAP / 2,
+                    bottom - GAP / 2,
+                    bottom - GAP / 2,
+                    y + GAP / 2,
+                )
+                .unwrap();
+            }
+            Self::Choice(_) | Self::Optional(_) => unreachable!("drawn as branches"),
+        }
+    }
+
+    pub fn to_svg(&self) -> String {
+        let size = self.size();
+        let width = size.width + 2 * MARGIN + 2 * GAP;
+        let height = size.up + size.down + 2 * MARGIN;
+        let y = MARGIN + size.up;
+        let mut out = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}"><style>{STYLE}</style>"#
+        );
+        // Bars marking the start and the end of the diagram
+        write!(
+            out,
+            r#"<path d="M{MARGIN} {}v{}M{} {}v{}"/>"#,
+            y - BOX_HALF_HEIGHT / 2,
+            BOX_HALF_HEIGHT,
+            width - MARGIN,
+            y - BOX_HALF_HEIGHT / 2,
+            BOX_HALF_HEIGHT,
+        )
+        .unwrap();
+        line(&mut out, MARGIN, MARGIN + GAP, y);
+        self.render(MARGIN + GAP, y, &mut out);
+        line(&mut out, MARGIN + GAP + size.width, width - MARGIN, y);
+        out.push_str("</svg>\n");
+        out
+    }
+}
+
+static EMPTY: Diagram = Diagram::Sequence(Vec::new());
+
+fn line(out: &mut String, from: i32, to: i32, y: i32) {
+    if from < to {
+        write!(out, r#"<line x1="{from}" y1="{y}" x2="{to}" y2="{y}"/>"#).unwrap();
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+/// Non-terminals generated by `ebnf!`, all of whose productions have the reserved `__` prefix
+fn is_ebnf_helper(grammar: &EnrichedGrammar, non_terminal: &Ident) -> bool {
+    let mut productions = grammar
+        .productions()
+        .iter()
+        .filter(|production| production.head() == non_terminal)
+        .peekable();
+    productions.peek().is_some()
+        && productions.all(|production| production.ident().to_string().starts_with("__"))
+}
+
+fn symbol_diagram(
+    grammar: &EnrichedGrammar,
+    symbol: &EnrichedSymbol,
+    expanding: &mut Vec<Ident>,
+) -> Diagram {
+    match symbol {
+        EnrichedSymbol::Token(token) => Diagram::Terminal(match token.match_string() {
+            Match::Literal(literal) => literal.clone(),
+            Match::Regex(_) | Match::Error => token.ident().to_string(),
+        }),
+        EnrichedSymbol::NonTerminal(non_terminal) => {
+            let ident = non_terminal.ident();
+            if is_ebnf_helper(grammar, ident) && !expanding.contains(ident) {
+                expanding.push(ident.clone());
+                let res = helper_diagram(grammar, ident, expanding);
+                expanding.pop();
+                res
+            } else {
+                Diagram::NonTerminal(ident.to_string())
+            }
+        }
+    }
+}
+
+fn body_diagram(
+    grammar: &EnrichedGrammar,
+    body: &[EnrichedSymbol],
+    expanding: &mut Vec<Ident>,
+) -> Diagram {
+    Diagram::sequence(
+        body.iter()
+            .map(|symbol| symbol_diagram(grammar, symbol, expanding))
+            .collect(),
+    )
+}
+
+/// Folds a helper back: `H -> H body | ε` is a repetition, `H -> body | ε` an optional and any
+/// other helper a choice between its bodies
+fn helper_diagram(
+    grammar: &EnrichedGrammar,
+    helper: &Ident,
+    expanding: &mut Vec<Ident>,
+) -> Diagram {
+    let bodies = grammar
+        .productions()
+        .iter()
+        .filter(|production| production.head() == helper)
+        .map(|production| production.body().as_slice())
+        .collect_vec();
+    if let [first, second] = bodies.as_slice()
+        && (first.is_empty() || second.is_empty())
+    {
+        let body = if first.is_empty() { second } else { first };
+        return match body.split_first() {
+            Some((EnrichedSymbol::NonTerminal(head), rest)) if head.ident() == helper => {
+                Diagram::ZeroOrMore(Box::new(body_diagram(grammar, rest, expanding)))
+            }
+            _ => Diagram::Optional(Box::new(body_diagram(grammar, body, expanding))),
+        };
+    }
+    Diagram::choice(
+        bodies
+            .into_iter()
+            .map(|body| body_diagram(grammar, body, expanding))
+            .collect(),
+    )
+}
+
+/// Diagram of a non-terminal: its productions as alternatives
+pub fn diagram(grammar: &EnrichedGrammar, non_terminal: &Ident) -> Diagram {
+    let mut expanding = vec![non_terminal.clone()];
+    Diagram::choice(
+        grammar
+            .productions()
+            .iter()
+            .filter(|production| production.head() == non_terminal)
+            .map(|production| body_diagram(grammar, production.body(), &mut expanding))
+            .collect(),
+    )
+}
+
+/// The SVG diagram of every non-terminal of the grammar, except the helpers of `ebnf!`, which
+/// are drawn inside the diagrams using them, and the non-terminals without productions, which
+/// have nothing to draw
+pub fn railroad_diagrams(grammar: &EnrichedGrammar) -> Vec<(Ident, String)> {
+    grammar
+        .non_terminals()
+        .iter()
+        .map(|non_terminal| non_terminal.ident())
+        .filter(|non_terminal| !is_ebnf_helper(grammar, non_terminal))
+        .filter(|non_terminal| {
+            grammar
+                .productions()
+                .iter()
+                .any(|production| production.head() == *non_terminal)
+        })
+        .map(|non_terminal| {
+            (
+                non_terminal.clone(),
+                diagram(grammar, non_terminal).to_svg(),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Diagram, diagram, railroad_diagrams};
+    use crate::{
+        EnrichedGrammar,
+        non_terminal::EnrichedNonTerminal,
+        production::EnrichedBaseProduction,
+        test_grammars::enriched_grammar,
+        token::{EnrichedToken, Match},
+    };
+    use syn::Ident;
+
+    fn ident(name: &str) -> Ident {
+        syn::parse_str(name).unwrap()
+    }
+
+    /// `ebnf!(P0, S -> (A*, (B, C)?, CorD { C, D }))` desugared, next to a plain production
+    fn ebnf_grammar() -> crate::EnrichedGrammar {
+        enriched_grammar(
+            "S",
+            &[
+                ("__P0_0_More", "__P0_0_Rep", &["__P0_0_Rep", "A"]),
+                ("__P0_0_Done", "__P0_0_Rep", &[]),
+                ("__P0_1_Some", "__P0_1_Opt", &["B", "C"]),
+                ("__P0_1_None", "__P0_1_Opt", &[]),
+                ("__P0_2_CorD_C", "CorD", &["C"]),
+                ("__P0_2_CorD_D", "CorD", &["D"]),
+                ("P0", "S", &["__P0_0_Rep", "__P0_1_Opt", "CorD"]),
+                ("P1", "S", &["T"]),
+                ("P2", "T", &["D"]),
+            ],
+        )
+    }
+
+    #[test]
+    fn ebnf_helpers_are_folded() {
+        let grammar = ebnf_grammar();
+        let terminal = |label: &str| Diagram::Terminal(label.to_string());
+        assert_eq!(
+            diagram(&grammar, &ident("S")),
+            Diagram::Choice(vec![
+                Diagram::Sequence(vec![
+                    Diagram::ZeroOrMore(Box::new(terminal("A"))),
+                    Diagram::Optional(Box::new(Diagram::Sequence(vec![
+                        terminal("B"),
+                        terminal("C")
+                    ]))),
+                    Diagram::Choice(vec![terminal("C"), terminal("D")]),
+                ]),
+                Diagram::NonTerminal("T".to_string()),
+            ])
+        );
+    }
+
+    #[test]
+    fn one_diagram_per_non_terminal() {
+        let diagrams = railroad_diagrams(&ebnf_grammar());
+        let names = diagrams
+            .iter()
+            .map(|(non_terminal, _)| non_terminal.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["S", "T"]);
+        for (_, svg) in diagrams.iter() {
+            assert!(svg.starts_with("<svg xmlns=\"http://www.w3.org/2000/svg\""));
+            assert!(svg.ends_with("</svg>\n"));
+            assert!(!svg.contains("__P0"));
+        }
+        assert!(diagrams[0].1.contains(">T</text>"));
+    }
+
+    #[test]
+    fn non_terminals_without_productions_are_not_drawn() {
+        let grammar = EnrichedGrammar::new(
+            None,
+            vec![
+                EnrichedNonTerminal::new(ident("S")),
+                EnrichedNonTerminal::new(ident("U")),
+            ],
+            vec![EnrichedToken::new(
+                ident("A"),
+                Match::Literal("a".to_string()),
+            )],
+            EnrichedNonTerminal::new(ident("S")),
+            vec![EnrichedBaseProduction::new(
+                ident("P0"),
+                ident("S"),
+                vec![ident("A")],
+            )],
+        );
+        let names = railroad_diagrams(&grammar)
+            .into_iter()
+            .map(|(non_terminal, _)| non_terminal.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["S"]);
+    }
+}
@@ -0,0 +1,253 @@
+//! Human-readable description of an automaton, in the spirit of bison's `.output` files: the
+//! grammar, then every state with its items, lookaheads, actions and conflicts, all in the
+//! user's names
+
+use crate::{
+    automaton::{Automaton, ItemCore, closure, resolve_shift_reduce},
+    parsing::{
+        action::{EofAction, TokenAction},
+        conflict::{ConflictKind, ConflictLookahead},
+    },
+};
+use itertools::Itertools;
+use std::fmt::Write;
+
+/// Name of the end of input in lookaheads and actions
+pub(crate) const EOF: &str = "$end";
+
+/// Items of the state, kernel first, each complete one followed by the tokens allowing its
+/// reduction
+pub(crate) fn state_items<A: Automaton + ?Sized>(automaton: &A, state: usize) -> Vec<String> {
+    let grammar = automaton.grammar();
+    let reductions = automaton.reductions(state);
+    let kernel = automaton.kernel(state).into_iter().sorted().collect_vec();
+    closure(grammar, kernel)
+        .into_iter()
+        .map(|item| {
+            let lookahead = reductions
+                .iter()
+                .find(|(production_id, _)| {
+                    let complete = ItemCore {
+                        production_id: *production_id,
+                        marker_position: grammar.get_production(*production_id).unwrap().arity(),
+                    };
+                    complete == item
+                })
+                .map(|(_, lookahead)| {
+                    let tokens = lookahead
+                        .tokens
+                        .iter()
+                        .sorted()
+                        .map(|token| grammar.token_name(*token))
+                        .chain(lookahead.can_eof_follow.then(|| EOF.to_string()));
+                    format!("  [{}]", tokens.format(", "))
+                })
+                .unwrap_or_default();
+            format!("{}{lookahead}", automaton.item_string(&item))
+        })
+        .collect()
+}
+
+pub fn report<A: Automaton + ?Sized>(automaton: &A) -> String {
+    let grammar = automaton.grammar();
+    let tables = automaton.generate_tables();
+    let mut res = String::new();
+    let precedence = |precedence: Option<_>| {
+        precedence
+            .map(|precedence| format!("  [precedence {precedence}]"))
+            .unwrap_or_default()
+    };
+
+    writeln!(res, "Grammar\n").unwrap();
+    for production in grammar.productions() {
+        writeln!(
+            res,
+            "    {}{}",
+            automaton.production_string(production.id()),
+            precedence(production.precedence())
+        )
+        .unwrap();
+    }
+    writeln!(res, "\nTokens\n").unwrap();
+    for token in 0..grammar.token_count() {
+        writeln!(
+            res,
+            "    {}{}",
+            grammar.token_name(token),
+            precedence(grammar.token_precedence(token))
+        )
+        .unwrap();
+    }
+    writeln!(res, "\nNon-terminals\n").unwrap();
+    for non_terminal in 0..grammar.non_terminal_count() {
+        writeln!(res, "    {}", grammar.non_terminal_name(non_terminal)).unwrap();
+    }
+
+    for state in 0..automaton.states_count() {
+        writeln!(res, "\n\nState {state}\n").unwrap();
+        let reductions = automaton
+            .reductions(state)
+            .into_iter()
+            .sorted_by_key(|(production_id, _)| *production_id)
+            .collect_vec();
+        for item in state_items(automaton, state) {
+            writeln!(res, "    {item}").unwrap();
+        }
+        res.push('\n');
+
+        let reduce_string = |production_id: usize| {
+            format!(
+                "reduce using {}",
+                automaton.production_string(production_id)
+            )
+        };
+        let mut resolutions = Vec::new();
+        for token in 0..grammar.token_count() {
+            let name = grammar.token_name(token);
+            match &tables.token_table[(state, token)] {
+                Some(TokenAction::Shift(target)) => {
+                    writeln!(res, "    {name:<16}shift, and go to state {target}").unwrap()
+                }
+                Some(TokenAction::Reduce(production_id)) => {
+                    writeln!(res, "    {name:<16}{}", reduce_string(*production_id)).unwrap()
+                }
+                None => {}
+            }
+            for (production_id, lookahead) in reductions.iter() {
+                if !lookahead.tokens.contains(&token) {
+                    continue;
+                }
+                // reductions on the token that the table does not keep
+                if tables.token_table[(state, token)] != Some(TokenAction::Reduce(*production_id)) {
+                    writeln!(res, "    {name:<16}[{}]", reduce_string(*production_id)).unwrap();
+                }
+                // shift/reduce conflicts solved by precedence, whichever action wins
+                if let Some(target) = automaton.transitions().token_transition(state, token)
+                    && let Some(action) =
+                        resolve_shift_reduce(automaton, token, target, *production_id)
+                {
+                    resolutions.push(format!(
+                        "Conflict between {} and token {name} resolved as {}",
+                        automaton.production_string(*production_id),
+                        match action {
+                            TokenAction::Shift(_) => "shift",
+                            TokenAction::Reduce(_) => "reduce",
+                        }
+                    ));
+                }
+            }
+        }
+        match &tables.eof_table[state] {
+            Some(EofAction::Accept) => writeln!(res, "    {EOF:<16}accept").unwrap(),
+            Some(EofAction::Reduce(production_id)) => {
+                writeln!(res, "    {EOF:<16}{}", reduce_string(*production_id)).unwrap()
+            }
+            None => {}
+        }
+        for (production_id, lookahead) in reductions.iter() {
+            if lookahead.can_eof_follow
+                && *production_id != usize::MAX
+                && tables.eof_table[state] != Some(EofAction::Reduce(*production_id))
+            {
+                writeln!(res, "    {EOF:<16}[{}]", reduce_string(*production_id)).unwrap();
+            }
+        }
+        let gotos = (0..grammar.non_terminal_count())
+            .filter_map(|non_terminal| {
+                let target = automaton
+                    .transitions()
+                    .non_terminal_transition(state, non_terminal)?;
+                Some((non_terminal, target))
+            })
+            .collect_vec();
+        if !gotos.is_empty() {
+            res.push('\n');
+        }
+        for (non_terminal, target) in gotos {
+            writeln!(
+                res,
+                "    {:<16}go to state {target}",
+                grammar.non_terminal_name(non_terminal)
+            )
+            .unwrap();
+        }
+
+        let conflicts = tables
+            .conflicts
+            .iter()
+            .filter(|conflict| conflict.state() == state)
+            .map(|conflict| {
+                let lookahead = match conflict.lookahead() {
+                    ConflictLookahead::Token(token) => grammar.token_name(*token),
+                    ConflictLookahead::Eof => EOF.to_string(),
+                };
+                match conflict.kind() {
+                    ConflictKind::ShiftReduce { shift, reduce } => format!(
+                        "Unresolved shift/reduce conflict on {lookahead} between shifting to state {shift} and {}, shifting",
+                        automaton.production_string(*reduce)
+                    ),
+                    ConflictKind::ReduceReduce {
+                        reduce,
+                        other_reduce,
+                    } => format!(
+                        "Unresolved reduce/reduce conflict on {lookahead} between {} and {}, reducing {}",
+                        automaton.production_string(*reduce),
+                        automaton.production_string(*other_reduce),
+                        grammar.production_name(*reduce)
+                    ),
+                }
+            })
+            .collect_vec();
+        if !resolutions.is_empty() || !conflicts.is_empty() {
+            res.push('\n');
+        }
+        for line in resolutions.into_iter().chain(conflicts) {
+            writeln!(res, "    {line}").unwrap();
+        }
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::report;
+    use crate::{
+        automaton::Automaton,
+        lalr::LalrAutomaton,
+        test_grammars::{ambiguous_arithmetic, grammar},
+    };
+
+    #[test]
+    fn report_of_an_ambiguous_grammar() {
+        let automaton =
+            LalrAutomaton::compute(grammar("E", &[("E", &["E", "plus", "E"]), ("E", &["id"])]));
+        let report = report(&automaton);
+        assert!(report.starts_with("Grammar\n\n    P0 (E -> E plus E)\n    P1 (E -> id)\n"));
+        assert!(report.contains("\n    $accept -> ·E\n    E -> ·E plus E\n    E -> ·id\n"));
+        assert!(report.contains("\n    $end            accept\n"));
+        assert!(report.contains("\n    E -> E·plus E\n    E -> E plus E·  [plus, $end]\n"));
+        assert!(report.contains("\n    plus            [reduce using P0 (E -> E plus E)]\n"));
+        assert!(
+            report.contains(
+                "\n    Unresolved shift/reduce conflict on plus between shifting to state"
+            )
+        );
+        assert_eq!(report.matches("\nState ").count(), automaton.states_count());
+
+        // with precedences, the conflicts are reported whichever action wins
+        let report = super::report(&LalrAutomaton::compute(ambiguous_arithmetic()));
+        assert!(report.contains(
+            "\n    Conflict between P0 (E -> E plus E) and token times resolved as shift\n"
+        ));
+        assert!(report.contains(
+            "\n    Conflict between P0 (E -> E plus E) and token plus resolved as reduce\n"
+        ));
+        assert!(report.contains(
+            "\n    Conflict between P1 (E -> E times E) and token plus resolved as reduce\n"
+        ));
+        assert!(report.contains(
+            "\n    Conflict between P1 (E -> E times E) and token times resolved as reduce\n"
+        ));
+        assert!(!report.contains("Unresolved"));
+    }
+}
@@ -0,0 +1,119 @@
+use crate::{
+    automaton::{Automaton, ItemCore, LookAhead},
+    lr0::Lr0Automaton,
+    parsing::tables::TransitionTables,
+    symbolic_grammar::SymbolicGrammar,
+};
+
+/// SLR(1) automaton: the LR(0) automaton, reducing a production only on the tokens that can
+/// follow its head anywhere in the grammar
+pub struct SlrAutomaton {
+    lr0: Lr0Automaton,
+}
+
+impl SlrAutomaton {
+    pub fn compute(grammar: SymbolicGrammar) -> Self {
+        Self {
+            lr0: Lr0Automaton::compute(grammar),
+        }
+    }
+}
+
+impl Automaton for SlrAutomaton {
+    fn grammar(&self) -> &SymbolicGrammar {
+        self.lr0.grammar()
+    }
+
+    fn transitions(&self) -> &TransitionTables {
+        self.lr0.transitions()
+    }
+
+    fn states_count(&self) -> usize {
+        self.lr0.states_count()
+    }
+
+    fn kernel(&self, state: usize) -> Vec<ItemCore> {
+        self.lr0.kernel(state)
+    }
+
+    fn reductions(&self, state: usize) -> Vec<(usize, LookAhead)> {
+        self.lr0
+            .reducing_productions(state)
+            .iter()
+            .map(|production_id| {
+                let lookahead = if *production_id == usize::MAX {
+                    LookAhead {
+                        tokens: Default::default(),
+                        can_eof_follow: true,
+                    }
+                } else {
+                    let grammar = self.grammar();
+                    let head = grammar.get_production(*production_id).unwrap().head();
+                    let follow = grammar.follow(*head);
+                    LookAhead {
+                        tokens: follow.tokens.clone(),
+                        can_eof_follow: follow.eof_follows,
+                    }
+                };
+                (*production_id, lookahead)
+            })
+            .collect()
+    }
+}
+
+impl From<SymbolicGrammar> for SlrAutomaton {
+    fn from(value: SymbolicGrammar) -> Self {
+        Self::compute(value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SlrAutomaton;
+    use crate::{
+        automaton::Automaton,
+        lalr::LalrAutomaton,
+        test_grammars::{accepts, expressions, grammar},
+    };
+
+    #[test]
+    fn slr_accepts_expressions() {
+        let automaton = SlrAutomaton::compute(expressions());
+        let tables = automaton.generate_tables();
+        assert!(tables.conflicts.is_empty());
+        let grammar = automaton.grammar();
+        assert!(accepts(
+            grammar,
+            &tables,
+            &["id", "plus", "id", "times", "id"]
+        ));
+        assert!(!accepts(grammar, &tables, &["id", "plus"]));
+    }
+
+    #[test]
+    fn slr_conflicts_on_lalr_grammar() {
+        // The dragon book grammar of assignments: FOLLOW(R) contains `=`, so SLR(1) cannot decide
+        // between shifting `=` and reducing `R -> L` after an `L`
+        let assignments = || {
+            grammar(
+                "S",
+                &[
+                    ("S", &["L", "eq", "R"]),
+                    ("S", &["R"]),
+                    ("L", &["star", "R"]),
+                    ("L", &["id"]),
+                    ("R", &["L"]),
+                ],
+            )
+        };
+        let tables = SlrAutomaton::compute(assignments()).generate_tables();
+        assert_eq!(tables.conflicts.len(), 1);
+        assert!(tables.conflicts[0].is_shift_reduce());
+        assert!(
+            LalrAutomaton::compute(assignments())
+                .generate_tables()
+                .conflicts
+                .is_empty()
+        );
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{EnrichedGrammar, production::EnrichedProduction};
+use crate::{EnrichedGrammar, precedence::Precedence, production::EnrichedProduction};
 use itertools::Itertools;
 use std::{collections::HashSet, fmt::Display, rc::Rc};
 use syn::Ident;
@@ -27,6 +27,7 @@ pub struct SymbolicProduction {
     production_id: usize,
     head: SymbolicNonTerminal,
     body: Vec<SymbolicSymbol>,
+    precedence: Option<Precedence>,
 }
 
 impl Display for SymbolicProduction {
@@ -58,20 +59,27 @@ impl SymbolicProduction {
         self.body.len()
     }
 
+    pub fn precedence(&self) -> Option<Precedence> {
+        self.precedence
+    }
+
     pub fn special_production(start_symbol: SymbolicNonTerminal) -> Self {
         Self {
             production_id: usize::MAX,
             head: usize::MAX,
             body: vec![SymbolicSymbol::NonTerminal(start_symbol)],
+            precedence: None,
         }
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SymbolicFirstSet {
     pub tokens: HashSet<SymbolicToken>,
     pub nullable: bool,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct SymbolicFollowSet {
     pub tokens: HashSet<SymbolicToken>,
     pub eof_follows: bool,
@@ -85,6 +93,10 @@ pub struct SymbolicGrammar {
     start_symbol: SymbolicNonTerminal,
     special_production: SymbolicProduction,
     productions: Vec<SymbolicProduction>,
+    /// Ids of the productions of each non-terminal
+    productions_by_head: Vec<Vec<usize>>,
+    firsts: Vec<SymbolicFirstSet>,
+    follows: Vec<SymbolicFollowSet>,
 }
 
 impl Display for SymbolicGrammar {
@@ -117,10 +129,18 @@ impl SymbolicGrammar {
         self.productions.get(id)
     }
 
+    pub fn productions(&self) -> &[SymbolicProduction] {
+        &self.productions
+    }
+
+    pub fn start_symbol(&self) -> SymbolicNonTerminal {
+        self.start_symbol
+    }
+
     pub fn get_productions_with_head(&self, head: SymbolicNonTerminal) -> Vec<&SymbolicProduction> {
-        self.productions
+        self.productions_by_head[head]
             .iter()
-            .filter(|prod| prod.head == head)
+            .map(|id| &self.productions[*id])
             .collect()
     }
 
@@ -132,6 +152,37 @@ impl SymbolicGrammar {
         self.non_terminal_count
     }
 
+    pub fn token_name(&self, token: SymbolicToken) -> String {
+        self.enriched_grammar.tokens()[token].ident().to_string()
+    }
+
+    pub fn token_precedence(&self, token: SymbolicToken) -> Option<Precedence> {
+        self.enriched_grammar.tokens()[token].precedence()
+    }
+
+    pub fn non_terminal_name(&self, non_terminal: SymbolicNonTerminal) -> String {
+        if non_terminal == usize::MAX {
+            return "$accept".to_string();
+        }
+        self.enriched_grammar.non_terminals()[non_terminal]
+            .ident()
+            .to_string()
+    }
+
+    pub fn symbol_name(&self, symbol: &SymbolicSymbol) -> String {
+        match symbol {
+            SymbolicSymbol::Token(token) => self.token_name(*token),
+            SymbolicSymbol::NonTerminal(non_terminal) => self.non_terminal_name(*non_terminal),
+        }
+    }
+
+    pub fn production_name(&self, id: usize) -> String {
+        if id == usize::MAX {
+            return "$accept".to_string();
+        }
+        self.enriched_grammar.productions()[id].ident().to_string()
+    }
+
     fn find_symbol(enriched_grammar: &EnrichedGrammar, ident: &Ident) -> Option<SymbolicSymbol> {
         enriched_grammar
             .token_id(ident)
@@ -171,26 +222,48 @@ impl SymbolicGrammar {
                     }
                 })
                 .collect(),
+            precedence: enriched_production.precedence(),
         }
     }
 
-    fn first_set_helper(
-        &self,
-        beta: &[SymbolicSymbol],
-        visited: &mut HashSet<usize>,
-    ) -> SymbolicFirstSet {
-        if beta.is_empty() {
-            return SymbolicFirstSet {
+    /// FIRST set of every non-terminal, grown until no production adds anything
+    fn non_terminal_firsts(
+        non_terminal_count: usize,
+        productions: &[SymbolicProduction],
+    ) -> Vec<SymbolicFirstSet> {
+        let mut firsts = vec![
+            SymbolicFirstSet {
                 tokens: HashSet::new(),
-                nullable: true,
+                nullable: false,
             };
+            non_terminal_count
+        ];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in productions {
+                let body_firsts = Self::sequence_first_set(&firsts, production.body());
+                let head = &mut firsts[*production.head()];
+                if body_firsts.nullable && !head.nullable {
+                    head.nullable = true;
+                    changed = true;
+                }
+                for token in body_firsts.tokens {
+                    changed |= head.tokens.insert(token);
+                }
+            }
         }
+        firsts
+    }
 
+    fn sequence_first_set(
+        firsts: &[SymbolicFirstSet],
+        beta: &[SymbolicSymbol],
+    ) -> SymbolicFirstSet {
         let mut res = SymbolicFirstSet {
             tokens: HashSet::new(),
             nullable: false,
         };
-
         for symbol in beta.iter() {
             match symbol {
                 SymbolicSymbol::Token(token) => {
@@ -198,29 +271,140 @@ impl SymbolicGrammar {
                     return res;
                 }
                 SymbolicSymbol::NonTerminal(non_terminal) => {
-                    let productions = self.get_productions_with_head(*non_terminal);
-                    for prod in productions.into_iter() {
-                        if visited.contains(&prod.id()) {
-                            continue;
-                        }
-                        visited.insert(prod.id());
-                        let body = prod.body();
-                        let firsts = self.first_set_helper(body, visited);
-                        res.tokens.extend(firsts.tokens);
-                        if !firsts.nullable {
-                            return res;
-                        }
+                    let firsts = &firsts[*non_terminal];
+                    res.tokens.extend(firsts.tokens.iter().copied());
+                    if !firsts.nullable {
+                        return res;
                     }
                 }
             }
         }
-
         res.nullable = true;
         res
     }
 
-    pub fn first_set(&self, beta: &[SymbolicSymbol]) -> SymbolicFirstSet {
-        self.first_set_helper(beta, &mut HashSet::new())
+    /// FOLLOW set of every non-terminal, grown until no production adds anything
+    fn non_terminal_follows(
+        start_symbol: SymbolicNonTerminal,
+        firsts: &[SymbolicFirstSet],
+        productions: &[SymbolicProduction],
+    ) -> Vec<SymbolicFollowSet> {
+        let mut follows = vec![
+            SymbolicFollowSet {
+                tokens: HashSet::new(),
+                eof_follows: false,
+            };
+            firsts.len()
+        ];
+        follows[start_symbol].eof_follows = true;
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in productions {
+                for (position, symbol) in production.body().iter().enumerate() {
+                    let SymbolicSymbol::NonTerminal(non_terminal) = symbol else {
+                        continue;
+                    };
+                    let rest_firsts =
+                        Self::sequence_first_set(firsts, &production.body()[position + 1..]);
+                    let head_follows = if rest_firsts.nullable {
+                        follows[production.head].clone()
+                    } else {
+                        SymbolicFollowSet {
+                            tokens: HashSet::new(),
+                            eof_follows: false,
+                        }
+                    };
+                    let follow = &mut follows[*non_terminal];
+                    for token in rest_firsts.tokens.into_iter().chain(head_follows.tokens) {
+                        changed |= follow.tokens.insert(token);
+                    }
+                    if head_follows.eof_follows && !follow.eof_follows {
+                        follow.eof_follows = true;
+                        changed = true;
+                    }
+                }
+            }
+        }
+        follows
+    }
+
+    /// Non-terminals appearing in some sentential form derived from the start symbol
+    pub fn reachable_non_terminals(&self) -> Vec<bool> {
+        let mut reachable = vec![false; self.non_terminal_count];
+        reachable[self.start_symbol] = true;
+        let mut stack = vec![self.start_symbol];
+        while let Some(non_terminal) = stack.pop() {
+            for production in self.get_productions_with_head(non_terminal) {
+                for symbol in production.body() {
+                    if let SymbolicSymbol::NonTerminal(next) = symbol
+                        && !reachable[*next]
+                    {
+                        reachable[*next] = true;
+                        stack.push(*next);
+                    }
+                }
+            }
+        }
+        reachable
+    }
+
+    /// Non-terminals deriving at least one string of tokens
+    pub fn productive_non_terminals(&self) -> Vec<bool> {
+        let mut productive = vec![false; self.non_terminal_count];
+        let mut changed = true;
+        while changed {
+            changed = false;
+            for production in self.productions.iter() {
+                if !productive[production.head]
+                    && production.body().iter().all(|symbol| match symbol {
+                        SymbolicSymbol::Token(_) => true,
+                        SymbolicSymbol::NonTerminal(non_terminal) => productive[*non_terminal],
+                    })
+                {
+                    productive[production.head] = true;
+                    changed = true;
+                }
+            }
+        }
+        productive
+    }
+
+    /// Tokens appearing in the body of some production
+    pub fn used_tokens(&self) -> Vec<bool> {
+        let mut used = vec![false; self.token_count];
+        for symbol in self
+            .productions
+            .iter()
+            .flat_map(|production| production.body())
+        {
+            if let SymbolicSymbol::Token(token) = symbol {
+                used[*token] = true;
+            }
+        }
+        used
+    }
+
+    /// Whether the non-terminal derives the empty string
+    pub fn nullable(&self, non_terminal: SymbolicNonTerminal) -> bool {
+        self.firsts[non_terminal].nullable
+    }
+
+    /// Tokens starting the strings derived from the non-terminal
+    pub fn first(&self, non_terminal: SymbolicNonTerminal) -> &SymbolicFirstSet {
+        &self.firsts[non_terminal]
+    }
+
+    /// Tokens starting the strings derived from the sequence, which is nullable if all of its
+    /// symbols are
+    pub fn first_of_sequence(&self, beta: &[SymbolicSymbol]) -> SymbolicFirstSet {
+        Self::sequence_first_set(&self.firsts, beta)
+    }
+
+    /// Tokens that can come right after the non-terminal in a sentential form, and whether the
+    /// input can end after it
+    pub fn follow(&self, non_terminal: SymbolicNonTerminal) -> &SymbolicFollowSet {
+        &self.follows[non_terminal]
     }
 }
 
@@ -234,7 +418,13 @@ impl From<Rc<EnrichedGrammar>> for SymbolicGrammar {
             .iter()
             .enumerate()
             .map(|(id, prod)| SymbolicGrammar::map_production(&value, id, prod))
-            .collect();
+            .collect::<Vec<_>>();
+        let mut productions_by_head = vec![Vec::new(); non_terminal_count];
+        for production in productions.iter() {
+            productions_by_head[production.head].push(production.production_id);
+        }
+        let firsts = SymbolicGrammar::non_terminal_firsts(non_terminal_count, &productions);
+        let follows = SymbolicGrammar::non_terminal_follows(start_symbol, &firsts, &productions);
         Self {
             enriched_grammar: value,
             token_count,
@@ -242,6 +432,171 @@ impl From<Rc<EnrichedGrammar>> for SymbolicGrammar {
             start_symbol,
             special_production: SymbolicProduction::special_production(start_symbol),
             productions,
+            productions_by_head,
+            firsts,
+            follows,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{SymbolicGrammar, SymbolicNonTerminal, SymbolicSymbol, SymbolicToken};
+    use crate::test_grammars::{expressions, grammar};
+    use std::collections::HashSet;
+
+    fn non_terminal(grammar: &SymbolicGrammar, name: &str) -> SymbolicNonTerminal {
+        (0..grammar.non_terminal_count())
+            .find(|nt| grammar.non_terminal_name(*nt) == name)
+            .expect("unknown non-terminal")
+    }
+
+    fn tokens(grammar: &SymbolicGrammar, names: &[&str]) -> HashSet<SymbolicToken> {
+        (0..grammar.token_count())
+            .filter(|token| names.contains(&grammar.token_name(*token).as_str()))
+            .collect()
+    }
+
+    /// Checks FIRST, nullability and FOLLOW of `name`, FOLLOW containing `$` for the end of input
+    fn assert_sets(
+        grammar: &SymbolicGrammar,
+        name: &str,
+        nullable: bool,
+        first: &[&str],
+        follow: &[&str],
+    ) {
+        let nt = non_terminal(grammar, name);
+        assert_eq!(grammar.nullable(nt), nullable, "NULLABLE({name})");
+        assert_eq!(
+            grammar.first(nt).tokens,
+            tokens(grammar, first),
+            "FIRST({name})"
+        );
+        assert_eq!(
+            grammar.follow(nt).tokens,
+            tokens(grammar, follow),
+            "FOLLOW({name})"
+        );
+        assert_eq!(
+            grammar.follow(nt).eof_follows,
+            follow.contains(&"$"),
+            "$ in FOLLOW({name})"
+        );
+    }
+
+    #[test]
+    fn useless_symbols() {
+        let grammar = grammar(
+            "S",
+            &[
+                ("S", &["a", "A"]),
+                ("S", &["B"]),
+                ("A", &["a"]),
+                ("B", &["b", "B"]),
+                ("C", &["c"]),
+                ("D", &["D", "C"]),
+            ],
+        );
+        let names = |flags: Vec<bool>| {
+            flags
+                .iter()
+                .enumerate()
+                .filter(|(_, flag)| **flag)
+                .map(|(nt, _)| grammar.non_terminal_name(nt))
+                .collect::<Vec<_>>()
+        };
+        // `D` can only be reached from itself
+        assert_eq!(names(grammar.reachable_non_terminals()), ["S", "A", "B"]);
+        // `B` never stops deriving itself, nor does `D`
+        assert_eq!(names(grammar.productive_non_terminals()), ["S", "A", "C"]);
+    }
+
+    #[test]
+    fn sets_of_the_predictive_expression_grammar() {
+        // The dragon book example of FIRST and FOLLOW sets
+        let grammar = grammar(
+            "E",
+            &[
+                ("E", &["T", "Ep"]),
+                ("Ep", &["plus", "T", "Ep"]),
+                ("Ep", &[]),
+                ("T", &["F", "Tp"]),
+                ("Tp", &["times", "F", "Tp"]),
+                ("Tp", &[]),
+                ("F", &["lparen", "E", "rparen"]),
+                ("F", &["id"]),
+            ],
+        );
+        assert_sets(&grammar, "E", false, &["lparen", "id"], &["rparen", "$"]);
+        assert_sets(&grammar, "Ep", true, &["plus"], &["rparen", "$"]);
+        assert_sets(
+            &grammar,
+            "T",
+            false,
+            &["lparen", "id"],
+            &["plus", "rparen", "$"],
+        );
+        assert_sets(&grammar, "Tp", true, &["times"], &["plus", "rparen", "$"]);
+        assert_sets(
+            &grammar,
+            "F",
+            false,
+            &["lparen", "id"],
+            &["plus", "times", "rparen", "$"],
+        );
+    }
+
+    #[test]
+    fn sets_of_the_left_recursive_expression_grammar() {
+        let grammar = expressions();
+        assert_sets(
+            &grammar,
+            "E",
+            false,
+            &["lparen", "id"],
+            &["plus", "rparen", "$"],
+        );
+        assert_sets(
+            &grammar,
+            "T",
+            false,
+            &["lparen", "id"],
+            &["plus", "times", "rparen", "$"],
+        );
+        assert_sets(
+            &grammar,
+            "F",
+            false,
+            &["lparen", "id"],
+            &["plus", "times", "rparen", "$"],
+        );
+    }
+
+    #[test]
+    fn sets_through_nullable_siblings() {
+        let grammar = grammar(
+            "S",
+            &[
+                ("S", &["A", "B", "c"]),
+                ("S", &["B", "S"]),
+                ("A", &["a"]),
+                ("A", &[]),
+                ("B", &["b"]),
+                ("B", &["A"]),
+            ],
+        );
+        assert_sets(&grammar, "S", false, &["a", "b", "c"], &["$"]);
+        assert_sets(&grammar, "A", true, &["a"], &["a", "b", "c"]);
+        assert_sets(&grammar, "B", true, &["a", "b"], &["a", "b", "c"]);
+
+        let [a, b] =
+            ["A", "B"].map(|name| SymbolicSymbol::NonTerminal(non_terminal(&grammar, name)));
+        let firsts = grammar.first_of_sequence(&[a.clone(), b.clone()]);
+        assert!(firsts.nullable);
+        assert_eq!(firsts.tokens, tokens(&grammar, &["a", "b"]));
+        let c = SymbolicSymbol::Token(*tokens(&grammar, &["c"]).iter().next().unwrap());
+        let firsts = grammar.first_of_sequence(&[a, b, c]);
+        assert!(!firsts.nullable);
+        assert_eq!(firsts.tokens, tokens(&grammar, &["a", "b", "c"]));
+    }
+}
@@ -0,0 +1,176 @@
+use crate::{
+    EnrichedGrammar,
+    non_terminal::EnrichedNonTerminal,
+    parsing::{
+        action::{EofAction, TokenAction},
+        tables::ParsingTables,
+    },
+    precedence::{Associativity, Precedence},
+    production::EnrichedBaseProduction,
+    symbolic_grammar::SymbolicGrammar,
+    token::{EnrichedToken, Match},
+};
+use itertools::Itertools;
+use std::rc::Rc;
+use syn::Ident;
+
+fn ident(name: &str) -> Ident {
+    syn::parse_str(name).expect("not an identifier")
+}
+
+/// Builds a grammar from `(name, head, body)` triples: heads are the non-terminals, every other
+/// symbol is a token
+pub fn enriched_grammar(
+    start_symbol: &str,
+    productions: &[(&str, &str, &[&str])],
+) -> EnrichedGrammar {
+    let non_terminals = productions
+        .iter()
+        .map(|(_, head, _)| *head)
+        .unique()
+        .collect_vec();
+    let tokens = productions
+        .iter()
+        .flat_map(|(_, _, body)| body.iter().copied())
+        .filter(|symbol| !non_terminals.contains(symbol))
+        .unique()
+        .collect_vec();
+    EnrichedGrammar::new(
+        None,
+        non_terminals
+            .iter()
+            .map(|nt| EnrichedNonTerminal::new(ident(nt)))
+            .collect(),
+        tokens
+            .iter()
+            .map(|tok| EnrichedToken::new(ident(tok), Match::Literal(tok.to_string())))
+            .collect(),
+        EnrichedNonTerminal::new(ident(start_symbol)),
+        productions
+            .iter()
+            .map(|(name, head, body)| {
+                EnrichedBaseProduction::new(
+                    ident(name),
+                    ident(head),
+                    body.iter().map(|symbol| ident(symbol)).collect(),
+                )
+            })
+            .collect(),
+    )
+}
+
+/// Builds a grammar from `(head, body)` pairs: heads are the non-terminals, every other symbol
+/// is a token. Productions are named `P0`, `P1`, ... in order
+pub fn grammar(start_symbol: &str, productions: &[(&str, &[&str])]) -> SymbolicGrammar {
+    let names = (0..productions.len())
+        .map(|id| format!("P{id}"))
+        .collect_vec();
+    let productions = productions
+        .iter()
+        .zip(names.iter())
+        .map(|((head, body), name)| (name.as_str(), *head, *body))
+        .collect_vec();
+    SymbolicGrammar::from(Rc::new(enriched_grammar(start_symbol, &productions)))
+}
+
+/// Runs the LR parsing algorithm driven by `tables` on the token names in `input`
+pub fn accepts(grammar: &SymbolicGrammar, tables: &ParsingTables, input: &[&str]) -> bool {
+    let token_id = |name: &str| {
+        (0..grammar.token_count())
+            .find(|token| grammar.token_name(*token) == name)
+            .expect("unknown token")
+    };
+    let mut tokens = input.iter().map(|name| token_id(name)).peekable();
+    let mut stack = vec![0];
+    loop {
+        let state = *stack.last().unwrap();
+        let production_id = match tokens.peek() {
+            Some(token) => match tables.token_table[(state, *token)] {
+                Some(TokenAction::Shift(target)) => {
+                    stack.push(target);
+                    tokens.next();
+                    continue;
+                }
+                Some(TokenAction::Reduce(production_id)) => production_id,
+                None => return false,
+            },
+            None => match tables.eof_table[state] {
+                Some(EofAction::Accept) => return true,
+                Some(EofAction::Reduce(production_id)) => production_id,
+                None => return false,
+            },
+        };
+        let production = grammar.get_production(production_id).unwrap();
+        stack.truncate(stack.len() - production.arity());
+        match tables.non_terminal_table[(*stack.last().unwrap(), *production.head())] {
+            Some(target) => stack.push(target),
+            None => return false,
+        }
+    }
+}
+
+/// LR(1) but not LALR(1): the states reached after `a e` and `b e` have the same kernel, merging
+/// them mixes the lookaheads of `E -> e` and `F -> e`
+pub fn lr1_not_lalr() -> SymbolicGrammar {
+    grammar(
+        "S",
+        &[
+            ("S", &["a", "E", "c"]),
+            ("S", &["a", "F", "d"]),
+            ("S", &["b", "F", "c"]),
+            ("S", &["b", "E", "d"]),
+            ("E", &["e"]),
+            ("F", &["e"]),
+        ],
+    )
+}
+
+/// The textbook LALR(1) expression grammar
+pub fn expressions() -> SymbolicGrammar {
+    grammar(
+        "E",
+        &[
+            ("E", &["E", "plus", "T"]),
+            ("E", &["T"]),
+            ("T", &["T", "times", "F"]),
+            ("T", &["F"]),
+            ("F", &["lparen", "E", "rparen"]),
+            ("F", &["id"]),
+        ],
+    )
+}
+
+/// `E -> E plus E | E times E | id`, made unambiguous by `plus` binding less tightly than
+/// `times`, both being left associative
+pub fn ambiguous_arithmetic() -> SymbolicGrammar {
+    let productions = [
+        ("P0", &["E", "plus", "E"][..]),
+        ("P1", &["E", "times", "E"][..]),
+        ("P2", &["id"][..]),
+    ];
+    let tokens = [("plus", Some(0)), ("times", Some(1)), ("id", None)]
+        .into_iter()
+        .map(|(name, level)| {
+            EnrichedToken::new(ident(name), Match::Literal(name.to_string())).with_precedence(
+                level.map(|level| Precedence::new(level, Some(Associativity::Left))),
+            )
+        })
+        .collect();
+    let enriched_grammar = EnrichedGrammar::new(
+        None,
+        vec![EnrichedNonTerminal::new(ident("E"))],
+        tokens,
+        EnrichedNonTerminal::new(ident("E")),
+        productions
+            .iter()
+            .map(|(name, body)| {
+                EnrichedBaseProduction::new(
+                    ident(name),
+                    ident("E"),
+                    body.iter().map(|symbol| ident(symbol)).collect(),
+                )
+            })
+            .collect(),
+    );
+    SymbolicGrammar::from(Rc::new(enriched_grammar))
+}
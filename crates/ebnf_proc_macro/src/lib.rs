@@ -61,12 +61,8 @@ pub fn ebnf(input: TokenStream) -> TokenStream {
             quote!(production!(#ident, #head -> #body, #sem_action);)
         }));
 
-    let res = quote! {
+    quote! {
         #(#items)*
     }
-    .into();
-
-    eprintln!("{res}");
-
-    res
+    .into()
 }
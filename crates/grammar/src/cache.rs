@@ -0,0 +1,245 @@
+//! Parse tables kept across builds: a grammar whose canonical form did not change since the
+//! last build reuses the tables serialized then, instead of building its automaton again
+
+use dyn_grammar::{EnrichedGrammar, automaton::Algorithm, parsing::tables::ParsingTables};
+use itertools::Itertools;
+use std::{
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+/// Set to print whether each grammar hit the cache
+const TRACE_VARIABLE: &str = "SEMASIA_TRACE";
+/// Layout of the cache files, bumped whenever it changes
+const CACHE_FORMAT: usize = 1;
+
+/// Versions of the crates generating the tables and of the cache files, so that the tables cached
+/// by another release are built again
+fn generator() -> String {
+    format!(
+        "grammar {} dyn_grammar {} format {CACHE_FORMAT}",
+        env!("CARGO_PKG_VERSION"),
+        dyn_grammar::VERSION
+    )
+}
+
+/// Every input of the table generation, in a stable textual form: the generator, the algorithm,
+/// the symbols with their precedences and the productions in order
+fn canonical_form(grammar: &EnrichedGrammar, algorithm: Algorithm, generator: &str) -> String {
+    let precedence = |precedence: Option<_>| {
+        precedence
+            .map(|precedence| format!(" [precedence {precedence}]"))
+            .unwrap_or_default()
+    };
+    let mut res = format!(
+        "generator {generator}\nalgorithm {algorithm}\nstart {}\nnon_terminals {}\n",
+        grammar.start_symbol(),
+        grammar.non_terminals().iter().format(" ")
+    );
+    for token in grammar.tokens() {
+        res += &format!("token {token}{}\n", precedence(token.precedence()));
+    }
+    for production in grammar.productions() {
+        res += &format!(
+            "production {production}{}\n",
+            precedence(production.precedence())
+        );
+    }
+    res
+}
+
+/// `target` directory of the build: `CARGO_TARGET_DIR` if set, else the closest one that cargo
+/// created above the manifest of the crate being compiled
+fn target_directory() -> PathBuf {
+    if let Some(target) = std::env::var_os("CARGO_TARGET_DIR") {
+        return target.into();
+    }
+    let manifest_dir = PathBuf::from(std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
+    manifest_dir
+        .ancestors()
+        .map(|dir| dir.join("target"))
+        .find(|target| target.join("CACHEDIR.TAG").is_file())
+        .unwrap_or_else(|| manifest_dir.join("target"))
+}
+
+pub struct TableCache {
+    canonical_form: String,
+    path: PathBuf,
+}
+
+impl TableCache {
+    pub fn new(grammar: &EnrichedGrammar, algorithm: Algorithm) -> Self {
+        Self::in_directory(
+            grammar,
+            algorithm,
+            &generator(),
+            &target_directory().join("semasia").join("table-cache"),
+        )
+    }
+
+    fn in_directory(
+        grammar: &EnrichedGrammar,
+        algorithm: Algorithm,
+        generator: &str,
+        directory: &Path,
+    ) -> Self {
+        let canonical_form = canonical_form(grammar, algorithm, generator);
+        let mut hasher = DefaultHasher::new();
+        canonical_form.hash(&mut hasher);
+        let path = directory.join(format!("{:016x}.tables", hasher.finish()));
+        Self {
+            canonical_form,
+            path,
+        }
+    }
+
+    /// Tables stored for the same canonical grammar. The whole grammar is stored along the
+    /// tables and compared, so that a hash collision is a miss
+    pub fn load(&self) -> Option<ParsingTables> {
+        let contents = std::fs::read_to_string(&self.path).ok()?;
+        let tables = contents
+            .strip_prefix(&self.canonical_form)?
+            .strip_prefix("---\n")?;
+        ParsingTables::deserialize(tables)
+    }
+
+    /// Best effort: a build does not fail because the cache could not be written
+    pub fn store(&self, tables: &ParsingTables) {
+        let Some(directory) = self.path.parent() else {
+            return;
+        };
+        // Written aside then renamed, so that concurrent builds never read a partial file
+        let temporary = self
+            .path
+            .with_extension(format!("tables.{}", std::process::id()));
+        let contents = format!("{}---\n{}", self.canonical_form, tables.serialize());
+        let stored = std::fs::create_dir_all(directory)
+            .and_then(|_| std::fs::write(&temporary, contents))
+            .and_then(|_| std::fs::rename(&temporary, &self.path));
+        if stored.is_err() {
+            let _ = std::fs::remove_file(&temporary);
+        }
+    }
+
+    /// Prints the outcome of the lookup when `SEMASIA_TRACE` is set
+    pub fn trace(&self, grammar: &EnrichedGrammar, hit: bool) {
+        if std::env::var_os(TRACE_VARIABLE).is_some() {
+            eprintln!(
+                "semasia: table cache {} for grammar {} ({})",
+                if hit { "hit" } else { "miss" },
+                grammar.start_symbol(),
+                self.path.display()
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TableCache, generator};
+    use dyn_grammar::{
+        EnrichedGrammar,
+        automaton::Algorithm,
+        non_terminal::EnrichedNonTerminal,
+        parsing::tables::ParsingTables,
+        precedence::{Associativity, Precedence},
+        production::EnrichedBaseProduction,
+        symbolic_grammar::SymbolicGrammar,
+        token::{EnrichedToken, Match},
+    };
+    use proc_macro2::Span;
+    use std::rc::Rc;
+    use syn::Ident;
+
+    fn ident(name: &str) -> Ident {
+        Ident::new(name, Span::call_site())
+    }
+
+    /// `E -> E Plus E | Num`, with `Plus` at the given precedence
+    fn grammar(plus_precedence: Option<Precedence>) -> EnrichedGrammar {
+        let e = EnrichedNonTerminal::new(ident("E"));
+        let tokens = vec![
+            EnrichedToken::new(ident("Plus"), Match::Literal("+".to_string()))
+                .with_precedence(plus_precedence),
+            EnrichedToken::new(ident("Num"), Match::Regex(r"\d+".to_string())),
+        ];
+        let productions = vec![
+            EnrichedBaseProduction::new(
+                ident("Add"),
+                ident("E"),
+                vec![ident("E"), ident("Plus"), ident("E")],
+            ),
+            EnrichedBaseProduction::new(ident("Lit"), ident("E"), vec![ident("Num")]),
+        ];
+        EnrichedGrammar::new(None, vec![e.clone()], tokens, e, productions)
+    }
+
+    fn tables(grammar: EnrichedGrammar) -> ParsingTables {
+        Algorithm::Lalr
+            .compute(SymbolicGrammar::from(Rc::new(grammar)))
+            .generate_tables()
+    }
+
+    fn directory(name: &str) -> std::path::PathBuf {
+        let directory =
+            std::env::temp_dir().join(format!("semasia-table-cache-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        directory
+    }
+
+    #[test]
+    fn stored_tables_are_found_again() {
+        let directory = directory("hit");
+        let original = grammar(None);
+        let cache = TableCache::in_directory(&original, Algorithm::Lalr, &generator(), &directory);
+        assert!(cache.load().is_none());
+
+        let tables = tables(grammar(None));
+        cache.store(&tables);
+        let loaded = TableCache::in_directory(&original, Algorithm::Lalr, &generator(), &directory)
+            .load()
+            .unwrap();
+        assert_eq!(loaded.serialize(), tables.serialize());
+        assert_eq!(loaded.conflicts.len(), 1);
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn any_change_to_the_grammar_or_algorithm_misses() {
+        let directory = directory("miss");
+        let original = grammar(None);
+        let tables = tables(grammar(None));
+        TableCache::in_directory(&original, Algorithm::Lalr, &generator(), &directory)
+            .store(&tables);
+
+        let left = Some(Precedence::new(0, Some(Associativity::Left)));
+        for (grammar, algorithm) in [
+            (grammar(None), Algorithm::Lr1),
+            (grammar(left), Algorithm::Lalr),
+        ] {
+            let cache = TableCache::in_directory(&grammar, algorithm, &generator(), &directory);
+            assert!(cache.load().is_none());
+        }
+        assert!(
+            TableCache::in_directory(&original, Algorithm::Lalr, &generator(), &directory)
+                .load()
+                .is_some()
+        );
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+
+    #[test]
+    fn another_generator_misses() {
+        let directory = directory("generator");
+        let original = grammar(None);
+        let cache = TableCache::in_directory(&original, Algorithm::Lalr, &generator(), &directory);
+        cache.store(&tables(grammar(None)));
+
+        let upgraded =
+            TableCache::in_directory(&original, Algorithm::Lalr, "grammar 0.2.0", &directory);
+        assert_ne!(upgraded.path, cache.path);
+        assert!(upgraded.load().is_none());
+        assert!(cache.load().is_some());
+        std::fs::remove_dir_all(directory).unwrap();
+    }
+}
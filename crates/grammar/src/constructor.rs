@@ -1,9 +1,40 @@
-use dyn_grammar::{EnrichedGrammar, lalr::LalrAutomaton};
-use syn::Ident;
-use std::rc::Rc;
+use crate::{diagnostics::ExpectedConflicts, grammar_options::TableEncoding};
+use dyn_grammar::{
+    EnrichedGrammar,
+    automaton::{Algorithm, Automaton},
+    symbolic_grammar::SymbolicGrammar,
+};
+use std::{cell::OnceCell, rc::Rc};
+use syn::{Ident, Item, LitStr};
 
 pub struct Constructor {
     pub enriched_grammar: Rc<EnrichedGrammar>,
-    pub automaton: LalrAutomaton,
+    pub algorithm: Algorithm,
+    pub table_encoding: TableEncoding,
+    /// Only built when the tables are not cached or something besides the tables is needed
+    pub automaton: OnceCell<Box<dyn Automaton>>,
     pub internal_mod_name: Option<Ident>,
+    pub allow_conflicts: bool,
+    pub expected_conflicts: Option<ExpectedConflicts>,
+    pub compare_algorithms: bool,
+    pub glr: bool,
+    pub bypass_unit_productions: bool,
+    pub report: Option<LitStr>,
+    pub dot: Option<LitStr>,
+    pub railroad: Option<LitStr>,
+    /// `#[merge]` functions, with the non-terminal they merge
+    pub merge_functions: Vec<(Ident, Ident)>,
+    /// Warnings found while extracting the grammar, added to the generated items
+    pub warnings: Vec<Item>,
+}
+
+impl Constructor {
+    pub fn automaton(&self) -> &dyn Automaton {
+        self.automaton
+            .get_or_init(|| {
+                self.algorithm
+                    .compute(SymbolicGrammar::from(self.enriched_grammar.clone()))
+            })
+            .as_ref()
+    }
 }
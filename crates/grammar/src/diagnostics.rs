@@ -0,0 +1,231 @@
+use std::{
+    collections::HashMap,
+    time::{Duration, Instant},
+};
+
+use dyn_grammar::{
+    EnrichedGrammar, parsing::conflict::Conflict, symbolic_grammar::SymbolicGrammar,
+};
+use proc_macro_error::{Diagnostic, Level};
+use proc_macro2::Span;
+use syn::{Ident, Item, Path, parse_quote};
+
+use crate::constructor::Constructor;
+
+/// Time the search for an ambiguous input may take for one conflict, and for all of them, so that
+/// a grammar with many conflicts still compiles quickly
+const COUNTEREXAMPLE_TIME_LIMIT: Duration = Duration::from_millis(500);
+const COUNTEREXAMPLES_BUDGET: Duration = Duration::from_secs(3);
+
+/// Numbers of conflicts a grammar module declares with `#[expect_conflicts(..)]`, which are then
+/// silent as long as the grammar has exactly these
+#[derive(Clone, Copy, Debug)]
+pub struct ExpectedConflicts {
+    pub shift_reduce: usize,
+    pub reduce_reduce: usize,
+    pub span: Span,
+}
+
+/// Checks for symbols that are useless to the grammar, each allowed by an attribute on the
+/// declaration of the symbol
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hygiene {
+    Unreachable,
+    Unproductive,
+    Unused,
+    NoProductions,
+}
+
+impl Hygiene {
+    const ALL: [Self; 4] = [
+        Self::Unreachable,
+        Self::Unproductive,
+        Self::Unused,
+        Self::NoProductions,
+    ];
+
+    pub fn attribute(self) -> &'static str {
+        match self {
+            Self::Unreachable => "allow_unreachable",
+            Self::Unproductive => "allow_unproductive",
+            Self::Unused => "allow_unused",
+            Self::NoProductions => "allow_no_productions",
+        }
+    }
+
+    /// Name of the deprecated constant whose use warns about the symbol
+    fn warning_name(self) -> &'static str {
+        match self {
+            Self::Unreachable => "unreachable_non_terminal",
+            Self::Unproductive => "unproductive_non_terminal",
+            Self::Unused => "unused_token",
+            Self::NoProductions => "non_terminal_without_productions",
+        }
+    }
+
+    pub fn from_attribute(path: &Path) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|hygiene| path.is_ident(hygiene.attribute()))
+    }
+}
+
+/// A warning rustc shows on stable, unlike the warnings of proc-macro-error which only nightly
+/// prints: the use at `span` of a deprecated constant named `name`, whose note is the message
+pub fn warning(name: &str, span: Span, message: &str) -> Item {
+    let constant = Ident::new(name, Span::call_site());
+    let used = Ident::new(name, span);
+    parse_quote! {
+        const _: () = {
+            #[deprecated(note = #message)]
+            #[allow(non_upper_case_globals)]
+            const #constant: () = ();
+            #used
+        };
+    }
+}
+
+impl Constructor {
+    /// Warnings about the declared symbols the parser can never use: non-terminals without
+    /// productions, deriving no string of tokens or unreachable from the start symbol, and tokens
+    /// absent from every production
+    pub fn report_hygiene(
+        enriched_grammar: &EnrichedGrammar,
+        grammar: &SymbolicGrammar,
+        declared_non_terminals: &[Ident],
+        allowed: &HashMap<Ident, Vec<Hygiene>>,
+    ) -> Vec<Item> {
+        let mut warnings = Vec::new();
+        let mut warn = |ident: &Ident, hygiene: Hygiene, message: String| {
+            if allowed
+                .get(ident)
+                .is_some_and(|allowed| allowed.contains(&hygiene))
+            {
+                return;
+            }
+            warnings.push(warning(
+                hygiene.warning_name(),
+                ident.span(),
+                &format!(
+                    "{message}, allow it with #[{}] on `{ident}`",
+                    hygiene.attribute()
+                ),
+            ));
+        };
+
+        let reachable = grammar.reachable_non_terminals();
+        let productive = grammar.productive_non_terminals();
+        let start_symbol = grammar.non_terminal_name(grammar.start_symbol());
+        for ident in declared_non_terminals {
+            let Some(id) = enriched_grammar.non_terminal_id(ident) else {
+                continue;
+            };
+            if grammar.get_productions_with_head(id).is_empty() {
+                warn(
+                    ident,
+                    Hygiene::NoProductions,
+                    format!("`{ident}` has no production, the parser can never build it"),
+                );
+            } else if !productive[id] {
+                warn(
+                    ident,
+                    Hygiene::Unproductive,
+                    format!(
+                        "`{ident}` derives no string of tokens, every production of it goes through a non-terminal that never stops deriving"
+                    ),
+                );
+            }
+            if !reachable[id] {
+                warn(
+                    ident,
+                    Hygiene::Unreachable,
+                    format!("`{ident}` is unreachable from the start symbol `{start_symbol}`"),
+                );
+            }
+        }
+
+        let used = grammar.used_tokens();
+        for (id, token) in enriched_grammar.tokens().iter().enumerate() {
+            let ident = token.ident();
+            if !used[id] {
+                warn(
+                    ident,
+                    Hygiene::Unused,
+                    format!("token `{ident}` is not used by any production"),
+                );
+            }
+        }
+        warnings
+    }
+
+    /// Errors for the conflicts of the tables, or warnings when the grammar allows them
+    pub fn report_conflicts(&self, conflicts: &[Conflict]) -> Vec<Item> {
+        if let Some(expected) = self.expected_conflicts {
+            let shift_reduce = conflicts
+                .iter()
+                .filter(|conflict| conflict.is_shift_reduce())
+                .count();
+            let reduce_reduce = conflicts.len() - shift_reduce;
+            if (shift_reduce, reduce_reduce) == (expected.shift_reduce, expected.reduce_reduce) {
+                return Vec::new();
+            }
+            Diagnostic::spanned(
+                expected.span,
+                Level::Error,
+                format!(
+                    "expected {} shift/reduce and {} reduce/reduce conflict(s), found {shift_reduce} and {reduce_reduce}",
+                    expected.shift_reduce, expected.reduce_reduce
+                ),
+            )
+            .emit();
+        }
+        let mut warnings = Vec::new();
+        let deadline = Instant::now() + COUNTEREXAMPLES_BUDGET;
+        for conflict in conflicts {
+            let mut idents = self
+                .automaton()
+                .conflict_productions(conflict)
+                .into_iter()
+                .map(|id| self.enriched_grammar.productions()[id].ident());
+            let span = idents
+                .next()
+                .map(Ident::span)
+                .unwrap_or_else(Span::call_site);
+
+            if self.allow_conflicts {
+                warnings.push(warning(
+                    "allowed_conflict",
+                    span,
+                    &format!(
+                        "{}, allowed by #[allow_conflicts]",
+                        self.automaton().describe_conflict(conflict)
+                    ),
+                ));
+                continue;
+            }
+            let mut diagnostic = Diagnostic::spanned(
+                span,
+                Level::Error,
+                self.automaton().describe_conflict(conflict),
+            );
+            for other in idents {
+                diagnostic = diagnostic.span_note(
+                    other.span(),
+                    format!("{other} is also involved in the conflict"),
+                );
+            }
+            let time_limit = deadline
+                .saturating_duration_since(Instant::now())
+                .min(COUNTEREXAMPLE_TIME_LIMIT);
+            if let Some(counterexample) = self.automaton().counterexample(conflict, time_limit) {
+                diagnostic = diagnostic.note(counterexample.describe(self.automaton().grammar()));
+            }
+            diagnostic = diagnostic.help(
+                "solve it with #[precedence] and associativity on the productions, or accept it with #[allow_conflicts] or #[expect_conflicts(..)] on the grammar module"
+                    .to_string(),
+            );
+            diagnostic.emit();
+        }
+        warnings
+    }
+}
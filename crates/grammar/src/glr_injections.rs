@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+
+use dyn_grammar::parsing::{
+    action::{EofAction, TokenAction},
+    conflict::{Conflict, ConflictKind, ConflictLookahead},
+    tables::{EofTable, TokenTable},
+};
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{Item, parse_quote};
+
+use crate::constructor::Constructor;
+
+impl Constructor {
+    /// Items a GLR parser needs on top of the deterministic ones: the merge functions and every
+    /// action of the conflicting table entries
+    pub fn glr_items(
+        &self,
+        token_table: &TokenTable,
+        eof_table: &EofTable,
+        conflicts: &[Conflict],
+    ) -> Vec<Item> {
+        vec![
+            self.merge(),
+            self.glr_tables(token_table, eof_table, conflicts),
+        ]
+    }
+
+    fn production_ident(&self, production_id: usize) -> TokenStream {
+        let production = self.enriched_grammar.productions()[production_id].ident();
+        quote!(ProductionName::#production)
+    }
+
+    fn merge(&self) -> Item {
+        let non_terminals = self.merge_functions.iter().map(|(nt, _)| nt);
+        let functions = self.merge_functions.iter().map(|(_, function)| function);
+        parse_quote! {
+            impl parser::Merge for NonTerminal {
+                fn merge(self, other: Self) -> Result<Self, (Self, Self)> {
+                    match (self, other) {
+                        #((Self::#non_terminals(first), Self::#non_terminals(second)) => {
+                            Ok(Self::#non_terminals(#functions(first, second)))
+                        })*
+                        (first, second) => Err((first, second)),
+                    }
+                }
+            }
+        }
+    }
+
+    fn glr_tables(
+        &self,
+        token_table: &TokenTable,
+        eof_table: &EofTable,
+        conflicts: &[Conflict],
+    ) -> Item {
+        // Conflicting entries hold the action kept in the table, then the discarded ones
+        let mut token_cells: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        let mut eof_cells: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        for conflict in conflicts {
+            let discarded = match conflict.kind() {
+                ConflictKind::ShiftReduce { reduce, .. } => *reduce,
+                ConflictKind::ReduceReduce { other_reduce, .. } => *other_reduce,
+            };
+            let state = conflict.state();
+            match conflict.lookahead() {
+                ConflictLookahead::Token(token) => {
+                    let kept = &token_table.table[state][*token];
+                    token_cells
+                        .entry((state, *token))
+                        .or_insert_with(|| kept.iter().cloned().collect())
+                        .push(TokenAction::Reduce(discarded))
+                }
+                ConflictLookahead::Eof => eof_cells
+                    .entry(state)
+                    .or_insert_with(|| eof_table.table[state].iter().cloned().collect())
+                    .push(EofAction::Reduce(discarded)),
+            }
+        }
+
+        let token_patts = token_cells.into_iter().map(|((state, token), actions)| {
+            let actions = actions.into_iter().map(|action| match action {
+                TokenAction::Shift(target) => quote!(parser::TokenAction::Shift(#target)),
+                TokenAction::Reduce(production) => {
+                    let production = self.production_ident(production);
+                    quote!(parser::TokenAction::Reduce(#production))
+                }
+            });
+            quote!((#state, #token) => vec![#(#actions),*])
+        });
+        let eof_patts = eof_cells.into_iter().map(|(state, actions)| {
+            let actions = actions.into_iter().map(|action| match action {
+                EofAction::Reduce(production) => {
+                    let production = self.production_ident(production);
+                    quote!(parser::EofAction::Reduce(#production))
+                }
+                EofAction::Accept => quote!(parser::EofAction::Accept),
+            });
+            quote!(#state => vec![#(#actions),*])
+        });
+
+        parse_quote! {
+            impl parser::GlrTables<NonTerminal, Token, ProductionName> for Tables {
+                fn query_token_actions(current_state: usize, current_token: &Token) -> Vec<parser::TokenAction<ProductionName>> {
+                    match (current_state, current_token.id()) {
+                        #(#token_patts,)*
+                        _ => <Self as parser::Tables<NonTerminal, Token, ProductionName>>::query_token_table(current_state, current_token).into_iter().collect(),
+                    }
+                }
+                fn query_eof_actions(current_state: usize) -> Vec<parser::EofAction<ProductionName>> {
+                    match current_state {
+                        #(#eof_patts,)*
+                        _ => <Self as parser::Tables<NonTerminal, Token, ProductionName>>::query_eof_table(current_state).into_iter().collect(),
+                    }
+                }
+            }
+        }
+    }
+}
@@ -1,9 +1,13 @@
-use std::{collections::HashSet, rc::Rc};
+use std::{
+    cell::OnceCell,
+    collections::{HashMap, HashSet},
+    rc::Rc,
+};
 
 use dyn_grammar::{
     EnrichedGrammar,
-    lalr::LalrAutomaton,
     non_terminal::EnrichedNonTerminal,
+    precedence::{Associativity, Precedence},
     production::EnrichedBaseProduction,
     symbolic_grammar::{self, SymbolicGrammar},
     token::{EnrichedToken, Match},
@@ -12,20 +16,42 @@ use ebnf_parser::EbnfProduction;
 use itertools::Itertools;
 use proc_macro_error::{emit_call_site_error, emit_call_site_warning, emit_error};
 use syn::{
-    Attribute, Ident, Item, ItemEnum, ItemStruct, ItemType, ItemUse, LitStr, Meta, Type, UseGroup,
-    UseTree,
+    Attribute, Expr, ExprLit, Ident, Item, ItemEnum, ItemFn, ItemMacro, ItemStruct, ItemType,
+    ItemUse, Lit, LitInt, LitStr, Meta, MetaNameValue, ReturnType, Token, Type, TypePath, UseGroup,
+    UseTree, punctuated::Punctuated, spanned::Spanned,
 };
 
-use crate::constructor::Constructor;
+use crate::{
+    constructor::Constructor,
+    diagnostics::{ExpectedConflicts, Hygiene},
+    grammar_options::GrammarOptions,
+};
 
 impl Constructor {
-    pub fn extract(items: &mut [Item], internal_mod_name: Option<Ident>) -> Self {
+    pub fn extract(
+        attrs: &mut Vec<Attribute>,
+        items: &mut [Item],
+        options: GrammarOptions,
+    ) -> Self {
+        let allow_conflicts = Self::extract_allow_conflicts(attrs);
+        let expected_conflicts = Self::extract_expected_conflicts(attrs);
+        if let Some(expected) = expected_conflicts
+            && allow_conflicts
+        {
+            emit_error!(
+                expected.span,
+                "#[expect_conflicts(..)] and #[allow_conflicts] cannot be used together"
+            );
+        }
+
         let mut tokens = Vec::new();
         let mut non_terminals = Vec::new();
         let mut ebnf_extra_non_terminals = HashSet::new();
         let mut productions = Vec::new();
         let mut start_symbol = None;
         let mut compiler_ctx = None;
+        let mut merge_functions = Vec::new();
+        let mut allowed_hygiene = HashMap::new();
 
         for item in items.iter_mut() {
             if let Some(ctx) = Self::extract_context(item) {
@@ -37,7 +63,26 @@ impl Constructor {
                     panic!();
                 }
                 compiler_ctx = Some(ctx);
+            } else if let Some(token) = Self::extract_error_token(item) {
+                if tokens
+                    .iter()
+                    .any(|token: &EnrichedToken| *token.match_string() == Match::Error)
+                {
+                    emit_error!(
+                        token.ident().span(),
+                        "error token declared for the second time here"
+                    );
+                }
+                if options.glr {
+                    emit_error!(
+                        token.ident().span(),
+                        "GLR parsers don't recover from syntax errors with an error token"
+                    );
+                }
+                allowed_hygiene.insert(token.ident().clone(), Self::extract_allowed_hygiene(item));
+                tokens.push(token);
             } else if let Some(token) = Self::extract_token(item) {
+                allowed_hygiene.insert(token.ident().clone(), Self::extract_allowed_hygiene(item));
                 tokens.push(token);
             } else if let Some((non_terminal, is_start)) = Self::extract_non_terminal(item) {
                 if is_start {
@@ -49,9 +94,17 @@ impl Constructor {
                     }
                     start_symbol = Some(non_terminal.clone());
                 }
+                allowed_hygiene.insert(
+                    non_terminal.ident().clone(),
+                    Self::extract_allowed_hygiene(item),
+                );
                 non_terminals.push(non_terminal);
             } else if let Some(production) = Self::extract_production(item) {
-                productions.push(production);
+                productions.push(
+                    production
+                        .with_precedence(Self::extract_precedence(item))
+                        .with_precedence_token(Self::extract_precedence_token(item)),
+                );
             } else if let Some(ebnf) = Self::extract_ebnf_production(item) {
                 let extra_prods = ebnf.compile().0.into_iter().map(Into::into).collect_vec();
                 let extra_nts = extra_prods
@@ -61,6 +114,20 @@ impl Constructor {
                     .map(EnrichedNonTerminal::new);
                 ebnf_extra_non_terminals.extend(extra_nts);
                 productions.extend(extra_prods);
+            } else if let Some(merge_function) = Self::extract_merge_function(item) {
+                merge_functions.push(merge_function);
+            }
+        }
+
+        Self::check_merge_functions(&merge_functions, &non_terminals, options.glr);
+        Self::check_precedence_tokens(&productions, &tokens);
+        // Tokens standing for a precedence level, like `UMinus`, are not expected in productions
+        for production in productions.iter() {
+            if let Some(allowed) = production
+                .precedence_token()
+                .and_then(|token| allowed_hygiene.get_mut(token))
+            {
+                allowed.push(Hygiene::Unused);
             }
         }
 
@@ -78,6 +145,10 @@ impl Constructor {
             non_terminals[0].clone()
         });
 
+        let declared_non_terminals = non_terminals
+            .iter()
+            .map(|nt| nt.ident().clone())
+            .collect_vec();
         non_terminals.extend(ebnf_extra_non_terminals);
 
         let enriched_grammar = Rc::new(EnrichedGrammar::new(
@@ -89,13 +160,171 @@ impl Constructor {
         ));
 
         let sym_grammar = SymbolicGrammar::from(enriched_grammar.clone());
-
-        let automaton = LalrAutomaton::compute(sym_grammar);
+        let warnings = Self::report_hygiene(
+            &enriched_grammar,
+            &sym_grammar,
+            &declared_non_terminals,
+            &allowed_hygiene,
+        );
 
         Self {
             enriched_grammar,
-            automaton,
-            internal_mod_name,
+            algorithm: options.algorithm,
+            table_encoding: options.table_encoding,
+            automaton: OnceCell::new(),
+            internal_mod_name: options.internal_mod_name,
+            allow_conflicts,
+            expected_conflicts,
+            compare_algorithms: options.compare_algorithms,
+            glr: options.glr,
+            bypass_unit_productions: options.bypass_unit_productions,
+            report: options.report,
+            dot: options.dot,
+            railroad: options.railroad,
+            merge_functions,
+            warnings,
+        }
+    }
+
+    fn extract_allow_conflicts(attrs: &mut Vec<Attribute>) -> bool {
+        let Some(id) = attrs.iter().position(
+            |attr| matches!(&attr.meta, Meta::Path(path) if path.is_ident("allow_conflicts")),
+        ) else {
+            return false;
+        };
+        attrs.remove(id);
+        true
+    }
+
+    /// Removes `#[expect_conflicts(shift_reduce = N, reduce_reduce = M)]` from the grammar module,
+    /// a missing count meaning no conflict of that kind
+    fn extract_expected_conflicts(attrs: &mut Vec<Attribute>) -> Option<ExpectedConflicts> {
+        let id = attrs
+            .iter()
+            .position(|attr| attr.path().is_ident("expect_conflicts"))?;
+        let attr = attrs.remove(id);
+        let mut expected = ExpectedConflicts {
+            shift_reduce: 0,
+            reduce_reduce: 0,
+            span: attr.span(),
+        };
+        let counts = match &attr.meta {
+            Meta::List(_) => attr
+                .parse_args_with(Punctuated::<MetaNameValue, Token![,]>::parse_terminated)
+                .unwrap_or_else(|err| {
+                    emit_error!(err.span(), "{}", err);
+                    Punctuated::new()
+                }),
+            _ => {
+                emit_error!(
+                    attr.span(),
+                    "expected #[expect_conflicts(shift_reduce = N, reduce_reduce = M)]"
+                );
+                Punctuated::new()
+            }
+        };
+        for count in counts {
+            let value = match &count.value {
+                Expr::Lit(ExprLit {
+                    lit: Lit::Int(value),
+                    ..
+                }) => value.base10_parse().unwrap_or_else(|err| {
+                    emit_error!(value.span(), "{}", err);
+                    0
+                }),
+                value => {
+                    emit_error!(value.span(), "expected a number of conflicts");
+                    0
+                }
+            };
+            if count.path.is_ident("shift_reduce") {
+                expected.shift_reduce = value;
+            } else if count.path.is_ident("reduce_reduce") {
+                expected.reduce_reduce = value;
+            } else {
+                emit_error!(
+                    count.path.span(),
+                    "unknown kind of conflict, expected `shift_reduce` or `reduce_reduce`"
+                );
+            }
+        }
+        Some(expected)
+    }
+
+    /// Removes the attributes allowing hygiene warnings from a symbol declaration
+    fn extract_allowed_hygiene(item: &mut Item) -> Vec<Hygiene> {
+        let Some((attrs, _)) = Self::extract_info(item) else {
+            return Vec::new();
+        };
+        let mut allowed = Vec::new();
+        attrs.retain(|attr| match &attr.meta {
+            Meta::Path(path) => match Hygiene::from_attribute(path) {
+                Some(hygiene) => {
+                    allowed.push(hygiene);
+                    false
+                }
+                None => true,
+            },
+            _ => true,
+        });
+        allowed
+    }
+
+    fn extract_merge_function(item: &mut Item) -> Option<(Ident, Ident)> {
+        let Item::Fn(ItemFn { attrs, sig, .. }) = item else {
+            return None;
+        };
+        let id = attrs
+            .iter()
+            .position(|attr| matches!(&attr.meta, Meta::Path(path) if path.is_ident("merge")))?;
+        attrs.remove(id);
+        let non_terminal = match &sig.output {
+            ReturnType::Type(_, ty) => match ty.as_ref() {
+                Type::Path(TypePath { path, .. }) => path.get_ident().cloned(),
+                _ => None,
+            },
+            ReturnType::Default => None,
+        };
+        let Some(non_terminal) = non_terminal else {
+            emit_error!(
+                sig.span(),
+                "a #[merge] function has to return the non-terminal it merges"
+            );
+            return None;
+        };
+        Some((non_terminal, sig.ident.clone()))
+    }
+
+    fn check_merge_functions(
+        merge_functions: &[(Ident, Ident)],
+        non_terminals: &[EnrichedNonTerminal],
+        glr: bool,
+    ) {
+        for (id, (non_terminal, function)) in merge_functions.iter().enumerate() {
+            if !glr {
+                emit_error!(
+                    function.span(),
+                    "#[merge] functions are only used by GLR parsers";
+                    help = "generate one with #[grammar(glr = true)]"
+                );
+            }
+            if !non_terminals.iter().any(|nt| nt.ident() == non_terminal) {
+                emit_error!(
+                    non_terminal.span(),
+                    "`{}` is not a non-terminal",
+                    non_terminal
+                );
+            }
+            if merge_functions[..id]
+                .iter()
+                .any(|(other, _)| other == non_terminal)
+            {
+                emit_error!(
+                    function.span(),
+                    "second #[merge] function for `{}`",
+                    non_terminal
+                );
+            }
         }
     }
 
@@ -137,6 +366,19 @@ impl Constructor {
         Some(ident.clone())
     }
 
+    /// Removes `#[error_token]` from the declaration of the token the parser shifts to recover
+    /// from syntax errors
+    fn extract_error_token(item: &mut Item) -> Option<EnrichedToken> {
+        let (attrs, ident) = Self::extract_info(item)?;
+        let id = attrs.iter().position(
+            |attr| matches!(&attr.meta, Meta::Path(path) if path.is_ident("error_token")),
+        )?;
+        attrs.remove(id);
+        Some(EnrichedToken::new(ident, Match::Error))
+    }
+
+    /// Reads `#[token("lit")]` or `#[token(regex = "re")]`, optionally followed by
+    /// `precedence = <level>` and `assoc = "left" | "right"`
     fn extract_token(item: &mut Item) -> Option<EnrichedToken> {
         let (attrs, ident) = Self::extract_info(item)?;
         let mut res = None;
@@ -144,8 +386,8 @@ impl Constructor {
             if !attr.path().is_ident("token") {
                 return true;
             }
-            let match_string = attr.parse_args_with(|input: syn::parse::ParseStream| {
-                if input.peek(syn::Ident) && input.peek2(syn::Token![=]) {
+            let parsed = attr.parse_args_with(|input: syn::parse::ParseStream| {
+                let match_string = if input.peek(syn::Ident) && input.peek2(syn::Token![=]) {
                     let regex_ident: Ident = input.parse()?;
                     if regex_ident != "regex" {
                         return Err(syn::Error::new(
@@ -155,18 +397,63 @@ impl Constructor {
                     }
                     input.parse::<syn::Token![=]>()?;
                     let regex: LitStr = input.parse()?;
-                    Ok(Match::Regex(regex.value()))
+                    Match::Regex(regex.value())
                 } else {
-                    Ok(Match::Literal(input.parse::<LitStr>()?.value()))
+                    Match::Literal(input.parse::<LitStr>()?.value())
+                };
+                let mut level = None;
+                let mut associativity = None;
+                while !input.is_empty() {
+                    input.parse::<syn::Token![,]>()?;
+                    let key: Ident = input.parse()?;
+                    input.parse::<syn::Token![=]>()?;
+                    if key == "precedence" {
+                        level = Some(input.parse::<LitInt>()?.base10_parse::<usize>()?);
+                    } else if key == "assoc" {
+                        let value: LitStr = input.parse()?;
+                        associativity = Some(match value.value().as_str() {
+                            "left" => Associativity::Left,
+                            "right" => Associativity::Right,
+                            _ => {
+                                return Err(syn::Error::new(
+                                    value.span(),
+                                    "expected \"left\" or \"right\"",
+                                ));
+                            }
+                        });
+                    } else {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            "expected `precedence` or `assoc`",
+                        ));
+                    }
                 }
+                let precedence = match (level, associativity) {
+                    (Some(level), associativity) => Some(Precedence::new(level, associativity)),
+                    (None, Some(_)) => {
+                        return Err(syn::Error::new(
+                            attr.span(),
+                            "associativity can only be declared together with a precedence",
+                        ));
+                    }
+                    (None, None) => None,
+                };
+                Ok((match_string, precedence))
             });
-            if let Ok(match_string) = match_string {
-                res = Some(match_string);
-                return false;
+            match parsed {
+                Ok(parsed) => {
+                    res = Some(parsed);
+                    false
+                }
+                Err(err) => {
+                    emit_error!(err.span(), "{}", err);
+                    true
+                }
             }
-            true
         });
-        res.map(|match_string| EnrichedToken::new(ident, match_string.into()))
+        res.map(|(match_string, precedence)| {
+            EnrichedToken::new(ident, match_string).with_precedence(precedence)
+        })
     }
 
     fn extract_non_terminal(item: &mut Item) -> Option<(EnrichedNonTerminal, bool)> {
@@ -195,6 +482,117 @@ impl Constructor {
         Some((EnrichedNonTerminal::new(ident), is_start))
     }
 
+    fn extract_precedence(item: &mut Item) -> Option<Precedence> {
+        let Item::Macro(ItemMacro { attrs, .. }) = item else {
+            return None;
+        };
+        let mut level = None;
+        let mut associativity = None;
+        attrs.retain(|attr| {
+            if attr.path().is_ident("precedence") {
+                let parsed_level = match &attr.meta {
+                    Meta::NameValue(MetaNameValue {
+                        value:
+                            Expr::Lit(ExprLit {
+                                lit: Lit::Int(lit), ..
+                            }),
+                        ..
+                    }) => lit.base10_parse::<usize>(),
+                    Meta::List(list) => list
+                        .parse_args::<LitInt>()
+                        .and_then(|lit| lit.base10_parse::<usize>()),
+                    _ => Err(syn::Error::new(
+                        attr.span(),
+                        "expected #[precedence = <level>] or #[precedence(<level>)]",
+                    )),
+                };
+                match parsed_level {
+                    Ok(parsed_level) => level = Some(parsed_level),
+                    Err(err) => emit_error!(err.span(), "{}", err),
+                }
+                return false;
+            }
+            let parsed_associativity = if attr.path().is_ident("left_associative") {
+                Associativity::Left
+            } else if attr.path().is_ident("right_associative") {
+                Associativity::Right
+            } else {
+                return true;
+            };
+            if associativity.is_some() {
+                emit_error!(
+                    attr.span(),
+                    "associativity declared for the second time here"
+                );
+            }
+            associativity = Some((parsed_associativity, attr.path().span()));
+            false
+        });
+        match (level, associativity) {
+            (Some(level), associativity) => Some(Precedence::new(
+                level,
+                associativity.map(|(associativity, _)| associativity),
+            )),
+            (None, Some((_, span))) => {
+                emit_error!(
+                    span,
+                    "associativity can only be declared together with #[precedence]"
+                );
+                None
+            }
+            (None, None) => None,
+        }
+    }
+
+    /// Removes `#[prec(Token)]` from a production, giving it the precedence of the token
+    fn extract_precedence_token(item: &mut Item) -> Option<Ident> {
+        let Item::Macro(ItemMacro { attrs, .. }) = item else {
+            return None;
+        };
+        let mut res = None;
+        attrs.retain(|attr| {
+            if !attr.path().is_ident("prec") {
+                return true;
+            }
+            match attr.parse_args::<Ident>() {
+                Ok(token) => res = Some(token),
+                Err(err) => emit_error!(err.span(), "expected #[prec(<token>)]"),
+            }
+            false
+        });
+        res
+    }
+
+    /// Checks that the tokens named by `#[prec(..)]` exist and have a precedence
+    fn check_precedence_tokens(productions: &[EnrichedBaseProduction], tokens: &[EnrichedToken]) {
+        for production in productions {
+            let Some(precedence_token) = production.precedence_token() else {
+                continue;
+            };
+            if production.precedence().is_some() {
+                emit_error!(
+                    precedence_token.span(),
+                    "{} already has a #[precedence]",
+                    production.ident()
+                );
+            }
+            match tokens.iter().find(|tok| tok.ident() == precedence_token) {
+                Some(token) if token.precedence().is_none() => emit_error!(
+                    precedence_token.span(),
+                    "`{}` has no precedence",
+                    precedence_token;
+                    help = "declare one with #[token(.., precedence = <level>)]"
+                ),
+                Some(_) => {}
+                None => emit_error!(
+                    precedence_token.span(),
+                    "`{}` is not a token",
+                    precedence_token
+                ),
+            }
+        }
+    }
+
     fn extract_production(item: &mut Item) -> Option<EnrichedBaseProduction> {
         match item {
             Item::Macro(mac) if mac.mac.path.is_ident("production") => mac
@@ -229,13 +627,13 @@ impl Constructor {
                             .collect(),
                         _ => panic!("type must be a unit, a single type or a tuple"),
                     };
-                    let res = Ok(EnrichedBaseProduction::new(name, head, body));
+                    let production = EnrichedBaseProduction::new(name, head, body);
                     if input.is_empty() {
-                        return res;
+                        return Ok(production.with_default_action(true));
                     }
                     input.parse::<syn::Token![,]>()?;
                     input.parse::<syn::Expr>()?;
-                    res
+                    Ok(production)
                 })
                 .ok(),
             _ => None,
@@ -0,0 +1,121 @@
+use dyn_grammar::automaton::Algorithm;
+use std::str::FromStr;
+use syn::{Ident, LitBool, LitStr, Token, parse::Parse};
+
+/// How the generated `Tables` encode the parse tables
+#[derive(Clone, Copy, Default, PartialEq, Eq)]
+pub enum TableEncoding {
+    /// One `match` arm per non-empty entry
+    #[default]
+    Match,
+    /// Comb vectors of shared rows, with a default reduction per row
+    Compressed,
+}
+
+impl FromStr for TableEncoding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "match" => Ok(Self::Match),
+            "compressed" => Ok(Self::Compressed),
+            other => Err(format!(
+                "unknown table encoding \"{other}\", expected one of \"match\", \"compressed\""
+            )),
+        }
+    }
+}
+
+/// Arguments of the `#[grammar(...)]` attribute: an optional bare identifier naming the module
+/// the generated items are put in, followed by `key = value` options
+#[derive(Default)]
+pub struct GrammarOptions {
+    pub internal_mod_name: Option<Ident>,
+    pub algorithm: Algorithm,
+    pub table_encoding: TableEncoding,
+    /// Generate an `ALGORITHM_COMPARISON` constant describing the grammar's conflicts with each
+    /// construction algorithm
+    pub compare_algorithms: bool,
+    /// Generate a GLR parser, keeping every conflicting action
+    pub glr: bool,
+    /// Skip the reductions of the unit productions without a semantic action in the tables
+    pub bypass_unit_productions: bool,
+    /// File to write the description of the automaton to, relative to the crate's manifest
+    pub report: Option<LitStr>,
+    /// File to write the Graphviz description of the automaton to, relative to the crate's manifest
+    pub dot: Option<LitStr>,
+    /// Directory to write an SVG railroad diagram of each non-terminal to, relative to the
+    /// crate's manifest
+    pub railroad: Option<LitStr>,
+}
+
+impl Parse for GrammarOptions {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let mut options = Self::default();
+        let mut bypass_span = None;
+        while !input.is_empty() {
+            let key: Ident = input.parse()?;
+            if input.peek(Token![=]) {
+                input.parse::<Token![=]>()?;
+                match key.to_string().as_str() {
+                    "algorithm" => {
+                        let value: LitStr = input.parse()?;
+                        options.algorithm = value
+                            .value()
+                            .parse()
+                            .map_err(|err| syn::Error::new(value.span(), err))?;
+                    }
+                    "tables" => {
+                        let value: LitStr = input.parse()?;
+                        options.table_encoding = value
+                            .value()
+                            .parse()
+                            .map_err(|err| syn::Error::new(value.span(), err))?;
+                    }
+                    "compare_algorithms" => {
+                        options.compare_algorithms = input.parse::<LitBool>()?.value;
+                    }
+                    "glr" => {
+                        options.glr = input.parse::<LitBool>()?.value;
+                    }
+                    "bypass_unit_productions" => {
+                        options.bypass_unit_productions = input.parse::<LitBool>()?.value;
+                        bypass_span = Some(key.span());
+                    }
+                    "report" => {
+                        options.report = Some(input.parse()?);
+                    }
+                    "dot" => {
+                        options.dot = Some(input.parse()?);
+                    }
+                    "railroad" => {
+                        options.railroad = Some(input.parse()?);
+                    }
+                    _ => {
+                        return Err(syn::Error::new(
+                            key.span(),
+                            format!("unknown grammar option `{key}`"),
+                        ));
+                    }
+                }
+            } else if options.internal_mod_name.is_none() {
+                options.internal_mod_name = Some(key);
+            } else {
+                return Err(syn::Error::new(
+                    key.span(),
+                    "internal module name declared for the second time here",
+                ));
+            }
+            if !input.is_empty() {
+                input.parse::<Token![,]>()?;
+            }
+        }
+        if let Some(span) = bypass_span.filter(|_| options.glr && options.bypass_unit_productions) {
+            return Err(syn::Error::new(
+                span,
+                "GLR parsers can't bypass unit productions, they follow every reduction",
+            ));
+        }
+        Ok(options)
+    }
+}
@@ -1,38 +1,99 @@
 use dyn_grammar::{
     EnrichedGrammar,
-    parsing::tables::{EofTable, NonTerminalTable, TokenTable},
+    automaton::Algorithm,
+    parsing::{
+        compressed::{CompressedAction, CompressedTable, CompressedTables},
+        tables::{EofTable, NonTerminalTable, ParsingTables, TokenTable},
+        unit_productions::UnitChains,
+    },
     production::EnrichedProduction,
+    railroad::railroad_diagrams,
+    symbolic_grammar::SymbolicGrammar,
 };
 use itertools::Itertools;
 use proc_macro::Span;
-use quote::quote;
-use syn::{Ident, Item, parse_quote};
+use proc_macro_error::emit_error;
+use proc_macro2::TokenStream;
+use quote::{ToTokens, quote};
+use syn::{Ident, Item, LitStr, parse_quote};
 
-use crate::Constructor;
+use crate::{Constructor, cache::TableCache, grammar_options::TableEncoding};
 
 impl Constructor {
     pub fn inject_items(&self, items: &mut Vec<Item>) {
-        let (token_table, eof_table, non_terminal_table) = self.automaton.generate_tables();
-        eprintln!("{token_table}");
-        eprintln!("{non_terminal_table}");
+        let mut tables = self.tables();
+        let unit_chains = if self.bypass_unit_productions {
+            let productions = self.enriched_grammar.productions();
+            tables.bypass_unit_productions(
+                &SymbolicGrammar::from(self.enriched_grammar.clone()),
+                |production_id| productions[production_id].default_action(),
+            )
+        } else {
+            UnitChains::new()
+        };
+        // the GLR parser follows every action of a state, so it can't take default reductions
+        let compressed_tables = (self.table_encoding == TableEncoding::Compressed)
+            .then(|| CompressedTables::compress(&tables, !self.glr));
+        let ParsingTables {
+            token_table,
+            eof_table,
+            non_terminal_table,
+            conflicts,
+            immediate_reductions,
+        } = tables;
+        if let Some(path) = self.report.as_ref() {
+            Self::write_output(path, "report", self.automaton().report());
+        }
+        if let Some(path) = self.dot.as_ref() {
+            Self::write_output(path, "Graphviz description", self.automaton().to_dot());
+        }
+        if let Some(directory) = self.railroad.as_ref() {
+            for (non_terminal, svg) in railroad_diagrams(&self.enriched_grammar) {
+                Self::write_output(
+                    &LitStr::new(
+                        &format!("{}/{non_terminal}.svg", directory.value()),
+                        directory.span(),
+                    ),
+                    "railroad diagram",
+                    svg,
+                );
+            }
+        }
+
+        let mut items_to_add = self.warnings.clone();
+        let glr_items = if self.glr {
+            self.glr_items(&token_table, &eof_table, &conflicts)
+        } else {
+            items_to_add.extend(self.report_conflicts(&conflicts));
+            Vec::new()
+        };
 
-        let mut items_to_add = Vec::new();
         items_to_add.extend(Self::uses());
         items_to_add.extend(self.token_enum());
         items_to_add.extend(self.non_terminal_enum());
         items_to_add.extend(self.production_enum());
         items_to_add.push(self.compiler_context());
-        items_to_add.extend(Self::match_tables(
-            &self.enriched_grammar,
-            token_table,
-            eof_table,
-            non_terminal_table,
-        ));
+        items_to_add.extend(match compressed_tables {
+            Some(compressed_tables) => Self::compressed_tables(
+                &self.enriched_grammar,
+                &token_table,
+                &immediate_reductions,
+                &unit_chains,
+                compressed_tables,
+            ),
+            None => Self::match_tables(
+                &self.enriched_grammar,
+                token_table,
+                eof_table,
+                non_terminal_table,
+                &immediate_reductions,
+                &unit_chains,
+            ),
+        });
+        items_to_add.extend(glr_items);
         items_to_add.push(self.parser());
-
-        for item in items_to_add.iter() {
-            println!("------------------------------");
-            println!("{}", quote!(#item));
+        if self.compare_algorithms {
+            items_to_add.push(self.algorithm_comparison());
         }
 
         match self.internal_mod_name.as_ref() {
@@ -49,6 +110,44 @@ impl Constructor {
         }
     }
 
+    /// Tables of the grammar, read from the cache when an identical grammar was built before
+    fn tables(&self) -> ParsingTables {
+        let cache = TableCache::new(&self.enriched_grammar, self.algorithm);
+        let cached = cache.load();
+        cache.trace(&self.enriched_grammar, cached.is_some());
+        cached.unwrap_or_else(|| {
+            let tables = self.automaton().generate_tables();
+            cache.store(&tables);
+            tables
+        })
+    }
+
+    /// Writes a build output to `path`, relative to the manifest of the crate being compiled
+    fn write_output(path: &LitStr, what: &str, contents: String) {
+        let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap_or_default();
+        let full_path = std::path::Path::new(&manifest_dir).join(path.value());
+        let written = full_path
+            .parent()
+            .map_or(Ok(()), std::fs::create_dir_all)
+            .and_then(|_| std::fs::write(&full_path, contents));
+        if let Err(err) = written {
+            emit_error!(
+                path.span(),
+                "could not write the {} to {}: {}",
+                what,
+                full_path.display(),
+                err
+            );
+        }
+    }
+
+    fn algorithm_comparison(&self) -> Item {
+        let comparison = Algorithm::comparison(&self.enriched_grammar);
+        parse_quote! {
+            pub const ALGORITHM_COMPARISON: &str = #comparison;
+        }
+    }
+
     fn uses() -> Vec<Item> {
         let file: syn::File = parse_quote! {
             use logos::Logos;
@@ -70,16 +169,25 @@ impl Constructor {
                     #[regex(#regex, parse)]
                     #ident(#ident)
                 },
+                dyn_grammar::token::Match::Error => quote! {
+                    #ident(#ident)
+                },
             }
         });
         let tokens: Vec<_> = tokens.iter().map(|token| token.ident()).collect();
         let counter = 0usize..;
+        // the GLR parser clones the values shared by several derivations
+        let derives = if self.glr {
+            quote!(Logos, Clone)
+        } else {
+            quote!(Logos)
+        };
         let file: syn::File = parse_quote! {
             fn parse<T: std::str::FromStr>(lex: &mut logos::Lexer<Token>) -> Option<T> {
                 lex.slice().parse().ok()
             }
 
-            #[derive(Logos)]
+            #[derive(#derives)]
             #[logos(skip r"[ \t\n\f]+")]
             pub enum Token {
                 #(#variants,)*
@@ -121,7 +229,13 @@ impl Constructor {
             .map(|non_terminal| non_terminal.ident())
             .collect_vec();
         let counter = 0usize..;
+        let derives = if self.glr {
+            quote!(#[derive(Clone)])
+        } else {
+            quote!()
+        };
         let file: syn::File = parse_quote! {
+            #derives
             pub enum NonTerminal {
                 #(#non_terminals (#non_terminals),)*
             }
@@ -201,8 +315,14 @@ impl Constructor {
                 }
             }
         });
+        let arities = productions.iter().map(EnrichedProduction::arity);
+        let heads = productions.iter().map(|prod| {
+            self.enriched_grammar
+                .non_terminal_id(prod.head())
+                .expect("non terminal not found")
+        });
         let file: syn::File = parse_quote! {
-            #[derive(Debug, Clone)]
+            #[derive(Debug, Clone, PartialEq)]
             pub enum ProductionName {
                 #(#idents,)*
             }
@@ -222,6 +342,19 @@ impl Constructor {
                     }
                 }
             }
+
+            impl parser::ProductionShape for ProductionName {
+                fn arity(&self) -> usize {
+                    match self {
+                        #(Self::#idents => #arities,)*
+                    }
+                }
+                fn head(&self) -> usize {
+                    match self {
+                        #(Self::#idents => #heads,)*
+                    }
+                }
+            }
         };
         file.items
     }
@@ -345,6 +478,8 @@ impl Constructor {
         token_table: TokenTable,
         eof_table: EofTable,
         non_terminal_table: NonTerminalTable,
+        immediate_reductions: &[Option<usize>],
+        unit_chains: &UnitChains,
     ) -> Vec<Item> {
         let token_table_patts = token_table
             .table
@@ -378,30 +513,12 @@ impl Constructor {
                 quote!((#state, #token_id) => Some(#action))
             });
 
-        let token_in_state_patts = token_table
-            .table
-            .iter()
-            .enumerate()
-            .map(|(state, row)| {
-                (
-                    state,
-                    row.into_iter()
-                        .enumerate()
-                        .map(move |(token_id, opt_action)| {
-                            opt_action.as_ref().map(|_| {
-                                enriched_grammar
-                                    .tokens()
-                                    .get(token_id)
-                                    .unwrap()
-                                    .ident()
-                                    .to_string()
-                            })
-                        })
-                        .flatten()
-                        .collect_vec(),
-                )
-            })
-            .map(|(state, tokens)| quote!(#state => &[#(#tokens),*]));
+        let token_in_state_patts = Self::tokens_in_state_patts(enriched_grammar, &token_table);
+        let token_fns = Self::token_fns(enriched_grammar);
+        let immediate_reduction_patts =
+            Self::immediate_reduction_patts(enriched_grammar, immediate_reductions);
+        let convert_goto_patts = Self::convert_goto_patts(enriched_grammar, unit_chains);
+        let error_token_fns = Self::error_token_fns(enriched_grammar, &token_table);
 
         let eof_table_patts = eof_table
             .table
@@ -448,10 +565,7 @@ impl Constructor {
 
             impl parser::Tables<NonTerminal, Token, ProductionName> for Tables {
                 fn query_token_table(current_state: usize, current_token: &Token) -> Option<parser::TokenAction<ProductionName>> {
-                    match (current_state, current_token.id()) {
-                        #(#token_table_patts,)*
-                        _ => None,
-                    }
+                    Self::query_token_id(current_state, current_token.id())
                 }
                 fn query_eof_table(current_state: usize) -> Option<parser::EofAction<ProductionName>> {
                     match current_state {
@@ -460,17 +574,332 @@ impl Constructor {
                     }
                 }
                 fn query_goto_table(current_state: usize, non_terminal: &NonTerminal) -> Option<usize> {
-                    match (current_state, non_terminal.id()) {
+                    Self::query_goto_id(current_state, non_terminal.id())
+                }
+                fn query_token_id(current_state: usize, token: usize) -> Option<parser::TokenAction<ProductionName>> {
+                    match (current_state, token) {
+                        #(#token_table_patts,)*
+                        _ => None,
+                    }
+                }
+                fn query_goto_id(current_state: usize, non_terminal: usize) -> Option<usize> {
+                    match (current_state, non_terminal) {
                         #(#non_terminal_patts,)*
                         _ => None,
                     }
                 }
+                fn query_immediate_reduction(current_state: usize) -> Option<ProductionName> {
+                    match current_state {
+                        #(#immediate_reduction_patts,)*
+                        _ => None,
+                    }
+                }
+                fn convert_goto(current_state: usize, non_terminal: NonTerminal) -> NonTerminal {
+                    match (current_state, non_terminal) {
+                        #(#convert_goto_patts,)*
+                        (_, non_terminal) => non_terminal,
+                    }
+                }
+                #error_token_fns
                 fn tokens_in_state(state: usize) -> &'static[&'static str] {
                     match state {
                         #(#token_in_state_patts,)*
                         _ => &[]
                     }
                 }
+                #token_fns
+            }
+        };
+
+        file.items
+    }
+
+    /// Arms of `tokens_in_state`, listing the tokens each state has an action for, the error token
+    /// aside
+    fn tokens_in_state_patts(
+        enriched_grammar: &EnrichedGrammar,
+        token_table: &TokenTable,
+    ) -> Vec<TokenStream> {
+        let error_token_id = enriched_grammar.error_token_id();
+        token_table
+            .table
+            .iter()
+            .enumerate()
+            .map(|(state, row)| {
+                (
+                    state,
+                    row.iter()
+                        .enumerate()
+                        .filter(|(token_id, _)| Some(*token_id) != error_token_id)
+                        .filter_map(|(token_id, opt_action)| {
+                            opt_action.as_ref().map(|_| {
+                                enriched_grammar
+                                    .tokens()
+                                    .get(token_id)
+                                    .unwrap()
+                                    .ident()
+                                    .to_string()
+                            })
+                        })
+                        .collect_vec(),
+                )
+            })
+            .map(|(state, tokens)| quote!(#state => &[#(#tokens),*]))
+            .collect_vec()
+    }
+
+    /// `token_id`, `token_spelling`, `insertable_tokens` and `insert_token`, the literal tokens
+    /// being the ones repairs can build
+    fn token_fns(enriched_grammar: &EnrichedGrammar) -> TokenStream {
+        let tokens = enriched_grammar.tokens();
+        let spellings = tokens.iter().map(|token| match token.match_string() {
+            dyn_grammar::token::Match::Literal(literal) => format!("`{literal}`"),
+            dyn_grammar::token::Match::Regex(_) | dyn_grammar::token::Match::Error => {
+                token.ident().to_string()
+            }
+        });
+        let counter = 0usize..;
+        let insertable =
+            (0..tokens.len()).filter(|id| Some(*id) != enriched_grammar.error_token_id());
+        let literal_patts = tokens.iter().enumerate().filter_map(|(id, token)| {
+            let ident = token.ident();
+            matches!(token.match_string(), dyn_grammar::token::Match::Literal(_))
+                .then(|| quote!(#id => Some(Token::#ident(#ident))))
+        });
+        quote! {
+            fn token_id(token: &Token) -> usize {
+                token.id()
+            }
+            fn token_spelling(token: usize) -> &'static str {
+                match token {
+                    #(#counter => #spellings,)*
+                    _ => unreachable!("no token has id {token}"),
+                }
+            }
+            fn insertable_tokens() -> &'static [usize] {
+                &[#(#insertable),*]
+            }
+            fn insert_token(token: usize) -> Option<Token> {
+                match token {
+                    #(#literal_patts,)*
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    /// `query_error_action` and `error_token`, with an arm per state with an action on the error
+    /// token
+    fn error_token_fns(
+        enriched_grammar: &EnrichedGrammar,
+        token_table: &TokenTable,
+    ) -> TokenStream {
+        let Some(error_token_id) = enriched_grammar.error_token_id() else {
+            return quote! {
+                fn query_error_action(_current_state: usize) -> Option<parser::TokenAction<ProductionName>> {
+                    None
+                }
+                fn error_token(_recovery: parser::ErrorRecovery<NonTerminal, Token>) -> Option<Token> {
+                    None
+                }
+            };
+        };
+        let error_token = enriched_grammar.tokens()[error_token_id].ident();
+        let error_action_patts = token_table
+            .table
+            .iter()
+            .enumerate()
+            .filter_map(|(state, row)| {
+                let action = match row[error_token_id].as_ref()? {
+                    dyn_grammar::parsing::action::TokenAction::Shift(target) => {
+                        quote!(parser::TokenAction::Shift(#target))
+                    }
+                    dyn_grammar::parsing::action::TokenAction::Reduce(production) => {
+                        let production = enriched_grammar.productions()[*production].ident();
+                        quote!(parser::TokenAction::Reduce(ProductionName::#production))
+                    }
+                };
+                Some(quote!(#state => Some(#action)))
+            });
+        quote! {
+            fn query_error_action(current_state: usize) -> Option<parser::TokenAction<ProductionName>> {
+                match current_state {
+                    #(#error_action_patts,)*
+                    _ => None,
+                }
+            }
+            fn error_token(recovery: parser::ErrorRecovery<NonTerminal, Token>) -> Option<Token> {
+                Some(Token::#error_token(recovery.into()))
+            }
+        }
+    }
+
+    /// Arms of `query_immediate_reduction`, one per state whose only action is a reduction
+    fn immediate_reduction_patts(
+        enriched_grammar: &EnrichedGrammar,
+        immediate_reductions: &[Option<usize>],
+    ) -> Vec<TokenStream> {
+        immediate_reductions
+            .iter()
+            .enumerate()
+            .filter_map(|(state, production)| {
+                let production = enriched_grammar.productions()[(*production)?].ident();
+                Some(quote!(#state => Some(ProductionName::#production)))
+            })
+            .collect_vec()
+    }
+
+    /// Arms of `convert_goto`, one per goto that bypasses unit productions, converting the body
+    /// of each of them into its head in turn
+    fn convert_goto_patts(
+        enriched_grammar: &EnrichedGrammar,
+        unit_chains: &UnitChains,
+    ) -> Vec<TokenStream> {
+        unit_chains
+            .iter()
+            .map(|((state, non_terminal), chain)| {
+                let non_terminal = enriched_grammar.non_terminals()[*non_terminal].ident();
+                let heads = chain
+                    .iter()
+                    .map(|production_id| enriched_grammar.productions()[*production_id].head())
+                    .collect_vec();
+                let last_head = heads.last().expect("chains are not empty");
+                quote! {
+                    (#state, NonTerminal::#non_terminal(body)) => {
+                        #(let body: #heads = body.into();)*
+                        NonTerminal::#last_head(body)
+                    }
+                }
+            })
+            .collect_vec()
+    }
+
+    /// A `parser::compressed::CompressedTable` literal, `filler` standing in the slots of the
+    /// comb vector that belong to no row
+    fn compressed_table<T: Copy>(
+        table: &CompressedTable<T>,
+        entry: impl Fn(T) -> TokenStream,
+        filler: TokenStream,
+    ) -> TokenStream {
+        let rows = &table.rows;
+        let defaults = table.defaults.iter().map(|default| match default {
+            Some(default) => {
+                let default = entry(*default);
+                quote!(Some(#default))
+            }
+            None => quote!(None),
+        });
+        let base = &table.comb.base;
+        let check = table
+            .comb
+            .slots
+            .iter()
+            .map(|slot| slot.map_or(usize::MAX, |(row, _)| row));
+        let values = table.comb.slots.iter().map(|slot| match slot {
+            Some((_, value)) => entry(*value),
+            None => filler.clone(),
+        });
+        quote! {
+            parser::compressed::CompressedTable {
+                rows: &[#(#rows),*],
+                defaults: &[#(#defaults),*],
+                base: &[#(#base),*],
+                check: &[#(#check),*],
+                values: &[#(#values),*],
+            }
+        }
+    }
+
+    fn compressed_tables(
+        enriched_grammar: &EnrichedGrammar,
+        token_table: &TokenTable,
+        immediate_reductions: &[Option<usize>],
+        unit_chains: &UnitChains,
+        compressed_tables: CompressedTables,
+    ) -> Vec<Item> {
+        let productions = enriched_grammar
+            .productions()
+            .iter()
+            .map(EnrichedProduction::ident);
+        let actions = Self::compressed_table(
+            &compressed_tables.actions,
+            |action| match action {
+                CompressedAction::Shift(state) => quote!(parser::compressed::Action::Shift(#state)),
+                CompressedAction::Reduce(production) => {
+                    quote!(parser::compressed::Action::Reduce(#production))
+                }
+                CompressedAction::Accept => quote!(parser::compressed::Action::Accept),
+            },
+            quote!(parser::compressed::Action::Error),
+        );
+        let gotos = Self::compressed_table(
+            &compressed_tables.gotos,
+            |target| target.into_token_stream(),
+            quote!(0),
+        );
+        let eof_column = compressed_tables.tokens_count;
+        let token_in_state_patts = Self::tokens_in_state_patts(enriched_grammar, token_table);
+        let token_fns = Self::token_fns(enriched_grammar);
+        let immediate_reduction_patts =
+            Self::immediate_reduction_patts(enriched_grammar, immediate_reductions);
+        let convert_goto_patts = Self::convert_goto_patts(enriched_grammar, unit_chains);
+        let error_token_fns = Self::error_token_fns(enriched_grammar, token_table);
+
+        let file: syn::File = parse_quote! {
+            #[derive(Debug)]
+            pub struct Tables;
+
+            impl Tables {
+                const PRODUCTIONS: &[ProductionName] = &[#(ProductionName::#productions),*];
+                const EOF_COLUMN: usize = #eof_column;
+                const ACTIONS: parser::compressed::CompressedTable<parser::compressed::Action> = #actions;
+                const GOTOS: parser::compressed::CompressedTable<usize> = #gotos;
+            }
+
+            impl parser::Tables<NonTerminal, Token, ProductionName> for Tables {
+                fn query_token_table(current_state: usize, current_token: &Token) -> Option<parser::TokenAction<ProductionName>> {
+                    Self::query_token_id(current_state, current_token.id())
+                }
+                fn query_token_id(current_state: usize, token: usize) -> Option<parser::TokenAction<ProductionName>> {
+                    match Tables::ACTIONS.get(current_state, token)? {
+                        parser::compressed::Action::Shift(state) => Some(parser::TokenAction::Shift(state)),
+                        parser::compressed::Action::Reduce(production) => Some(parser::TokenAction::Reduce(Tables::PRODUCTIONS[production].clone())),
+                        parser::compressed::Action::Accept | parser::compressed::Action::Error => None,
+                    }
+                }
+                fn query_eof_table(current_state: usize) -> Option<parser::EofAction<ProductionName>> {
+                    match Tables::ACTIONS.get(current_state, Tables::EOF_COLUMN)? {
+                        parser::compressed::Action::Reduce(production) => Some(parser::EofAction::Reduce(Tables::PRODUCTIONS[production].clone())),
+                        parser::compressed::Action::Accept => Some(parser::EofAction::Accept),
+                        parser::compressed::Action::Shift(_) | parser::compressed::Action::Error => None,
+                    }
+                }
+                fn query_goto_table(current_state: usize, non_terminal: &NonTerminal) -> Option<usize> {
+                    Self::query_goto_id(current_state, non_terminal.id())
+                }
+                fn query_goto_id(current_state: usize, non_terminal: usize) -> Option<usize> {
+                    Tables::GOTOS.get(current_state, non_terminal)
+                }
+                fn query_immediate_reduction(current_state: usize) -> Option<ProductionName> {
+                    match current_state {
+                        #(#immediate_reduction_patts,)*
+                        _ => None,
+                    }
+                }
+                fn convert_goto(current_state: usize, non_terminal: NonTerminal) -> NonTerminal {
+                    match (current_state, non_terminal) {
+                        #(#convert_goto_patts,)*
+                        (_, non_terminal) => non_terminal,
+                    }
+                }
+                #error_token_fns
+                fn tokens_in_state(state: usize) -> &'static[&'static str] {
+                    match state {
+                        #(#token_in_state_patts,)*
+                        _ => &[]
+                    }
+                }
+                #token_fns
             }
         };
 
@@ -479,6 +908,10 @@ impl Constructor {
 
     fn parser(&self) -> Item {
         let start_symbol = self.enriched_grammar.start_symbol().ident();
-        parse_quote!(pub type Parser = parser::Parser<NonTerminal, Token, #start_symbol, ProductionName, Tables, __CompilerContext>;)
+        if self.glr {
+            parse_quote!(pub type Parser = parser::glr::GlrParser<NonTerminal, Token, #start_symbol, ProductionName, Tables, __CompilerContext>;)
+        } else {
+            parse_quote!(pub type Parser = parser::Parser<NonTerminal, Token, #start_symbol, ProductionName, Tables, __CompilerContext>;)
+        }
     }
 }
@@ -1,32 +1,39 @@
-use crate::{constructor::Constructor};
+use crate::{constructor::Constructor, grammar_options::GrammarOptions};
 use proc_macro::TokenStream;
-use proc_macro_error::{emit_call_site_error, proc_macro_error};
+use proc_macro_error::{emit_call_site_error, emit_error, proc_macro_error};
 use quote::quote;
-use syn::{File, Ident, Item, ItemMod};
+use syn::{File, Item, ItemMod};
 
+mod cache;
 mod constructor;
+mod diagnostics;
+mod glr_injections;
 mod grammar_extraction;
+mod grammar_options;
 mod item_injections;
 
 #[proc_macro_attribute]
 #[proc_macro_error]
 pub fn grammar(attr: TokenStream, item: TokenStream) -> TokenStream {
-    let internal_mod_name = syn::parse::<Ident>(attr).ok();
+    let options = syn::parse::<GrammarOptions>(attr).unwrap_or_else(|err| {
+        emit_error!(err.span(), "{}", err);
+        GrammarOptions::default()
+    });
     if let Ok(mut module) = syn::parse::<ItemMod>(item.clone()) {
         let (_, items) = module
             .content
             .as_mut()
             .expect("grammar module must be inline (contain braces)");
 
-        let constructor = Constructor::extract(items, internal_mod_name);
+        let constructor = Constructor::extract(&mut module.attrs, items, options);
         constructor.inject_items(items);
 
         quote! { #module }.into()
-    } else if let Ok(File { items, .. }) = &mut syn::parse(item) {
-        let constructor = Constructor::extract(items, internal_mod_name);
+    } else if let Ok(File { attrs, items, .. }) = &mut syn::parse(item) {
+        let constructor = Constructor::extract(attrs, items, options);
         constructor.inject_items(items);
 
-        quote! { #(#items)* }.into()
+        quote! { #(#attrs)* #(#items)* }.into()
     } else {
         emit_call_site_error!("a grammar is either an inline module or a file");
         panic!()
@@ -44,6 +51,10 @@ macro_rules! dummy_attribute {
 }
 
 dummy_attribute!(token, "type aliases, structs, enums or use directives");
+dummy_attribute!(
+    error_token,
+    "ONLY ONE type alias, struct, enum or use directive"
+);
 dummy_attribute!(
     start_symbol,
     "type aliases, structs, enums or use directives"
@@ -54,8 +65,16 @@ dummy_attribute!(
 );
 dummy_attribute!(left_associative, "production macros");
 dummy_attribute!(right_associative, "production macros");
-dummy_attribute!(precedence, "production marcos");
+dummy_attribute!(precedence, "production macros");
+dummy_attribute!(prec, "production macros");
 dummy_attribute!(
     context,
     "ONLY ONE type alias, struct, enum or use directive"
 );
+dummy_attribute!(allow_conflicts, "grammar modules, after #[grammar]");
+dummy_attribute!(expect_conflicts, "grammar modules, after #[grammar]");
+dummy_attribute!(merge, "functions of a GLR grammar module");
+dummy_attribute!(allow_unreachable, "non-terminals");
+dummy_attribute!(allow_unproductive, "non-terminals");
+dummy_attribute!(allow_no_productions, "non-terminals");
+dummy_attribute!(allow_unused, "tokens");
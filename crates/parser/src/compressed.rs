@@ -0,0 +1,35 @@
+//! Runtime side of the compressed table encoding, `#[grammar(tables = "compressed")]`
+
+/// Entry of the compressed action table, productions being referred to by their index
+#[derive(Clone, Copy, Debug)]
+pub enum Action {
+    Shift(usize),
+    Reduce(usize),
+    Accept,
+    /// Filler of the slots that belong to no row, never returned
+    Error,
+}
+
+/// A sparse table stored as a comb vector: identical rows are shared between states, the entry
+/// of a row at a column lives at `base[row] + column` and belongs to the row only if `check`
+/// holds the row there, otherwise the row's default applies
+#[derive(Debug)]
+pub struct CompressedTable<T: 'static> {
+    pub rows: &'static [usize],
+    pub defaults: &'static [Option<T>],
+    pub base: &'static [usize],
+    pub check: &'static [usize],
+    pub values: &'static [T],
+}
+
+impl<T: Copy> CompressedTable<T> {
+    pub fn get(&self, state: usize, column: usize) -> Option<T> {
+        let row = self.rows[state];
+        let slot = self.base[row] + column;
+        if self.check.get(slot) == Some(&row) {
+            Some(self.values[slot])
+        } else {
+            self.defaults[row]
+        }
+    }
+}
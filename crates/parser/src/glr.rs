@@ -0,0 +1,495 @@
+use crate::{EofAction, GlrTables, Merge, ProductionShape, Reduce, Stacks, Symbol, TokenAction};
+use itertools::Itertools;
+use logos::Logos;
+use std::{
+    collections::{HashMap, VecDeque},
+    fmt::Display,
+    marker::PhantomData,
+    ops::Range,
+};
+
+struct GssNode {
+    state: usize,
+    /// Number of tokens read when the node was created
+    level: usize,
+    /// Nodes below this one, each with the forest node of the symbol between them
+    edges: Vec<(usize, usize)>,
+}
+
+enum ForestNode<Prod> {
+    Token(usize),
+    /// The derivations of a non-terminal over a span of the input, each with its children
+    NonTerminal(Vec<(Prod, Vec<usize>)>),
+}
+
+#[derive(Debug)]
+pub enum GlrParseError<NonTerminal, Token> {
+    UnexpectedToken {
+        position: usize,
+        token: Token,
+        expected: Vec<&'static str>,
+    },
+    UnexpectedEof {
+        expected: Vec<&'static str>,
+    },
+    Ambiguity {
+        first: NonTerminal,
+        second: NonTerminal,
+    },
+}
+
+impl<NonTerminal: Display, Token: Display> Display for GlrParseError<NonTerminal, Token> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnexpectedToken {
+                position,
+                token,
+                expected,
+            } => write!(
+                f,
+                "ParseError: unexpected {token} at token {position}, expected any of [{}]",
+                expected.iter().format(", ")
+            ),
+            Self::UnexpectedEof { expected } => write!(
+                f,
+                "ParseError: unexpected end of input, expected any of [{}]",
+                expected.iter().format(", ")
+            ),
+            Self::Ambiguity { first, .. } => write!(
+                f,
+                "ParseError: ambiguous {first}, there is no #[merge] function to choose between its derivations"
+            ),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum GlrLexParseError<'source, NonTerminal, Token: Logos<'source>> {
+    LexError {
+        span: Range<usize>,
+        error: Token::Error,
+    },
+    ParseError(GlrParseError<NonTerminal, Token>),
+}
+
+impl<'source, NonTerminal: Display, Token: Logos<'source> + Display> Display
+    for GlrLexParseError<'source, NonTerminal, Token>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::LexError { span, .. } => {
+                write!(
+                    f,
+                    "LexError: unrecognized token at {}..{}",
+                    span.start, span.end
+                )
+            }
+            Self::ParseError(parse_error) => parse_error.fmt(f),
+        }
+    }
+}
+
+/// Generalized LR parser: on a conflict every action is taken, the stacks being shared in a
+/// graph. The derivations are kept in a shared packed forest, and the semantic actions only run
+/// once the whole input is accepted, on the derivations of the accepted start symbol. When a
+/// non-terminal has several derivations over the same input, they are combined with its
+/// `#[merge]` function
+pub struct GlrParser<
+    NonTerminal: Into<StartSymbol> + Merge + Clone,
+    Token: Clone,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, Ctx> + ProductionShape + Clone + PartialEq,
+    Tab: GlrTables<NonTerminal, Token, Prod>,
+    Ctx,
+> {
+    ctx: Ctx,
+    tokens: Vec<Token>,
+    gss: Vec<GssNode>,
+    /// Nodes on top of the stacks, by state
+    frontier: Vec<usize>,
+    frontier_ids: HashMap<usize, usize>,
+    forest: Vec<ForestNode<Prod>>,
+    /// Non-terminal forest nodes by head and span
+    forest_ids: HashMap<(usize, usize, usize), usize>,
+    phantom_data: PhantomData<(NonTerminal, StartSymbol, Tab)>,
+}
+
+impl<
+    NonTerminal: Into<StartSymbol> + Merge + Clone,
+    Token: Clone,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, Ctx> + ProductionShape + Clone + PartialEq,
+    Tab: GlrTables<NonTerminal, Token, Prod>,
+    Ctx,
+> GlrParser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
+{
+    fn new(ctx: Ctx) -> Self {
+        Self {
+            ctx,
+            tokens: Vec::new(),
+            gss: vec![GssNode {
+                state: 0,
+                level: 0,
+                edges: Vec::new(),
+            }],
+            frontier: vec![0],
+            frontier_ids: HashMap::from([(0, 0)]),
+            forest: Vec::new(),
+            forest_ids: HashMap::new(),
+            phantom_data: PhantomData,
+        }
+    }
+
+    fn expected(&self) -> Vec<&'static str> {
+        self.frontier
+            .iter()
+            .flat_map(|node| Tab::tokens_in_state(self.gss[*node].state).iter().copied())
+            .unique()
+            .collect()
+    }
+
+    /// Paths of `length` edges down from `node`, with the node they end on and the forest nodes
+    /// along them, leftmost first. With `through`, only the paths taking that edge: until they take
+    /// it they stay on the current level, the only nodes it can be reached from
+    fn paths(
+        &self,
+        node: usize,
+        length: usize,
+        through: Option<(usize, usize)>,
+    ) -> Vec<(usize, Vec<usize>)> {
+        let level = self.tokens.len();
+        let mut paths = vec![(node, Vec::new(), through.is_none())];
+        for _ in 0..length {
+            paths = paths
+                .into_iter()
+                .flat_map(|(node, children, crossed)| {
+                    self.gss[node]
+                        .edges
+                        .iter()
+                        .filter_map(move |(below, label)| {
+                            let crossed = crossed || through == Some((node, *below));
+                            if !crossed && self.gss[*below].level != level {
+                                return None;
+                            }
+                            let mut children = children.clone();
+                            children.push(*label);
+                            Some((*below, children, crossed))
+                        })
+                })
+                .collect();
+        }
+        paths
+            .into_iter()
+            .filter(|(_, _, crossed)| *crossed)
+            .map(|(below, mut children, _)| {
+                children.reverse();
+                (below, children)
+            })
+            .collect()
+    }
+
+    fn non_terminal_node(&mut self, prod: Prod, start: usize, children: Vec<usize>) -> usize {
+        let key = (prod.head(), start, self.tokens.len());
+        let next_id = self.forest.len();
+        let id = *self.forest_ids.entry(key).or_insert(next_id);
+        if id == next_id {
+            self.forest.push(ForestNode::NonTerminal(Vec::new()));
+        }
+        let ForestNode::NonTerminal(alternatives) = &mut self.forest[id] else {
+            unreachable!("this is not a non terminal")
+        };
+        if !alternatives
+            .iter()
+            .any(|(other_prod, other_children)| *other_prod == prod && *other_children == children)
+        {
+            alternatives.push((prod, children));
+        }
+        id
+    }
+
+    /// Links a node of the current level in `state` to `below`, creating it if needed. Returns the
+    /// node if it is new or got a new edge, its reductions then have to be done (again)
+    fn add_edge(&mut self, state: usize, below: usize, label: usize) -> Option<usize> {
+        match self.frontier_ids.get(&state) {
+            Some(&node) => {
+                if self.gss[node]
+                    .edges
+                    .iter()
+                    .any(|(other, _)| *other == below)
+                {
+                    return None;
+                }
+                self.gss[node].edges.push((below, label));
+                Some(node)
+            }
+            None => {
+                let node = self.gss.len();
+                self.gss.push(GssNode {
+                    state,
+                    level: self.tokens.len(),
+                    edges: vec![(below, label)],
+                });
+                self.frontier.push(node);
+                self.frontier_ids.insert(state, node);
+                Some(node)
+            }
+        }
+    }
+
+    /// Does every reduction possible on top of the stacks, `reductions` giving the productions
+    /// to reduce in a state on the current lookahead. When an edge is added, only the paths going
+    /// through it are reduced, the others already were
+    fn reduce_all(&mut self, reductions: impl Fn(usize) -> Vec<Prod>) {
+        let mut queue: VecDeque<_> = self
+            .frontier
+            .iter()
+            .flat_map(|node| {
+                reductions(self.gss[*node].state)
+                    .into_iter()
+                    .map(|prod| (*node, prod, None))
+            })
+            .collect();
+        while let Some((node, prod, through)) = queue.pop_front() {
+            for (below, children) in self.paths(node, prod.arity(), through) {
+                let Some(target) = Tab::query_goto_id(self.gss[below].state, prod.head()) else {
+                    continue;
+                };
+                let label = self.non_terminal_node(prod.clone(), self.gss[below].level, children);
+                let frontier_len = self.frontier.len();
+                let Some(top) = self.add_edge(target, below, label) else {
+                    continue;
+                };
+                let edge = Some((top, below));
+                if self.frontier.len() > frontier_len {
+                    queue.extend(reductions(target).into_iter().map(|prod| {
+                        let through = if prod.arity() == 0 { None } else { edge };
+                        (top, prod, through)
+                    }));
+                } else {
+                    // Nodes on top of this one through empty reductions may now have new paths
+                    // going down through the new edge, the empty reductions are already done
+                    for node in &self.frontier {
+                        queue.extend(
+                            reductions(self.gss[*node].state)
+                                .into_iter()
+                                .filter(|prod| prod.arity() > 0)
+                                .map(|prod| (*node, prod, edge)),
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    fn parse_token(&mut self, token: Token) -> Result<(), GlrParseError<NonTerminal, Token>> {
+        self.reduce_all(|state| {
+            Tab::query_token_actions(state, &token)
+                .into_iter()
+                .filter_map(|action| match action {
+                    TokenAction::Reduce(prod) => Some(prod),
+                    TokenAction::Shift(_) => None,
+                })
+                .collect()
+        });
+
+        let shifts = self
+            .frontier
+            .iter()
+            .flat_map(|node| {
+                Tab::query_token_actions(self.gss[*node].state, &token)
+                    .into_iter()
+                    .filter_map(|action| match action {
+                        TokenAction::Shift(target) => Some((*node, target)),
+                        TokenAction::Reduce(_) => None,
+                    })
+            })
+            .collect_vec();
+        if shifts.is_empty() {
+            return Err(GlrParseError::UnexpectedToken {
+                position: self.tokens.len(),
+                expected: self.expected(),
+                token,
+            });
+        }
+
+        let label = self.forest.len();
+        self.forest.push(ForestNode::Token(self.tokens.len()));
+        self.tokens.push(token);
+        self.frontier.clear();
+        self.frontier_ids.clear();
+        for (below, target) in shifts {
+            self.add_edge(target, below, label);
+        }
+        Ok(())
+    }
+
+    /// Reduces on the end of input, then returns the forest node of the accepted start symbol
+    fn parse_eof(&mut self) -> Result<usize, GlrParseError<NonTerminal, Token>> {
+        self.reduce_all(|state| {
+            Tab::query_eof_actions(state)
+                .into_iter()
+                .filter_map(|action| match action {
+                    EofAction::Reduce(prod) => Some(prod),
+                    EofAction::Accept => None,
+                })
+                .collect()
+        });
+
+        self.frontier
+            .iter()
+            .find(|node| {
+                Tab::query_eof_actions(self.gss[**node].state)
+                    .into_iter()
+                    .any(|action| matches!(action, EofAction::Accept))
+            })
+            .map(|node| self.gss[*node].edges[0].1)
+            .ok_or_else(|| GlrParseError::UnexpectedEof {
+                expected: self.expected(),
+            })
+    }
+
+    /// Runs the semantic actions bottom-up on the forest below `root`, merging the derivations of
+    /// the same non-terminal over the same input
+    fn evaluate(mut self, root: usize) -> Result<StartSymbol, GlrParseError<NonTerminal, Token>> {
+        let mut values: Vec<Option<Symbol<NonTerminal, Token>>> =
+            self.forest.iter().map(|_| None).collect();
+        let mut expanded = vec![false; self.forest.len()];
+        let mut stack = vec![root];
+        while let Some(&node) = stack.last() {
+            if values[node].is_some() {
+                stack.pop();
+                continue;
+            }
+            match &self.forest[node] {
+                ForestNode::Token(position) => {
+                    values[node] = Some(Symbol::Token(self.tokens[*position].clone()));
+                    stack.pop();
+                }
+                ForestNode::NonTerminal(alternatives) if !expanded[node] => {
+                    expanded[node] = true;
+                    stack.extend(
+                        alternatives
+                            .iter()
+                            .flat_map(|(_, children)| children.iter().copied())
+                            // a child being expanded is a cycle in the derivations
+                            .filter(|child| values[*child].is_none() && !expanded[*child]),
+                    );
+                }
+                ForestNode::NonTerminal(alternatives) => {
+                    let mut merged: Option<NonTerminal> = None;
+                    for (prod, children) in alternatives {
+                        let mut stacks = Stacks::new();
+                        for child in children {
+                            match &values[*child] {
+                                Some(Symbol::Token(token)) => stacks.shift(0, token.clone()),
+                                Some(Symbol::NonTerminal(non_terminal)) => {
+                                    stacks.goto(0, non_terminal.clone())
+                                }
+                                None => break,
+                            }
+                        }
+                        if stacks.symbol_stack.len() != children.len() {
+                            continue;
+                        }
+                        let value = prod.reduce(&mut self.ctx, &mut stacks);
+                        merged = Some(match merged {
+                            Some(previous) => {
+                                previous.merge(value).map_err(|(first, second)| {
+                                    GlrParseError::Ambiguity { first, second }
+                                })?
+                            }
+                            None => value,
+                        });
+                    }
+                    values[node] = merged.map(Symbol::NonTerminal);
+                    stack.pop();
+                }
+            }
+        }
+
+        let Some(Symbol::NonTerminal(non_terminal)) = values[root].take() else {
+            unreachable!("the start symbol has no derivation")
+        };
+        Ok(non_terminal.into())
+    }
+
+    pub fn parse_with_ctx(
+        ctx: Ctx,
+        tokens: impl IntoIterator<Item = Token>,
+    ) -> Result<StartSymbol, GlrParseError<NonTerminal, Token>> {
+        let mut parser = Self::new(ctx);
+        for token in tokens.into_iter() {
+            parser.parse_token(token)?;
+        }
+        let root = parser.parse_eof()?;
+        parser.evaluate(root)
+    }
+
+    pub fn parse_default_ctx(
+        tokens: impl IntoIterator<Item = Token>,
+    ) -> Result<StartSymbol, GlrParseError<NonTerminal, Token>>
+    where
+        Ctx: Default,
+    {
+        Self::parse_with_ctx(Default::default(), tokens)
+    }
+
+    pub fn lex_parse_with_ctx<'source>(
+        ctx: Ctx,
+        source: &'source Token::Source,
+    ) -> Result<StartSymbol, GlrLexParseError<'source, NonTerminal, Token>>
+    where
+        Token: Logos<'source>,
+        Token::Extras: Default,
+    {
+        let mut parser = Self::new(ctx);
+        let mut lexer = Token::lexer(source);
+        while let Some(token) = lexer.next() {
+            let token = token.map_err(|error| GlrLexParseError::LexError {
+                span: lexer.span(),
+                error,
+            })?;
+            parser
+                .parse_token(token)
+                .map_err(GlrLexParseError::ParseError)?;
+        }
+        let root = parser.parse_eof().map_err(GlrLexParseError::ParseError)?;
+        parser.evaluate(root).map_err(GlrLexParseError::ParseError)
+    }
+
+    pub fn lex_parse_default_ctx<'source>(
+        source: &'source Token::Source,
+    ) -> Result<StartSymbol, GlrLexParseError<'source, NonTerminal, Token>>
+    where
+        Token: Logos<'source>,
+        Token::Extras: Default,
+        Ctx: Default,
+    {
+        Self::lex_parse_with_ctx(Default::default(), source)
+    }
+}
+
+impl<
+    NonTerminal: Into<StartSymbol> + Merge + Clone,
+    Token: Clone,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, ()> + ProductionShape + Clone + PartialEq,
+    Tab: GlrTables<NonTerminal, Token, Prod>,
+> GlrParser<NonTerminal, Token, StartSymbol, Prod, Tab, ()>
+{
+    pub fn parse(
+        tokens: impl Iterator<Item = Token>,
+    ) -> Result<StartSymbol, GlrParseError<NonTerminal, Token>> {
+        Self::parse_with_ctx((), tokens)
+    }
+
+    pub fn lex_parse<'source>(
+        source: &'source Token::Source,
+    ) -> Result<StartSymbol, GlrLexParseError<'source, NonTerminal, Token>>
+    where
+        Token: Logos<'source>,
+        Token::Extras: Default,
+    {
+        Self::lex_parse_with_ctx((), source)
+    }
+}
@@ -1,15 +1,24 @@
+use crate::repair::LOOKAHEAD;
 use crate::results::{
     LexError, LexParseError, ParseEof, ParseEofError, ParseError, ParseOneError, ParseToken,
     ParseTokenError,
 };
-use logos::Logos;
-use std::{fmt::Display, marker::PhantomData};
+use logos::{Logos, Source};
+use std::{
+    collections::VecDeque, convert::Infallible, fmt::Display, marker::PhantomData, ops::Range,
+};
 
 mod actions;
+pub mod compressed;
+pub mod glr;
+mod recovery;
+mod repair;
 pub mod results;
 mod traits;
 
 pub use actions::*;
+pub use recovery::*;
+pub use repair::{Repair, RepairedError};
 pub use traits::*;
 
 #[derive(Debug)]
@@ -67,6 +76,12 @@ pub struct Parser<
 > {
     stacks: Stacks<NonTerminal, Token>,
     ctx: Ctx,
+    /// Recovery from a syntax error waiting for a token that can follow the error token
+    recovery: Option<Box<ErrorRecovery<NonTerminal, Token>>>,
+    /// Whether no token was shifted since the error token
+    recovered: bool,
+    /// Tokens `push` dropped right after a recovery, until they are taken
+    dropped: Vec<Token>,
     phantom_data: PhantomData<(StartSymbol, Prod, Tab)>,
 }
 
@@ -74,17 +89,27 @@ impl<
     NonTerminal: Into<StartSymbol>,
     Token,
     StartSymbol,
-    Prod: Reduce<NonTerminal, Token, Ctx>,
+    Prod: Reduce<NonTerminal, Token, Ctx> + ProductionShape,
     Tab: Tables<NonTerminal, Token, Prod>,
     Ctx,
 > Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
 {
-    fn new(ctx: Ctx) -> Self {
-        Self {
+    /// A parser at the start of the input, to which tokens are then pushed one at a time. The
+    /// reductions the start state does on any lookahead are done right away
+    pub fn start(ctx: Ctx) -> Self {
+        let mut parser = Self {
             stacks: Stacks::new(),
             ctx,
+            recovery: None,
+            recovered: false,
+            dropped: Vec::new(),
             phantom_data: PhantomData,
-        }
+        };
+        assert!(
+            parser.reduce_immediately().is_ok(),
+            "no goto after a reduction of the start state"
+        );
+        parser
     }
 
     fn parse_token(
@@ -95,17 +120,19 @@ impl<
         match Tab::query_token_table(current_state, &token) {
             Some(TokenAction::Shift(new_state)) => {
                 self.stacks.shift(new_state, token);
+                self.reduce_immediately().map_err(|leftover_non_terminal| {
+                    ParseTokenError::GotoNotFound {
+                        leftover_non_terminal,
+                    }
+                })?;
                 Ok(ParseToken::Shifted)
             }
             Some(TokenAction::Reduce(prod)) => {
-                let head = prod.reduce(&mut self.ctx, &mut self.stacks);
-                let new_current_state = self.stacks.current_state();
-                let Some(next_state) = Tab::query_goto_table(new_current_state, &head) else {
-                    return Err(ParseTokenError::GotoNotFound {
-                        leftover_non_terminal: head,
-                    });
-                };
-                self.stacks.goto(next_state, head);
+                self.reduce(prod).map_err(|leftover_non_terminal| {
+                    ParseTokenError::GotoNotFound {
+                        leftover_non_terminal,
+                    }
+                })?;
                 Ok(ParseToken::Reduced {
                     leftover_token: token,
                 })
@@ -116,18 +143,37 @@ impl<
         }
     }
 
+    /// Reduces as long as the current state's only action is a reduction, so that semantic
+    /// actions run as soon as the tokens they need are shifted instead of when the next token
+    /// arrives. Gives back the non-terminal whose goto is missing
+    fn reduce_immediately(&mut self) -> Result<(), NonTerminal> {
+        while let Some(prod) = Tab::query_immediate_reduction(self.stacks.current_state()) {
+            self.reduce(prod)?;
+        }
+        Ok(())
+    }
+
+    /// Reduces the production and follows the goto on its head, converting the head through the
+    /// unit productions the goto bypasses. Gives back the head if there is no goto
+    fn reduce(&mut self, prod: Prod) -> Result<(), NonTerminal> {
+        let head = prod.reduce(&mut self.ctx, &mut self.stacks);
+        let new_current_state = self.stacks.current_state();
+        let Some(next_state) = Tab::query_goto_table(new_current_state, &head) else {
+            return Err(head);
+        };
+        let head = Tab::convert_goto(new_current_state, head);
+        self.stacks.goto(next_state, head);
+        Ok(())
+    }
+
     fn parse_eof(&mut self) -> Result<ParseEof, ParseEofError<NonTerminal>> {
         let current_state = self.stacks.current_state();
         match Tab::query_eof_table(current_state) {
             Some(EofAction::Reduce(prod)) => {
-                let head = prod.reduce(&mut self.ctx, &mut self.stacks);
-                let new_current_state = self.stacks.current_state();
-                let Some(next_state) = Tab::query_goto_table(new_current_state, &head) else {
-                    return Err(ParseEofError::GotoNotFound {
-                        leftover_non_terminal: head,
-                    });
-                };
-                self.stacks.goto(next_state, head);
+                self.reduce(prod)
+                    .map_err(|leftover_non_terminal| ParseEofError::GotoNotFound {
+                        leftover_non_terminal,
+                    })?;
                 Ok(ParseEof::Reduced)
             }
             Some(EofAction::Accept) => Ok(ParseEof::Accepted),
@@ -135,117 +181,393 @@ impl<
         }
     }
 
-    pub fn parse_with_ctx(
-        ctx: Ctx,
-        tokens: impl IntoIterator<Item = Token>,
-    ) -> Result<StartSymbol, ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>> {
-        let mut parser = Self::new(ctx);
-        for mut token in tokens.into_iter() {
-            loop {
-                match parser.parse_token(token) {
-                    Ok(ParseToken::Shifted) => {
-                        break;
-                    }
-                    Ok(ParseToken::Reduced { leftover_token }) => {
-                        token = leftover_token;
+    /// Parses a token up to its shift. On a syntax error, the stacks are popped down to a state
+    /// shifting the error token and the tokens that can't follow it are discarded, before
+    /// shifting it and going on. `span` is where the token is in the source, if known
+    fn push_token(
+        &mut self,
+        mut token: Token,
+        span: Option<Range<usize>>,
+    ) -> Result<Option<Token>, ParseTokenError<NonTerminal, Token>> {
+        loop {
+            if self.recovery.is_some() {
+                if Tab::query_token_table(self.error_state(), &token).is_none() {
+                    self.skip_token(token, span);
+                    return Ok(None);
+                }
+                self.shift_error_token().map_err(|leftover_non_terminal| {
+                    ParseTokenError::GotoNotFound {
+                        leftover_non_terminal,
                     }
-                    Err(err) => {
-                        return Err(ParseError::new(parser, ParseOneError::ParseTokenError(err)));
+                })?;
+            }
+            match self.parse_token(token) {
+                Ok(ParseToken::Shifted) => {
+                    self.recovered = false;
+                    return Ok(None);
+                }
+                Ok(ParseToken::Reduced { leftover_token }) => {
+                    token = leftover_token;
+                }
+                // like yacc, the tokens failing before any is shifted after the error token are
+                // dropped and given back, instead of recovering again at the same place
+                Err(ParseTokenError::ActionNotFound { leftover_token }) if self.recovered => {
+                    return Ok(Some(leftover_token));
+                }
+                Err(ParseTokenError::ActionNotFound { leftover_token }) => {
+                    let recovering =
+                        self.start_recovery(span.clone())
+                            .map_err(|leftover_non_terminal| ParseTokenError::GotoNotFound {
+                                leftover_non_terminal,
+                            })?;
+                    if !recovering {
+                        return Err(ParseTokenError::ActionNotFound { leftover_token });
                     }
+                    token = leftover_token;
                 }
+                Err(err) => return Err(err),
             }
         }
+    }
 
+    /// Parses the end of input up to the acceptance, recovering from a syntax error like
+    /// `push_token` does if the error token can be followed by the end of input. `span` is where
+    /// the input ends in the source, if known
+    fn push_eof(
+        &mut self,
+        span: Option<Range<usize>>,
+    ) -> Result<StartSymbol, ParseEofError<NonTerminal>> {
         loop {
-            match parser.parse_eof() {
-                Ok(ParseEof::Accepted) => {
-                    break;
+            if self.recovery.is_some() {
+                if Tab::query_eof_table(self.error_state()).is_none() {
+                    self.recovery = None;
+                    return Err(ParseEofError::ActionNotFound);
                 }
-                Ok(ParseEof::Reduced) => {
-                    continue;
+                self.shift_error_token().map_err(|leftover_non_terminal| {
+                    ParseEofError::GotoNotFound {
+                        leftover_non_terminal,
+                    }
+                })?;
+            }
+            match self.parse_eof() {
+                Ok(ParseEof::Accepted) => break,
+                Ok(ParseEof::Reduced) => {}
+                Err(ParseEofError::ActionNotFound) if !self.recovered => {
+                    let recovering =
+                        self.start_recovery(span.clone())
+                            .map_err(|leftover_non_terminal| ParseEofError::GotoNotFound {
+                                leftover_non_terminal,
+                            })?;
+                    if !recovering {
+                        return Err(ParseEofError::ActionNotFound);
+                    }
                 }
-                Err(err) => return Err(ParseError::new(parser, ParseOneError::ParseEofError(err))),
+                Err(err) => return Err(err),
             }
         }
 
-        let Symbol::NonTerminal(non_terminal) = parser.stacks.symbol_stack.pop().unwrap() else {
+        let Symbol::NonTerminal(non_terminal) = self.stacks.symbol_stack.pop().unwrap() else {
             unreachable!()
         };
 
         Ok(non_terminal.into())
     }
 
-    pub fn parse_default_ctx(
-        tokens: impl IntoIterator<Item = Token>,
-    ) -> Result<StartSymbol, ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>
+    /// Parses the next token of the input, recovering from a syntax error if the grammar has an
+    /// error token. Otherwise a syntax error gives back the token unshifted, so that other tokens
+    /// can be pushed in its place. The tokens dropped right after a recovery are kept for
+    /// `take_dropped_tokens`
+    pub fn push(&mut self, token: Token) -> Result<(), ParseTokenError<NonTerminal, Token>> {
+        if let Some(dropped) = self.push_token(token, None)? {
+            self.dropped.push(dropped);
+        }
+        Ok(())
+    }
+
+    /// Takes the tokens `push` dropped since the last call: like yacc, a token failing after the
+    /// error token is shifted and before any other token is, is dropped instead of recovering
+    /// again at the same place
+    pub fn take_dropped_tokens(&mut self) -> Vec<Token> {
+        std::mem::take(&mut self.dropped)
+    }
+
+    /// Parses the end of input, giving back the start symbol, or the error with its repairs
+    pub fn finish(
+        mut self,
+    ) -> Result<StartSymbol, ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>> {
+        match self.parse_end::<Infallible>(None, None) {
+            Ok(start_symbol) => Ok(start_symbol),
+            Err(stop) => Err(stop
+                .into_parse_error(self)
+                .unwrap_or_else(|err| match err {})),
+        }
+    }
+
+    /// Parses the input, each token with its span in the source if known, up to its end or to an
+    /// `Err` item. If `repaired` collects the syntax errors gone past, the repairs of an error are
+    /// searched on the tokens following it and the best one is applied to go on. Otherwise the
+    /// error keeps these tokens, its repairs being only searched if asked for
+    fn parse_input<E>(
+        &mut self,
+        input: impl Iterator<Item = Result<(Token, Option<Range<usize>>), E>>,
+        eof_span: Option<Range<usize>>,
+        mut repaired: Option<&mut Vec<RepairedError>>,
+    ) -> Result<StartSymbol, Stop<NonTerminal, Token, E>>
     where
-        Ctx: Default,
+        Token: Display,
     {
-        Self::parse_with_ctx(Default::default(), tokens)
+        self.parse_tokens(input, &eof_span, true, repaired.as_deref_mut())?;
+        self.parse_end(eof_span, repaired)
     }
 
-    pub fn lex_parse_with_ctx<'source>(
-        ctx: Ctx,
-        source: &'source Token::Source,
-    ) -> Result<StartSymbol, LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>
+    /// Parses the tokens of the input like `parse_input`, without its end. The repairs are only
+    /// searched up to the end of input if the tokens are `complete`
+    fn parse_tokens<E>(
+        &mut self,
+        mut input: impl Iterator<Item = Result<(Token, Option<Range<usize>>), E>>,
+        eof_span: &Option<Range<usize>>,
+        complete: bool,
+        mut repaired: Option<&mut Vec<RepairedError>>,
+    ) -> Result<(), Stop<NonTerminal, Token, E>>
     where
-        Token: Logos<'source>,
-        Token::Extras: Default,
+        Token: Display,
     {
-        let mut parser = Self::new(ctx);
-        for token in Token::lexer(source) {
-            let mut token = match token {
-                Ok(token) => token,
-                Err(err) => return Err(LexParseError::LexError(LexError::new(parser, err))),
+        let mut lookahead = VecDeque::new();
+        // item read ahead that stops the parse once the tokens before it are parsed
+        let mut stop = None;
+        let mut ended = false;
+        loop {
+            let (token, span) = match lookahead.pop_front() {
+                Some(next) => next,
+                None if ended => break,
+                None => match stop.take().map(Err).or_else(|| input.next()) {
+                    Some(Ok(next)) => next,
+                    Some(Err(err)) => return Err(Stop::Input(err)),
+                    None => break,
+                },
+            };
+            let leftover_token = match self.push_token(token, span.clone()) {
+                Ok(_) => continue,
+                Err(ParseTokenError::ActionNotFound { leftover_token }) => leftover_token,
+                Err(err) => return Err(Stop::Token(err, span, Some(Vec::new()), lookahead, None)),
             };
 
-            loop {
-                match parser.parse_token(token) {
-                    Ok(ParseToken::Shifted) => {
-                        break;
-                    }
-                    Ok(ParseToken::Reduced { leftover_token }) => {
-                        token = leftover_token;
-                    }
-                    Err(err) => {
-                        return Err(LexParseError::ParseError(ParseError::new(
-                            parser,
-                            ParseOneError::ParseTokenError(err),
-                        )));
+            while lookahead.len() < LOOKAHEAD && stop.is_none() && !ended {
+                match input.next() {
+                    Some(Ok(next)) => lookahead.push_back(next),
+                    Some(Err(err)) => stop = Some(err),
+                    None => ended = true,
+                }
+            }
+            let input_end = (complete && ended && stop.is_none()).then(|| eof_span.clone());
+            let Some(errors) = repaired.as_deref_mut() else {
+                let err = ParseTokenError::ActionNotFound { leftover_token };
+                return Err(Stop::Token(err, span, None, lookahead, input_end));
+            };
+            let message = format!(
+                "ParseError: unexpected {leftover_token}, expected any of [{}]",
+                Tab::tokens_in_state(self.stacks.current_state()).join(", ")
+            );
+            lookahead.push_front((leftover_token, span.clone()));
+            let tokens = lookahead.iter().map(|(token, _)| token).collect::<Vec<_>>();
+            let found = self.find_repairs(&tokens, input_end.is_some());
+            let located = lookahead
+                .iter()
+                .map(|(token, span)| (token, span))
+                .collect::<Vec<_>>();
+            let repairs = found
+                .iter()
+                .map(|steps| Self::describe_repair(steps, &located, eof_span))
+                .collect();
+            match found.first() {
+                Some(best) if Self::can_apply(best) => {
+                    errors.push(RepairedError {
+                        message,
+                        span,
+                        repairs,
+                    });
+                    if let Err((err, span)) = self.apply_repair(best, &mut lookahead, eof_span) {
+                        return Err(Stop::Token(err, span, Some(Vec::new()), lookahead, None));
                     }
                 }
+                _ => {
+                    let (leftover_token, span) = lookahead.pop_front().unwrap();
+                    let err = ParseTokenError::ActionNotFound { leftover_token };
+                    return Err(Stop::Token(err, span, Some(repairs), lookahead, input_end));
+                }
             }
         }
+        Ok(())
+    }
 
+    /// Parses the end of input like `parse_input`
+    fn parse_end<E>(
+        &mut self,
+        eof_span: Option<Range<usize>>,
+        mut repaired: Option<&mut Vec<RepairedError>>,
+    ) -> Result<StartSymbol, Stop<NonTerminal, Token, E>> {
         loop {
-            match parser.parse_eof() {
-                Ok(ParseEof::Accepted) => {
-                    break;
-                }
-                Ok(ParseEof::Reduced) => {
-                    continue;
-                }
-                Err(err) => {
-                    return Err(LexParseError::ParseError(ParseError::new(
-                        parser,
-                        ParseOneError::ParseEofError(err),
-                    )));
+            let err = match self.push_eof(eof_span.clone()) {
+                Ok(start_symbol) => return Ok(start_symbol),
+                Err(err @ ParseEofError::ActionNotFound) => err,
+                Err(err) => return Err(Stop::Eof(err, eof_span, Some(Vec::new()))),
+            };
+            let Some(errors) = repaired.as_deref_mut() else {
+                return Err(Stop::Eof(err, eof_span, None));
+            };
+            let message = format!(
+                "ParseError: unexpected end of input, expected any of [{}]",
+                Tab::tokens_in_state(self.stacks.current_state()).join(", ")
+            );
+            let found = self.find_repairs(&[], true);
+            let mut lookahead = VecDeque::new();
+            let repairs = found
+                .iter()
+                .map(|steps| Self::describe_repair(steps, &[], &eof_span))
+                .collect();
+            match found.first() {
+                Some(best) if Self::can_apply(best) => {
+                    errors.push(RepairedError {
+                        message,
+                        span: eof_span.clone(),
+                        repairs,
+                    });
+                    if let Err((err, span)) = self.apply_repair(best, &mut lookahead, &eof_span) {
+                        return Err(Stop::Token(err, span, Some(Vec::new()), lookahead, None));
+                    }
                 }
+                _ => return Err(Stop::Eof(err, eof_span, Some(repairs))),
             }
         }
+    }
 
-        let Symbol::NonTerminal(non_terminal) = parser.stacks.symbol_stack.pop().unwrap() else {
-            unreachable!()
-        };
+    pub fn parse_with_ctx(
+        ctx: Ctx,
+        tokens: impl IntoIterator<Item = Token>,
+    ) -> Result<StartSymbol, ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>
+    where
+        Token: Display,
+    {
+        Self::start(ctx).resume(tokens)
+    }
 
-        Ok(non_terminal.into())
+    /// Parses the rest of the input, with a parser given back by `ParseError::resume_with` or
+    /// `ParseError::skip`
+    pub fn resume(
+        mut self,
+        tokens: impl IntoIterator<Item = Token>,
+    ) -> Result<StartSymbol, ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>
+    where
+        Token: Display,
+    {
+        let input = tokens.into_iter().map(|token| Ok((token, None)));
+        match self.parse_input::<Infallible>(input, None, None) {
+            Ok(start_symbol) => Ok(start_symbol),
+            Err(stop) => Err(stop
+                .into_parse_error(self)
+                .unwrap_or_else(|err| match err {})),
+        }
+    }
+
+    /// Parses like `parse_with_ctx`, but goes past the syntax errors by applying the best of
+    /// their repairs, giving them back with the start symbol
+    #[allow(clippy::type_complexity)]
+    pub fn parse_repairing_with_ctx(
+        ctx: Ctx,
+        tokens: impl IntoIterator<Item = Token>,
+    ) -> Result<
+        (StartSymbol, Vec<RepairedError>),
+        ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>,
+    >
+    where
+        Token: Display,
+    {
+        let mut parser = Self::start(ctx);
+        let mut repaired = Vec::new();
+        let input = tokens.into_iter().map(|token| Ok((token, None)));
+        match parser.parse_input::<Infallible>(input, None, Some(&mut repaired)) {
+            Ok(start_symbol) => Ok((start_symbol, repaired)),
+            Err(stop) => Err(stop
+                .into_parse_error(parser)
+                .unwrap_or_else(|err| match err {})),
+        }
+    }
+
+    pub fn parse_default_ctx(
+        tokens: impl IntoIterator<Item = Token>,
+    ) -> Result<StartSymbol, ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>
+    where
+        Token: Display,
+        Ctx: Default,
+    {
+        Self::parse_with_ctx(Default::default(), tokens)
+    }
+
+    /// Parses the tokens lexed from the source, each with its span
+    fn lex_parse_input<'source>(
+        mut self,
+        source: &'source Token::Source,
+        repaired: Option<&mut Vec<RepairedError>>,
+    ) -> Result<StartSymbol, LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>
+    where
+        Token: Logos<'source> + Display,
+        Token::Extras: Default,
+    {
+        let mut lexer = Token::lexer(source);
+        let input = std::iter::from_fn(|| {
+            let token = lexer.next()?;
+            Some(match token {
+                Ok(token) => Ok((token, Some(lexer.span()))),
+                Err(err) => Err((err, lexer.span())),
+            })
+        });
+        let eof_span = source.len()..source.len();
+        match self.parse_input(input, Some(eof_span), repaired) {
+            Ok(start_symbol) => Ok(start_symbol),
+            Err(stop) => Err(match stop.into_parse_error(self) {
+                Ok(parse_error) => LexParseError::ParseError(parse_error),
+                Err(((err, span), parser)) => {
+                    LexParseError::LexError(LexError::new(parser, err, span))
+                }
+            }),
+        }
+    }
+
+    pub fn lex_parse_with_ctx<'source>(
+        ctx: Ctx,
+        source: &'source Token::Source,
+    ) -> Result<StartSymbol, LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>
+    where
+        Token: Logos<'source> + Display,
+        Token::Extras: Default,
+    {
+        Self::start(ctx).lex_parse_input(source, None)
+    }
+
+    /// Parses like `lex_parse_with_ctx`, but goes past the syntax errors by applying the best of
+    /// their repairs, giving them back with the start symbol
+    #[allow(clippy::type_complexity)]
+    pub fn lex_parse_repairing_with_ctx<'source>(
+        ctx: Ctx,
+        source: &'source Token::Source,
+    ) -> Result<
+        (StartSymbol, Vec<RepairedError>),
+        LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>,
+    >
+    where
+        Token: Logos<'source> + Display,
+        Token::Extras: Default,
+    {
+        let mut repaired = Vec::new();
+        let start_symbol = Self::start(ctx).lex_parse_input(source, Some(&mut repaired))?;
+        Ok((start_symbol, repaired))
     }
 
     pub fn lex_parse_default_ctx<'source>(
         source: &'source Token::Source,
     ) -> Result<StartSymbol, LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>
     where
-        Token: Logos<'source>,
+        Token: Logos<'source> + Display,
         Token::Extras: Default,
         Ctx: Default,
     {
@@ -253,17 +575,89 @@ impl<
     }
 }
 
+/// Why `parse_input` stopped: an `Err` item of the input, or a syntax error with the span of
+/// the token or the end of input it is found on and its repairs, if they were searched. A syntax
+/// error on a token also keeps the tokens read past it, and the span of the end of input if the
+/// input ends right after them
+enum Stop<NonTerminal, Token, E> {
+    Input(E),
+    Token(
+        ParseTokenError<NonTerminal, Token>,
+        Option<Range<usize>>,
+        Option<Vec<Vec<Repair>>>,
+        VecDeque<(Token, Option<Range<usize>>)>,
+        Option<Option<Range<usize>>>,
+    ),
+    Eof(
+        ParseEofError<NonTerminal>,
+        Option<Range<usize>>,
+        Option<Vec<Vec<Repair>>>,
+    ),
+}
+
+impl<NonTerminal, Token, E> Stop<NonTerminal, Token, E> {
+    /// The syntax error, owning the parser, or the `Err` item of the input along with the parser
+    #[allow(clippy::type_complexity)]
+    fn into_parse_error<
+        StartSymbol,
+        Prod: Reduce<NonTerminal, Token, Ctx>,
+        Tab: Tables<NonTerminal, Token, Prod>,
+        Ctx,
+    >(
+        self,
+        parser: Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>,
+    ) -> Result<
+        ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>,
+        (E, Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>),
+    >
+    where
+        NonTerminal: Into<StartSymbol>,
+    {
+        let (parse_error, span, repairs, pending, input_end) = match self {
+            Stop::Input(err) => return Err((err, parser)),
+            Stop::Token(err, span, repairs, pending, input_end) => (
+                ParseOneError::ParseTokenError(err),
+                span,
+                repairs,
+                pending,
+                input_end,
+            ),
+            Stop::Eof(err, span, repairs) => (
+                ParseOneError::ParseEofError(err),
+                span.clone(),
+                repairs,
+                VecDeque::new(),
+                Some(span),
+            ),
+        };
+        let parse_error = ParseError::new(parser, parse_error)
+            .with_pending(pending)
+            .with_input_end(input_end);
+        let parse_error = match repairs {
+            Some(repairs) => parse_error.with_repairs(repairs),
+            None => parse_error,
+        };
+        Ok(match span {
+            Some(span) => parse_error.with_span(span),
+            None => parse_error,
+        })
+    }
+}
+
 impl<
     NonTerminal: Into<StartSymbol>,
     Token,
     StartSymbol,
-    Prod: Reduce<NonTerminal, Token, ()>,
+    Prod: Reduce<NonTerminal, Token, ()> + ProductionShape,
     Tab: Tables<NonTerminal, Token, Prod>,
 > Parser<NonTerminal, Token, StartSymbol, Prod, Tab, ()>
 {
     pub fn parse(
         tokens: impl Iterator<Item = Token>,
-    ) -> Result<StartSymbol, ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, ()>> {
+    ) -> Result<StartSymbol, ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, ()>>
+    where
+        Token: Display,
+    {
         Self::parse_with_ctx((), tokens)
     }
 
@@ -271,9 +665,23 @@ impl<
         source: &'source Token::Source,
     ) -> Result<StartSymbol, LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, ()>>
     where
-        Token: Logos<'source>,
+        Token: Logos<'source> + Display,
         Token::Extras: Default,
     {
         Self::lex_parse_with_ctx((), source)
     }
+
+    #[allow(clippy::type_complexity)]
+    pub fn lex_parse_repairing<'source>(
+        source: &'source Token::Source,
+    ) -> Result<
+        (StartSymbol, Vec<RepairedError>),
+        LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, ()>,
+    >
+    where
+        Token: Logos<'source> + Display,
+        Token::Extras: Default,
+    {
+        Self::lex_parse_repairing_with_ctx((), source)
+    }
 }
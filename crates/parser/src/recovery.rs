@@ -0,0 +1,94 @@
+use crate::{Parser, ProductionShape, Reduce, Symbol, Tables, TokenAction};
+use std::ops::Range;
+
+/// What the parser threw away to recover from a syntax error, carried by the error token to the
+/// semantic action of the production recovering from it. The symbols reduced on the error token
+/// before shifting it are kept on the stack instead
+#[derive(Debug)]
+pub struct ErrorRecovery<NonTerminal, Token> {
+    /// Symbols popped off the stack until a state could shift the error token, bottom first
+    pub popped: Vec<Symbol<NonTerminal, Token>>,
+    /// Tokens discarded until one could follow the error token, starting from the one the error
+    /// was found on
+    pub skipped: Vec<Token>,
+    /// Bytes of the source from the token the error was found on to the last skipped one, known
+    /// when the parser lexed the source itself
+    pub span: Option<Range<usize>>,
+}
+
+impl<
+    NonTerminal: Into<StartSymbol>,
+    Token,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, Ctx> + ProductionShape,
+    Tab: Tables<NonTerminal, Token, Prod>,
+    Ctx,
+> Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
+{
+    /// Pops the stacks and reduces on the error token until a state shifts it, `span` being where
+    /// the error was found. Leaves the stacks untouched if no state on them has an action on the
+    /// error token, and gives back the non-terminal whose goto is missing if a reduction fails
+    pub(crate) fn start_recovery(
+        &mut self,
+        span: Option<Range<usize>>,
+    ) -> Result<bool, NonTerminal> {
+        if !self
+            .stacks
+            .state_stack
+            .iter()
+            .any(|state| Tab::query_error_action(*state).is_some())
+        {
+            return Ok(false);
+        }
+        let mut popped = Vec::new();
+        loop {
+            match Tab::query_error_action(self.stacks.current_state()) {
+                Some(TokenAction::Shift(_)) => break,
+                Some(TokenAction::Reduce(prod)) => self.reduce(prod)?,
+                None => match self.stacks.symbol_stack.pop() {
+                    Some(symbol) => {
+                        self.stacks.state_stack.pop();
+                        popped.push(symbol);
+                    }
+                    None => return Ok(false),
+                },
+            }
+        }
+        popped.reverse();
+        self.recovery = Some(Box::new(ErrorRecovery {
+            popped,
+            skipped: Vec::new(),
+            span,
+        }));
+        Ok(true)
+    }
+
+    /// State the pending recovery shifts the error token to
+    pub(crate) fn error_state(&self) -> usize {
+        match Tab::query_error_action(self.stacks.current_state()) {
+            Some(TokenAction::Shift(error_state)) => error_state,
+            _ => unreachable!("recoveries stop at states shifting the error token"),
+        }
+    }
+
+    /// Discards a token that can't follow the error token
+    pub(crate) fn skip_token(&mut self, token: Token, span: Option<Range<usize>>) {
+        let recovery = self.recovery.as_mut().expect("no recovery is pending");
+        recovery.skipped.push(token);
+        if let (Some(recovery_span), Some(span)) = (recovery.span.as_mut(), span) {
+            recovery_span.end = span.end;
+        }
+    }
+
+    /// Shifts the error token carrying what the pending recovery threw away. Gives back the
+    /// non-terminal whose goto is missing if the reductions following the shift fail
+    pub(crate) fn shift_error_token(&mut self) -> Result<(), NonTerminal> {
+        let error_state = self.error_state();
+        let recovery = self.recovery.take().expect("no recovery is pending");
+        let error_token = Tab::error_token(*recovery)
+            .expect("only the grammars declaring an error token have states shifting it");
+        self.stacks.shift(error_state, error_token);
+        self.recovered = true;
+        self.reduce_immediately()
+    }
+}
@@ -0,0 +1,317 @@
+use crate::{
+    EofAction, Parser, ProductionShape, Reduce, Tables, TokenAction,
+    results::{ParseTokenError, line_column, render_span},
+};
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt::Display,
+    ops::Range,
+};
+
+/// Most edits a repair makes
+const MAX_COST: usize = 3;
+/// Tokens following the one a syntax error is found on that a repair has to let the parser shift
+pub(crate) const LOOKAHEAD: usize = 3;
+
+/// An edit of the input letting the parse go on past a syntax error
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repair {
+    /// Inserting the token, written as its literal or its name, where `span` starts
+    Insert {
+        token: &'static str,
+        span: Option<Range<usize>>,
+    },
+    /// Deleting the token in `span`
+    Delete {
+        token: &'static str,
+        span: Option<Range<usize>>,
+    },
+}
+
+impl Repair {
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            Repair::Insert { span, .. } | Repair::Delete { span, .. } => span.clone(),
+        }
+    }
+
+    /// The edit followed by the line and column of `source` it is at, if known
+    pub fn render(&self, source: &str) -> String {
+        match self.span() {
+            Some(span) => {
+                let (line, column) = line_column(source, span.start);
+                format!("{self} at {line}:{column}")
+            }
+            None => self.to_string(),
+        }
+    }
+}
+
+impl Display for Repair {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Repair::Insert { token, .. } => write!(f, "insert {token}"),
+            Repair::Delete { token, .. } => write!(f, "delete {token}"),
+        }
+    }
+}
+
+/// One line per sequence of repairs, each edit at its position in `source`
+pub(crate) fn render_repairs(source: &str, repairs: &[Vec<Repair>]) -> String {
+    repairs
+        .iter()
+        .map(|repair| {
+            let edits = repair.iter().map(|edit| edit.render(source));
+            format!("\n  = help: {}", edits.collect::<Vec<_>>().join(", then "))
+        })
+        .collect()
+}
+
+/// A syntax error the parser went past by applying the first of its repairs
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RepairedError {
+    /// The error, as `ParseError` describes it
+    pub message: String,
+    /// Bytes of the source the error is at, known when the parser lexed the source itself
+    pub span: Option<Range<usize>>,
+    /// The cheapest sequences of edits found, the applied one first
+    pub repairs: Vec<Vec<Repair>>,
+}
+
+impl RepairedError {
+    /// The error followed, when its span is known, by its position and the line of `source` it
+    /// is on, and by the repairs found
+    pub fn render(&self, source: &str) -> String {
+        let error = match &self.span {
+            Some(span) => render_span(source, span.clone(), &self.message),
+            None => self.message.clone(),
+        };
+        error + &render_repairs(source, &self.repairs)
+    }
+}
+
+impl Display for RepairedError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// A step of the search of repairs: an edit of the input, or parsing its next token as is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Step {
+    Insert(usize),
+    Delete,
+    Shift,
+}
+
+struct Configuration {
+    states: Vec<usize>,
+    /// Tokens of the lookahead deleted or shifted
+    position: usize,
+    steps: Vec<Step>,
+}
+
+/// Follows the reductions on the states alone, without running semantic actions, until the
+/// action is a shift or an acceptance. False if the action is missing
+fn simulate<Prod: ProductionShape>(
+    states: &mut Vec<usize>,
+    action: impl Fn(usize) -> Option<TokenAction<Prod>>,
+    goto: impl Fn(usize, usize) -> Option<usize>,
+) -> bool {
+    loop {
+        let state = *states.last().expect("state stack is empty!");
+        match action(state) {
+            Some(TokenAction::Shift(target)) => {
+                states.push(target);
+                return true;
+            }
+            Some(TokenAction::Reduce(prod)) => {
+                states.truncate(states.len() - prod.arity());
+                let state = *states.last().expect("state stack is empty!");
+                let Some(target) = goto(state, prod.head()) else {
+                    return false;
+                };
+                states.push(target);
+            }
+            None => return false,
+        }
+    }
+}
+
+impl<
+    NonTerminal: Into<StartSymbol>,
+    Token,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, Ctx> + ProductionShape,
+    Tab: Tables<NonTerminal, Token, Prod>,
+    Ctx,
+> Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
+{
+    /// The cheapest sequences of steps, up to `MAX_COST` edits, letting the parser shift every
+    /// token of `lookahead` after the last edit, and accept if the input `ends` there. Searched
+    /// by cost on the states alone, keeping the first repair reaching each configuration. The
+    /// ones whose insertions can all be built come first
+    pub(crate) fn find_repairs(&self, lookahead: &[&Token], ends: bool) -> Vec<Vec<Step>> {
+        let eof_action = |state| match Tab::query_eof_table(state)? {
+            EofAction::Reduce(prod) => Some(TokenAction::Reduce(prod)),
+            // the acceptance stands for a shift, ending the simulation
+            EofAction::Accept => Some(TokenAction::Shift(state)),
+        };
+        let mut levels: Vec<VecDeque<Configuration>> =
+            (0..=MAX_COST).map(|_| VecDeque::new()).collect();
+        levels[0].push_back(Configuration {
+            states: self.stacks.state_stack.clone(),
+            position: 0,
+            steps: Vec::new(),
+        });
+        // state stacks reached by each position of the lookahead, the cheapest first
+        let mut seen = vec![HashSet::new(); lookahead.len() + 1];
+        for cost in 0..=MAX_COST {
+            let mut found = Vec::new();
+            while let Some(configuration) = levels[cost].pop_front() {
+                if seen[configuration.position].contains(&configuration.states) {
+                    continue;
+                }
+                seen[configuration.position].insert(configuration.states.clone());
+                let mut states = configuration.states.clone();
+                let mut steps = configuration.steps.clone();
+                steps.push(Step::Shift);
+                match lookahead.get(configuration.position) {
+                    Some(token)
+                        if simulate(
+                            &mut states,
+                            |state| Tab::query_token_table(state, token),
+                            Tab::query_goto_id,
+                        ) =>
+                    {
+                        if configuration.position + 1 == lookahead.len() && !ends {
+                            found.push(steps);
+                        } else {
+                            levels[cost].push_back(Configuration {
+                                states,
+                                position: configuration.position + 1,
+                                steps,
+                            });
+                        }
+                    }
+                    None if ends && simulate(&mut states, eof_action, Tab::query_goto_id) => {
+                        found.push(configuration.steps.clone());
+                    }
+                    _ => {}
+                }
+                if cost == MAX_COST {
+                    continue;
+                }
+                // an insertion after a deletion is searched as the deletion after the insertion
+                if configuration.steps.last() != Some(&Step::Delete) {
+                    for &token in Tab::insertable_tokens() {
+                        let mut states = configuration.states.clone();
+                        if simulate(
+                            &mut states,
+                            |state| Tab::query_token_id(state, token),
+                            Tab::query_goto_id,
+                        ) {
+                            let mut steps = configuration.steps.clone();
+                            steps.push(Step::Insert(token));
+                            levels[cost + 1].push_back(Configuration {
+                                states,
+                                position: configuration.position,
+                                steps,
+                            });
+                        }
+                    }
+                }
+                if configuration.position < lookahead.len() {
+                    let mut steps = configuration.steps.clone();
+                    steps.push(Step::Delete);
+                    levels[cost + 1].push_back(Configuration {
+                        states: configuration.states,
+                        position: configuration.position + 1,
+                        steps,
+                    });
+                }
+            }
+            if !found.is_empty() {
+                found.sort_by_key(|steps| !Self::can_apply(steps));
+                return found;
+            }
+        }
+        Vec::new()
+    }
+
+    /// Whether every token the repair inserts can be built without a source
+    pub(crate) fn can_apply(steps: &[Step]) -> bool {
+        steps.iter().all(|step| match step {
+            Step::Insert(token) => Tab::insert_token(*token).is_some(),
+            Step::Delete | Step::Shift => true,
+        })
+    }
+
+    /// The edits of the steps, located with the spans of the `lookahead` tokens, or of the end
+    /// of input past them
+    pub(crate) fn describe_repair(
+        steps: &[Step],
+        lookahead: &[(&Token, &Option<Range<usize>>)],
+        eof_span: &Option<Range<usize>>,
+    ) -> Vec<Repair> {
+        let mut position = 0;
+        steps
+            .iter()
+            .filter_map(|step| match step {
+                Step::Insert(token) => Some(Repair::Insert {
+                    token: Tab::token_spelling(*token),
+                    span: match lookahead.get(position) {
+                        Some((_, span)) => span.as_ref().map(|span| span.start..span.start),
+                        None => eof_span.clone(),
+                    },
+                }),
+                Step::Delete => {
+                    let (token, span) = lookahead[position];
+                    position += 1;
+                    Some(Repair::Delete {
+                        token: Tab::token_spelling(Tab::token_id(token)),
+                        span: span.clone(),
+                    })
+                }
+                Step::Shift => {
+                    position += 1;
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Parses the input as the steps edit it, taking the tokens from `lookahead`. Gives back the
+    /// error and the span of the token it is found on if the parse fails anyway
+    #[allow(clippy::type_complexity)]
+    pub(crate) fn apply_repair(
+        &mut self,
+        steps: &[Step],
+        lookahead: &mut VecDeque<(Token, Option<Range<usize>>)>,
+        eof_span: &Option<Range<usize>>,
+    ) -> Result<(), (ParseTokenError<NonTerminal, Token>, Option<Range<usize>>)> {
+        for step in steps {
+            let (token, span) = match step {
+                Step::Insert(token) => {
+                    let span = match lookahead.front() {
+                        Some((_, span)) => span.clone().map(|span| span.start..span.start),
+                        None => eof_span.clone(),
+                    };
+                    let token =
+                        Tab::insert_token(*token).expect("only buildable insertions are applied");
+                    (token, span)
+                }
+                Step::Delete => {
+                    lookahead.pop_front();
+                    continue;
+                }
+                Step::Shift => lookahead
+                    .pop_front()
+                    .expect("repairs only shift the tokens of the lookahead"),
+            };
+            self.push_token(token, span.clone())
+                .map_err(|err| (err, span))?;
+        }
+        Ok(())
+    }
+}
@@ -1,9 +1,9 @@
-use std::fmt::{Display, Pointer};
+use std::{cell::OnceCell, convert::Infallible, fmt::Display, ops::Range};
 
 use itertools::Itertools;
 use logos::Logos;
 
-use crate::{Parser, Reduce, Tables};
+use crate::{Parser, ProductionShape, Reduce, Repair, Tables, repair::render_repairs};
 
 #[derive(Debug)]
 pub enum ParseToken<Token> {
@@ -44,8 +44,17 @@ pub struct ParseError<
     Tab: Tables<NonTerminal, Token, Prod>,
     Ctx,
 > {
-    parser: Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>,
+    parser: Box<Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>>,
     parse_one_error: ParseOneError<NonTerminal, Token>,
+    /// Bytes of the source the error is at, known when the parser lexed the source itself
+    span: Option<Range<usize>>,
+    /// The cheapest sequences of edits of the input found to go past the error, searched when
+    /// they are first asked for
+    repairs: OnceCell<Box<[Vec<Repair>]>>,
+    /// Tokens read past the error, with their spans, parsed first when resuming
+    pending: Box<[(Token, Option<Range<usize>>)]>,
+    /// Where the input ends in the source, if it is known to end right after the pending tokens
+    input_end: Option<Option<Range<usize>>>,
 }
 
 impl<
@@ -62,8 +71,143 @@ impl<
         parse_one_error: ParseOneError<NonTerminal, Token>,
     ) -> Self {
         Self {
-            parser,
+            parser: Box::new(parser),
             parse_one_error,
+            span: None,
+            repairs: OnceCell::new(),
+            pending: Box::new([]),
+            input_end: None,
+        }
+    }
+
+    pub fn with_span(mut self, span: Range<usize>) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.clone()
+    }
+
+    pub fn with_repairs(mut self, repairs: Vec<Vec<Repair>>) -> Self {
+        self.repairs = OnceCell::from(repairs.into_boxed_slice());
+        self
+    }
+
+    pub(crate) fn with_pending(
+        mut self,
+        pending: impl IntoIterator<Item = (Token, Option<Range<usize>>)>,
+    ) -> Self {
+        self.pending = pending.into_iter().collect();
+        self
+    }
+
+    pub(crate) fn with_input_end(mut self, input_end: Option<Option<Range<usize>>>) -> Self {
+        self.input_end = input_end;
+        self
+    }
+
+    /// The token no action was found on, if the error is on a token
+    pub fn leftover_token(&self) -> Option<&Token> {
+        match &self.parse_one_error {
+            ParseOneError::ParseTokenError(ParseTokenError::ActionNotFound { leftover_token }) => {
+                Some(leftover_token)
+            }
+            _ => None,
+        }
+    }
+
+    /// The non-terminal no goto was found on after its reduction, if the error is on one
+    pub fn leftover_non_terminal(&self) -> Option<&NonTerminal> {
+        match &self.parse_one_error {
+            ParseOneError::ParseTokenError(ParseTokenError::GotoNotFound {
+                leftover_non_terminal,
+            })
+            | ParseOneError::ParseEofError(ParseEofError::GotoNotFound {
+                leftover_non_terminal,
+            }) => Some(leftover_non_terminal),
+            _ => None,
+        }
+    }
+}
+
+impl<
+    NonTerminal: Into<StartSymbol>,
+    Token: Display,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, Ctx> + ProductionShape,
+    Tab: Tables<NonTerminal, Token, Prod>,
+    Ctx,
+> ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
+{
+    /// The cheapest sequences of edits of the input going past the error, searched on the tokens
+    /// read past it the first time they are asked for
+    pub fn repairs(&self) -> &[Vec<Repair>] {
+        self.repairs.get_or_init(|| {
+            let leftover = match &self.parse_one_error {
+                ParseOneError::ParseTokenError(ParseTokenError::ActionNotFound {
+                    leftover_token,
+                }) => Some((leftover_token, &self.span)),
+                ParseOneError::ParseEofError(ParseEofError::ActionNotFound) => None,
+                _ => return Box::new([]),
+            };
+            let lookahead = leftover
+                .into_iter()
+                .chain(self.pending.iter().map(|(token, span)| (token, span)))
+                .collect::<Vec<_>>();
+            let tokens = lookahead
+                .iter()
+                .map(|(token, _)| *token)
+                .collect::<Vec<_>>();
+            let eof_span = self.input_end.clone().flatten();
+            self.parser
+                .find_repairs(&tokens, self.input_end.is_some())
+                .iter()
+                .map(|steps| {
+                    Parser::<_, _, _, _, Tab, _>::describe_repair(steps, &lookahead, &eof_span)
+                })
+                .collect()
+        })
+    }
+
+    /// Parses `token` where the parse stopped, then the leftover token and the tokens read past
+    /// it, giving back the parser to go on with the rest of the input with `Parser::resume`
+    pub fn resume_with(
+        self,
+        token: Token,
+    ) -> Result<Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>, Self> {
+        self.resume_parsing(Some(token))
+    }
+
+    /// Drops the leftover token and parses the tokens read past it, giving back the parser to go
+    /// on with the rest of the input with `Parser::resume`
+    pub fn skip(self) -> Result<Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>, Self> {
+        self.resume_parsing(None)
+    }
+
+    /// Parses the pending tokens, after the `inserted` token and the leftover one if any. A
+    /// leftover non-terminal is lost, its symbols being already popped
+    fn resume_parsing(
+        self,
+        inserted: Option<Token>,
+    ) -> Result<Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>, Self> {
+        let mut input = Vec::new();
+        if let Some(token) = inserted {
+            input.push((token, self.span.clone().map(|span| span.start..span.start)));
+            if let ParseOneError::ParseTokenError(ParseTokenError::ActionNotFound {
+                leftover_token,
+            }) = self.parse_one_error
+            {
+                input.push((leftover_token, self.span));
+            }
+        }
+        input.extend(self.pending);
+        let mut parser = *self.parser;
+        match parser.parse_tokens::<Infallible>(input.into_iter().map(Ok), &None, false, None) {
+            Ok(()) => Ok(parser),
+            Err(stop) => Err(stop
+                .into_parse_error(parser)
+                .unwrap_or_else(|err| match err {})),
         }
     }
 }
@@ -78,12 +222,51 @@ impl<
 > Display for ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
 {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "ParseError: after [{}] expected any of [{}]",
-            self.parser.stacks.symbol_stack.iter().format(", "),
-            Tab::tokens_in_state(self.parser.stacks.current_state()).iter().format(", ")
-        )
+        let expected = Tab::tokens_in_state(self.parser.stacks.current_state());
+        match &self.parse_one_error {
+            ParseOneError::ParseTokenError(ParseTokenError::ActionNotFound { leftover_token }) => {
+                write!(
+                    f,
+                    "ParseError: unexpected {leftover_token}, expected any of [{}]",
+                    expected.iter().format(", ")
+                )
+            }
+            ParseOneError::ParseEofError(ParseEofError::ActionNotFound) => write!(
+                f,
+                "ParseError: unexpected end of input, expected any of [{}]",
+                expected.iter().format(", ")
+            ),
+            ParseOneError::ParseTokenError(ParseTokenError::GotoNotFound {
+                leftover_non_terminal,
+            })
+            | ParseOneError::ParseEofError(ParseEofError::GotoNotFound {
+                leftover_non_terminal,
+            }) => write!(
+                f,
+                "ParseError: no transition on {leftover_non_terminal} after [{}]",
+                self.parser.stacks.symbol_stack.iter().format(", ")
+            ),
+        }
+    }
+}
+
+impl<
+    NonTerminal: Into<StartSymbol> + Display,
+    Token: Display,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, Ctx> + ProductionShape,
+    Tab: Tables<NonTerminal, Token, Prod>,
+    Ctx,
+> ParseError<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
+{
+    /// The error followed, when its span is known, by its position and the line of `source` it
+    /// is on, with a caret under the offending token, and by its repairs
+    pub fn render(&self, source: &str) -> String {
+        let error = match &self.span {
+            Some(span) => render_span(source, span.clone(), self),
+            None => self.to_string(),
+        };
+        error + &render_repairs(source, self.repairs())
     }
 }
 
@@ -99,6 +282,8 @@ pub struct LexError<
 > {
     parser: Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>,
     lexer_error: Token::Error,
+    /// Bytes of the source that match no token
+    span: Range<usize>,
 }
 
 impl<
@@ -114,12 +299,39 @@ impl<
     pub fn new(
         parser: Parser<NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>,
         lexer_error: Token::Error,
+        span: Range<usize>,
     ) -> Self {
         Self {
             parser,
             lexer_error,
+            span,
         }
     }
+
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The error followed by its position and the line of `source` it is on, with a caret under
+    /// the unrecognized characters
+    pub fn render(&self, source: &str) -> String {
+        render_span(source, self.span.clone(), self)
+    }
+}
+
+impl<
+    'source,
+    NonTerminal: Into<StartSymbol>,
+    Token: Logos<'source>,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, Ctx>,
+    Tab: Tables<NonTerminal, Token, Prod>,
+    Ctx,
+> Display for LexError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
+{
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "LexError: unrecognized token")
+    }
 }
 
 #[derive(Debug)]
@@ -144,11 +356,79 @@ impl<
     Prod: Reduce<NonTerminal, Token, Ctx>,
     Tab: Tables<NonTerminal, Token, Prod>,
     Ctx,
-> Display for LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx> {
+> Display for LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
+{
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             LexParseError::LexError(lex_error) => lex_error.fmt(f),
-            LexParseError::ParseError(parse_error) => Display::fmt(parse_error, f),
+            LexParseError::ParseError(parse_error) => parse_error.fmt(f),
+        }
+    }
+}
+
+impl<
+    'source,
+    NonTerminal: Into<StartSymbol> + Display,
+    Token: Logos<'source> + Display,
+    StartSymbol,
+    Prod: Reduce<NonTerminal, Token, Ctx> + ProductionShape,
+    Tab: Tables<NonTerminal, Token, Prod>,
+    Ctx,
+> LexParseError<'source, NonTerminal, Token, StartSymbol, Prod, Tab, Ctx>
+{
+    pub fn span(&self) -> Option<Range<usize>> {
+        match self {
+            LexParseError::LexError(lex_error) => Some(lex_error.span()),
+            LexParseError::ParseError(parse_error) => parse_error.span(),
         }
     }
+
+    /// The error followed by its position and the line of `source` it is on, with a caret under
+    /// the offending characters
+    pub fn render(&self, source: &str) -> String {
+        match self {
+            LexParseError::LexError(lex_error) => lex_error.render(source),
+            LexParseError::ParseError(parse_error) => parse_error.render(source),
+        }
+    }
+}
+
+/// Line and column, both starting at 1, of the byte `offset` of `source`, the column counting
+/// characters
+pub fn line_column(source: &str, offset: usize) -> (usize, usize) {
+    let before = &source[..offset];
+    let line_start = before.rfind('\n').map_or(0, |newline| newline + 1);
+    (
+        before.matches('\n').count() + 1,
+        before[line_start..].chars().count() + 1,
+    )
+}
+
+/// `message` followed by the position of `span` and the line of `source` it starts on, with
+/// carets under the part of the span on that line:
+///
+/// ```text
+/// ParseError: unexpected Times, expected any of [Number, OpenPar]
+///  --> 1:5
+///   |
+/// 1 | 1*5+*3
+///   |     ^
+/// ```
+pub fn render_span(source: &str, span: Range<usize>, message: impl Display) -> String {
+    let (line, column) = line_column(source, span.start);
+    let line_start = source[..span.start]
+        .rfind('\n')
+        .map_or(0, |newline| newline + 1);
+    let line_text = source[line_start..].lines().next().unwrap_or_default();
+    let line_end = line_start + line_text.len();
+    let carets = source[span.start..span.end.min(line_end).max(span.start)]
+        .chars()
+        .count()
+        .max(1);
+    let gutter = " ".repeat(line.to_string().len());
+    format!(
+        "{message}\n{gutter}--> {line}:{column}\n{gutter} |\n{line} | {line_text}\n{gutter} | {}{}",
+        " ".repeat(column - 1),
+        "^".repeat(carets)
+    )
 }
@@ -1,13 +1,53 @@
-use crate::{EofAction, Stacks, TokenAction};
+use crate::{EofAction, ErrorRecovery, Stacks, TokenAction};
 
 pub trait Tables<NonTerminal, Token, Prod> {
     fn query_token_table(current_state: usize, current_token: &Token) -> Option<TokenAction<Prod>>;
     fn query_eof_table(current_state: usize) -> Option<EofAction<Prod>>;
     fn query_goto_table(current_state: usize, non_terminal: &NonTerminal) -> Option<usize>;
-    fn tokens_in_state(current_state: usize) -> &'static[&'static str];
+    /// Action of the state on the token with the id, for the parsers that don't hold a token of
+    /// that kind
+    fn query_token_id(current_state: usize, token: usize) -> Option<TokenAction<Prod>>;
+    fn query_goto_id(current_state: usize, non_terminal: usize) -> Option<usize>;
+    /// The production to reduce in a state whose only action is reducing it, which then needs no
+    /// lookahead
+    fn query_immediate_reduction(current_state: usize) -> Option<Prod>;
+    /// Converts the non-terminal the goto of the state is followed on into the head of the unit
+    /// productions the goto bypasses, if any
+    fn convert_goto(current_state: usize, non_terminal: NonTerminal) -> NonTerminal;
+    /// Action of the state on the error token, to recover from a syntax error
+    fn query_error_action(current_state: usize) -> Option<TokenAction<Prod>>;
+    /// The error token carrying what the recovery threw away, if the grammar declares one
+    fn error_token(recovery: ErrorRecovery<NonTerminal, Token>) -> Option<Token>;
+    fn tokens_in_state(current_state: usize) -> &'static [&'static str];
+    fn token_id(token: &Token) -> usize;
+    /// How the token with the id is written in repairs: its literal, or its name if it matches a
+    /// regex
+    fn token_spelling(token: usize) -> &'static str;
+    /// Ids of the tokens repairs can insert, every token but the error token
+    fn insertable_tokens() -> &'static [usize];
+    /// The token with the id, if its value doesn't come from the source, for repairs to insert
+    fn insert_token(token: usize) -> Option<Token>;
 }
 
 pub trait Reduce<NonTerminal, Token, Ctx> {
     fn reduce(&self, ctx: &mut Ctx, stacks: &mut Stacks<NonTerminal, Token>) -> NonTerminal;
 }
 
+/// Tables of a GLR parser: every action of a cell is kept, conflicting ones included
+pub trait GlrTables<NonTerminal, Token, Prod>: Tables<NonTerminal, Token, Prod> {
+    fn query_token_actions(current_state: usize, current_token: &Token) -> Vec<TokenAction<Prod>>;
+    fn query_eof_actions(current_state: usize) -> Vec<EofAction<Prod>>;
+}
+
+/// Shape of a production, needed to reduce before running any semantic action: by the GLR parser,
+/// and by the search of repairs
+pub trait ProductionShape {
+    fn arity(&self) -> usize;
+    fn head(&self) -> usize;
+}
+
+pub trait Merge: Sized {
+    /// Merges two derivations of the same non-terminal over the same input, giving them back if
+    /// the non-terminal has no merge function
+    fn merge(self, other: Self) -> Result<Self, (Self, Self)>;
+}
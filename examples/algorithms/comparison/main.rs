@@ -0,0 +1,46 @@
+use semasia::*;
+
+// The assignment grammar of the dragon book: `=` can follow an `R` (in `*R = ...`), so SLR(1)
+// reduces `R -> L` before `=` and conflicts with the shift, while LALR(1) knows that `=` cannot
+// follow the `R` of `S -> R`
+#[grammar(compare_algorithms = true)]
+mod assignments {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type S = String;
+
+    #[non_terminal]
+    pub type L = String;
+
+    #[non_terminal]
+    pub type R = String;
+
+    #[token("=")]
+    pub struct Eq;
+
+    #[token("*")]
+    pub struct Star;
+
+    #[token(regex = r"[a-z]+")]
+    pub type Id = String;
+
+    production!(P1, S -> (L, Eq, R), |(l, _, r)| format!("{l} <- {r}"));
+    production!(P2, S -> R);
+    production!(P3, L -> (Star, R), |(_, r)| format!("deref({r})"));
+    production!(P4, L -> Id);
+    production!(P5, R -> L);
+}
+
+use assignments::*;
+
+fn main() {
+    print!("{ALGORITHM_COMPARISON}");
+    for input in ["*p = x", "**p = *x", "x"] {
+        match Parser::lex_parse(input) {
+            Ok(res) => println!("{input}: {res}"),
+            Err(err) => println!("{input}: error: {err}"),
+        }
+    }
+}
@@ -0,0 +1,52 @@
+use semasia::*;
+
+// LALR(1) merges the states reached after `a e` and `b e`, making `E -> e` and `F -> e` clash on
+// both `c` and `d`: only canonical LR(1), or minimal LR(1) with `algorithm = "minimal-lr1"`, can
+// build conflict-free tables for this grammar
+#[grammar(algorithm = "lr1")]
+mod lr1_only {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type S = &'static str;
+
+    #[non_terminal]
+    pub struct E;
+
+    #[non_terminal]
+    pub struct F;
+
+    #[token("a")]
+    pub struct A;
+
+    #[token("b")]
+    pub struct B;
+
+    #[token("c")]
+    pub struct C;
+
+    #[token("d")]
+    pub struct D;
+
+    #[token("e")]
+    pub struct Eps;
+
+    production!(P1, S -> (A, E, C), |_| "a E c");
+    production!(P2, S -> (A, F, D), |_| "a F d");
+    production!(P3, S -> (B, F, C), |_| "b F c");
+    production!(P4, S -> (B, E, D), |_| "b E d");
+    production!(P5, E -> Eps, |_| E);
+    production!(P6, F -> Eps, |_| F);
+}
+
+use lr1_only::*;
+
+fn main() {
+    for input in ["aec", "aed", "bec", "bed"] {
+        match Parser::lex_parse(input) {
+            Ok(res) => println!("{input} is {res}"),
+            Err(err) => println!("{err}"),
+        }
+    }
+}
@@ -1,4 +1,4 @@
-use static_sdd::*;
+use semasia::*;
 
 #[grammar]
 mod ambiguous {
@@ -32,43 +32,40 @@ mod ambiguous {
     #[token(")")]
     pub struct ClosePar;
 
-    // #[precedence = 0]
-    // #[left_associative]
+    #[precedence = 0]
+    #[left_associative]
     production!(P1, E -> (E, Plus, E), |(e1, _, e2)| e1 + e2);
 
-    // #[precedence = 0]
-    // #[left_associative]
+    #[precedence = 0]
+    #[left_associative]
     production!(P2, E -> (E, Minus, E), |(e1, _, e2)| e1 - e2);
 
-    // #[precedence = 1]
-    // #[left_associative]
+    #[precedence = 1]
+    #[left_associative]
     production!(P3, E -> (E, Times, E), |(e1, _, e2)| e1 * e2);
 
-    // #[precedence = 1]
-    // #[left_associative]
-    production!(P4, E -> (E, Division, E), |(e1, _, e2)| e1 * e2);
+    #[precedence = 1]
+    #[left_associative]
+    production!(P4, E -> (E, Division, E), |(e1, _, e2)| e1 / e2);
 
-    // #[precedence = 2]
-    // #[right_associative]
+    #[precedence = 2]
+    #[right_associative]
     production!(P5, E -> (E, Power, E), |(e1, _, e2)| e1.pow(e2 as u32));
 
-    // #[precedence = 3]
+    #[precedence = 3]
     production!(P6, E -> (OpenPar, E, ClosePar), |(_, e, _)| e);
 
-    // #[precedence = 3]
+    #[precedence = 3]
     production!(P7, E -> Id);
 }
 
 use ambiguous::*;
 
 fn main() {
-    let res = parse((), [
-        Token::Id(5),
-        Token::Power(Power),
-        Token::Id(2),
-        Token::Minus(Minus),
-        Token::Id(1),
-    ]).ok().expect("couldn't parse");
-
-    println!("{res}");
+    let res = Parser::lex_parse("5^2-1");
+
+    match res {
+        Ok(res) => println!("result is {res}"),
+        Err(err) => println!("{err}"),
+    }
 }
@@ -0,0 +1,63 @@
+use semasia::*;
+use std::time::Instant;
+
+// The arithmetic grammar generated with each table encoding, to compare how fast they parse the
+// same input. The sizes of the two encodings on a real language are reported by the ANSI C
+// benchmark of `dyn_grammar`
+macro_rules! arithmetic {
+    ($name:ident, $($options:tt)*) => {
+        #[grammar($($options)*)]
+        mod $name {
+            use super::*;
+
+            #[start_symbol]
+            #[non_terminal]
+            pub type Expression = usize;
+
+            #[non_terminal]
+            pub type Term = usize;
+
+            #[non_terminal]
+            pub type Factor = usize;
+
+            #[token(regex = r"\d+")]
+            pub type Number = usize;
+
+            #[token("+")]
+            pub struct Plus;
+
+            #[token("*")]
+            pub struct Times;
+
+            #[token("(")]
+            pub struct OpenPar;
+
+            #[token(")")]
+            pub struct ClosedPar;
+
+            production!(Addition, Expression -> (Expression, Plus, Term), |(e, _, t)| e.wrapping_add(t));
+            production!(NoAddition, Expression -> Term);
+            production!(Multiplication, Term -> (Term, Times, Factor), |(t, _, f)| t.wrapping_mul(f));
+            production!(NoMultiplication, Term -> Factor);
+            production!(Parenthesis, Factor -> (OpenPar, Expression, ClosedPar), |(_, e, _)| e);
+            production!(ActualNumber, Factor -> Number);
+        }
+    };
+}
+
+arithmetic!(matched, tables = "match");
+arithmetic!(compressed, tables = "compressed");
+
+fn main() {
+    let input = vec!["(1 + 2) * 3 + 4 * (5 + 6 * (7 + 8))"; 20_000].join(" + ");
+
+    let start = Instant::now();
+    let matched = matched::Parser::lex_parse(&input).unwrap();
+    println!("match tables:      {:?}", start.elapsed());
+
+    let start = Instant::now();
+    let compressed = compressed::Parser::lex_parse(&input).unwrap();
+    println!("compressed tables: {:?}", start.elapsed());
+
+    assert_eq!(matched, compressed);
+}
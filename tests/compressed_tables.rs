@@ -0,0 +1,104 @@
+use semasia::*;
+
+/// The arithmetic grammar, once with each table encoding
+macro_rules! arithmetic {
+    ($name:ident, $($options:tt)*) => {
+        #[grammar($($options)*)]
+        mod $name {
+            use super::*;
+
+            #[start_symbol]
+            #[non_terminal]
+            pub type Expression = usize;
+
+            #[non_terminal]
+            pub type Term = usize;
+
+            #[non_terminal]
+            pub type Factor = usize;
+
+            #[token(regex = r"\d+")]
+            pub type Number = usize;
+
+            #[token("+")]
+            pub struct Plus;
+
+            #[token("*")]
+            pub struct Times;
+
+            #[token("(")]
+            pub struct OpenPar;
+
+            #[token(")")]
+            pub struct ClosedPar;
+
+            production!(Addition, Expression -> (Expression, Plus, Term), |(e, _, t)| e + t);
+            production!(NoAddition, Expression -> Term);
+            production!(Multiplication, Term -> (Term, Times, Factor), |(t, _, f)| t * f);
+            production!(NoMultiplication, Term -> Factor);
+            production!(Parenthesis, Factor -> (OpenPar, Expression, ClosedPar), |(_, e, _)| e);
+            production!(ActualNumber, Factor -> Number);
+        }
+    };
+}
+
+arithmetic!(matched, tables = "match");
+arithmetic!(compressed, tables = "compressed");
+arithmetic!(compressed_lr1, algorithm = "lr1", tables = "compressed");
+
+/// Ambiguous, parsed by GLR from compressed tables without default reductions
+#[grammar(glr = true, tables = "compressed")]
+mod ambiguous {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type E = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Id = usize;
+
+    #[token("+")]
+    #[derive(Clone)]
+    pub struct Plus;
+
+    production!(P1, E -> (E, Plus, E), |(e1, _, e2)| e1 + e2);
+    production!(P2, E -> Id);
+
+    #[merge]
+    fn merge_e(first: E, _second: E) -> E {
+        first
+    }
+}
+
+#[test]
+fn compressed_tables_parse_like_match_tables() {
+    for input in ["1", "(1 + 2) * 3 + 4", "((7))", "2 * 3 * (4 + 5 * 6)"] {
+        let expected = matched::Parser::lex_parse(input).unwrap();
+        assert_eq!(
+            compressed::Parser::lex_parse(input).unwrap(),
+            expected,
+            "{input}"
+        );
+        assert_eq!(
+            compressed_lr1::Parser::lex_parse(input).unwrap(),
+            expected,
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn compressed_tables_reject_what_match_tables_reject() {
+    for input in ["", "1 +", "(1 + 2", "1 2", "* 3", "1 + )"] {
+        assert!(matched::Parser::lex_parse(input).is_err(), "{input}");
+        assert!(compressed::Parser::lex_parse(input).is_err(), "{input}");
+        assert!(compressed_lr1::Parser::lex_parse(input).is_err(), "{input}");
+    }
+}
+
+#[test]
+fn compressed_tables_drive_glr() {
+    assert_eq!(ambiguous::Parser::lex_parse("1+2+3").unwrap(), 6);
+    assert!(ambiguous::Parser::lex_parse("1++3").is_err());
+}
@@ -0,0 +1,9 @@
+#[test]
+fn diagnostics() {
+    let cases = trybuild::TestCases::new();
+    cases.compile_fail("tests/ui/hygiene_warnings.rs");
+    cases.pass("tests/ui/hygiene_allowed.rs");
+    cases.compile_fail("tests/ui/associativity_without_precedence.rs");
+    cases.compile_fail("tests/ui/conflicts.rs");
+    cases.compile_fail("tests/ui/allowed_conflicts.rs");
+}
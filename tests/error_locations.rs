@@ -0,0 +1,92 @@
+use semasia::*;
+
+#[grammar]
+mod arithmetic {
+    use super::*;
+
+    #[start_symbol]
+    #[non_terminal]
+    pub type Expression = usize;
+
+    #[non_terminal]
+    pub type Term = usize;
+
+    #[non_terminal]
+    pub type Factor = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Number = usize;
+
+    #[token("+")]
+    pub struct Plus;
+
+    #[token("*")]
+    pub struct Times;
+
+    #[token("(")]
+    pub struct OpenPar;
+
+    #[token(")")]
+    pub struct ClosedPar;
+
+    production!(Addition, Expression -> (Expression, Plus, Term), |(e, _, t)| e + t);
+    production!(NoAddition, Expression -> Term);
+    production!(Multiplication, Term -> (Term, Times, Factor), |(t, _, f)| t * f);
+    production!(NoMultiplication, Term -> Factor);
+    production!(Parenthesis, Factor -> (OpenPar, Expression, ClosedPar), |(_, e, _)| e);
+    production!(ActualNumber, Factor -> Number);
+}
+
+#[test]
+fn unexpected_tokens_are_located() {
+    let source = "1*5+*3";
+    let err = arithmetic::Parser::lex_parse(source).unwrap_err();
+    assert_eq!(err.span(), Some(4..5));
+    assert_eq!(
+        err.render(source),
+        "ParseError: unexpected Times, expected any of [Number, OpenPar]\n --> 1:5\n  |\n1 | 1*5+*3\n  |     ^\n  = help: delete `*` at 1:5\n  = help: insert Number at 1:5"
+    );
+}
+
+#[test]
+fn positions_count_lines_and_characters() {
+    let source = "(1 + 2)\n* (3 4)";
+    let err = arithmetic::Parser::lex_parse(source).unwrap_err();
+    assert_eq!(err.span(), Some(13..14));
+    assert_eq!(
+        err.render(source),
+        "ParseError: unexpected Number, expected any of [Plus, Times, ClosedPar]\n --> 2:6\n  |\n2 | * (3 4)\n  |      ^\n  = help: delete Number at 2:6"
+    );
+}
+
+#[test]
+fn end_of_input_is_located_after_the_last_character() {
+    let source = "12 + 34 *";
+    let err = arithmetic::Parser::lex_parse(source).unwrap_err();
+    assert_eq!(err.span(), Some(9..9));
+    assert!(
+        err.render(source)
+            .ends_with("1 | 12 + 34 *\n  |          ^\n  = help: insert Number at 1:10")
+    );
+}
+
+#[test]
+fn lex_errors_are_located() {
+    let source = "1 + 2 # 3";
+    let err = arithmetic::Parser::lex_parse(source).unwrap_err();
+    assert_eq!(err.span(), Some(6..7));
+    assert_eq!(
+        err.render(source),
+        "LexError: unrecognized token\n --> 1:7\n  |\n1 | 1 + 2 # 3\n  |       ^"
+    );
+}
+
+#[test]
+fn carets_span_the_whole_token() {
+    let source = "1 + 2 345";
+    let err = arithmetic::Parser::lex_parse(source).unwrap_err();
+    assert!(
+        err.render(source)
+            .contains("1 | 1 + 2 345\n  |       ^^^\n  = help: delete Number at 1:7")
+    );
+}
@@ -0,0 +1,133 @@
+use semasia::*;
+
+/// Statements ending with `;`, a statement with a syntax error being skipped up to its `;`
+#[grammar]
+mod statements {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type Program = Vec<Statement>;
+
+    #[non_terminal]
+    pub type Statement = Result<usize, Skipped>;
+
+    #[non_terminal]
+    pub type Sum = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Num = usize;
+
+    #[token("+")]
+    pub struct Plus;
+
+    #[token(";")]
+    pub struct Semicolon;
+
+    #[error_token]
+    pub struct ParseErr(pub Skipped);
+
+    production!(More, Program -> (Program, Statement), |(mut program, statement)| {
+        program.push(statement);
+        program
+    });
+    production!(Empty, Program -> (), |_| Vec::new());
+    production!(Valid, Statement -> (Sum, Semicolon), |(sum, _)| Ok(sum));
+    production!(Invalid, Statement -> (ParseErr, Semicolon), |(ParseErr(skipped), _)| Err(skipped));
+    production!(Addition, Sum -> (Sum, Plus, Num), |(sum, _, num)| sum + num);
+    production!(Single, Sum -> Num);
+}
+
+use statements::*;
+
+/// What a recovery threw away, by name
+#[derive(Debug, PartialEq)]
+pub struct Skipped {
+    popped: Vec<String>,
+    skipped: Vec<String>,
+    span: Option<std::ops::Range<usize>>,
+}
+
+impl From<parser::ErrorRecovery<NonTerminal, Token>> for ParseErr {
+    fn from(recovery: parser::ErrorRecovery<NonTerminal, Token>) -> Self {
+        let popped = recovery.popped.iter().map(|symbol| match symbol {
+            parser::Symbol::NonTerminal(non_terminal) => non_terminal.to_string(),
+            parser::Symbol::Token(token) => token.to_string(),
+        });
+        ParseErr(Skipped {
+            popped: popped.collect(),
+            skipped: recovery.skipped.iter().map(Token::to_string).collect(),
+            span: recovery.span,
+        })
+    }
+}
+
+fn skipped(popped: &[&str], skipped: &[&str], span: std::ops::Range<usize>) -> Statement {
+    Err(Skipped {
+        popped: popped.iter().map(|symbol| symbol.to_string()).collect(),
+        skipped: skipped.iter().map(|token| token.to_string()).collect(),
+        span: Some(span),
+    })
+}
+
+#[test]
+fn statements_with_errors_are_skipped() {
+    assert_eq!(
+        Parser::lex_parse("1 + 2; 3 + + 4 5; 6;").unwrap(),
+        [
+            Ok(3),
+            skipped(&["Sum", "Plus"], &["Plus", "Num", "Num"], 11..16),
+            Ok(6),
+        ]
+    );
+}
+
+#[test]
+fn errors_right_at_the_semicolon_skip_nothing() {
+    assert_eq!(
+        Parser::lex_parse("1 +; 2;").unwrap(),
+        [skipped(&["Sum", "Plus"], &[], 3..4), Ok(2)]
+    );
+    assert_eq!(
+        Parser::lex_parse(";;").unwrap(),
+        [skipped(&[], &[], 0..1), skipped(&[], &[], 1..2)]
+    );
+}
+
+#[test]
+fn several_errors_are_recovered_from() {
+    let program = Parser::lex_parse("+; 1; 2 2; 3 +; 4;").unwrap();
+    assert_eq!(
+        program.iter().map(Result::is_ok).collect::<Vec<_>>(),
+        [false, true, false, false, true]
+    );
+}
+
+#[test]
+fn errors_without_a_following_semicolon_are_not_recovered_from() {
+    assert!(Parser::lex_parse("1; 2 +").is_err());
+    assert!(Parser::lex_parse("1; 2 2").is_err());
+}
+
+#[test]
+fn recovery_works_without_the_lexer() {
+    let tokens = [
+        Token::Num(1),
+        Token::Num(2),
+        Token::Semicolon(Semicolon),
+        Token::Num(3),
+        Token::Semicolon(Semicolon),
+    ];
+    let program = Parser::parse(tokens.into_iter()).unwrap();
+    assert_eq!(
+        program,
+        [
+            Err(Skipped {
+                popped: vec!["Sum".to_string()],
+                skipped: vec!["Num".to_string()],
+                span: None,
+            }),
+            Ok(3),
+        ]
+    );
+}
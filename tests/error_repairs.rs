@@ -0,0 +1,134 @@
+use semasia::*;
+
+#[grammar]
+mod arithmetic {
+    use super::*;
+
+    #[start_symbol]
+    #[non_terminal]
+    pub type Expression = usize;
+
+    #[non_terminal]
+    pub type Term = usize;
+
+    #[non_terminal]
+    pub type Factor = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Number = usize;
+
+    #[token("+")]
+    pub struct Plus;
+
+    #[token("*")]
+    pub struct Times;
+
+    #[token("(")]
+    pub struct OpenPar;
+
+    #[token(")")]
+    pub struct ClosedPar;
+
+    production!(Addition, Expression -> (Expression, Plus, Term), |(e, _, t)| e + t);
+    production!(NoAddition, Expression -> Term);
+    production!(Multiplication, Term -> (Term, Times, Factor), |(t, _, f)| t * f);
+    production!(NoMultiplication, Term -> Factor);
+    production!(Parenthesis, Factor -> (OpenPar, Expression, ClosedPar), |(_, e, _)| e);
+    production!(ActualNumber, Factor -> Number);
+}
+
+use arithmetic::*;
+
+fn insert(token: &'static str, span: std::ops::Range<usize>) -> parser::Repair {
+    parser::Repair::Insert {
+        token,
+        span: Some(span),
+    }
+}
+
+fn delete(token: &'static str, span: std::ops::Range<usize>) -> parser::Repair {
+    parser::Repair::Delete {
+        token,
+        span: Some(span),
+    }
+}
+
+fn repairs(source: &str) -> Vec<Vec<parser::Repair>> {
+    match Parser::lex_parse(source).unwrap_err() {
+        parser::results::LexParseError::ParseError(err) => err.repairs().to_vec(),
+        parser::results::LexParseError::LexError(err) => panic!("unexpected lex error {err}"),
+    }
+}
+
+#[test]
+fn cheapest_repairs_are_found() {
+    assert_eq!(
+        repairs("1*5+*3"),
+        [vec![delete("`*`", 4..5)], vec![insert("Number", 4..4)]]
+    );
+}
+
+#[test]
+fn missing_tokens_are_inserted_at_the_end_of_input() {
+    assert_eq!(repairs("(1+2"), [vec![insert("`)`", 4..4)]]);
+}
+
+#[test]
+fn repairs_are_rendered_as_help() {
+    let source = "(1+2";
+    let err = Parser::lex_parse(source).unwrap_err();
+    assert!(
+        err.render(source)
+            .ends_with("\n  = help: insert `)` at 1:5")
+    );
+}
+
+#[test]
+fn repairing_reports_every_error() {
+    let (value, errors) = Parser::lex_parse_repairing("1 + + 2 * (3 4").unwrap();
+    assert_eq!(value, 7);
+    assert_eq!(errors.len(), 2);
+    assert_eq!(errors[0].span, Some(4..5));
+    assert_eq!(errors[0].repairs[0], [delete("`+`", 4..5)]);
+    assert_eq!(errors[1].span, Some(13..14));
+    assert_eq!(
+        errors[1].repairs[0],
+        [insert("`)`", 13..13), delete("Number", 13..14)]
+    );
+}
+
+#[test]
+fn repairing_stops_when_no_repair_can_be_applied() {
+    // a number to insert can't be made up
+    let source = "12 + 34 *";
+    let err = Parser::lex_parse_repairing(source).unwrap_err();
+    assert_eq!(err.span(), Some(9..9));
+}
+
+#[test]
+fn repairs_are_found_without_the_source() {
+    let tokens = [
+        Token::Number(1),
+        Token::Times(Times),
+        Token::Number(2),
+        Token::Number(3),
+    ];
+    let err = Parser::parse(tokens.into_iter()).unwrap_err();
+    assert_eq!(
+        err.repairs(),
+        [
+            vec![parser::Repair::Delete {
+                token: "Number",
+                span: None
+            }],
+            vec![parser::Repair::Insert {
+                token: "`+`",
+                span: None
+            }],
+            vec![parser::Repair::Insert {
+                token: "`*`",
+                span: None
+            }],
+        ]
+    );
+}
@@ -0,0 +1,71 @@
+use semasia::*;
+
+/// The dangling else: its only conflict is declared, shifting `Else` attaches it to the closest
+/// `If`. The automaton is described in a report and a Graphviz file
+#[grammar(
+    report = "target/semasia/dangling_else.output",
+    dot = "target/semasia/dangling_else.dot"
+)]
+#[expect_conflicts(shift_reduce = 1)]
+mod dangling_else {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type S = String;
+
+    #[token("if")]
+    pub struct If;
+
+    #[token("else")]
+    pub struct Else;
+
+    #[token("x")]
+    pub struct X;
+
+    production!(P1, S -> (If, S), |(_, s)| format!("if({s})"));
+    production!(P2, S -> (If, S, Else, S), |(_, s1, _, s2)| format!("if({s1}, {s2})"));
+    production!(P3, S -> X, |_| "x".to_string());
+}
+
+#[test]
+fn expected_conflicts_are_resolved_by_shifting() {
+    assert_eq!(
+        dangling_else::Parser::lex_parse("if if x else x").unwrap(),
+        "if(if(x, x))"
+    );
+    assert_eq!(
+        dangling_else::Parser::lex_parse("if x else if x").unwrap(),
+        "if(x, if(x))"
+    );
+}
+
+#[test]
+fn report_lists_the_expected_conflict() {
+    let report = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/target/semasia/dangling_else.output"
+    ))
+    .unwrap();
+    assert!(report.starts_with("Grammar\n\n    P1 (S -> If S)\n"));
+    assert!(report.contains("\n    S -> If S·  [Else, $end]\n    S -> If S·Else S\n"));
+    assert!(report.contains("\n    Else            [reduce using P1 (S -> If S)]\n"));
+    assert_eq!(
+        report
+            .matches("Unresolved shift/reduce conflict on Else")
+            .count(),
+        1
+    );
+}
+
+#[test]
+fn dot_highlights_the_conflict_state() {
+    let dot = std::fs::read_to_string(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/target/semasia/dangling_else.dot"
+    ))
+    .unwrap();
+    assert!(dot.starts_with("digraph automaton {\n"));
+    assert!(dot.contains("\\lS -> If S·  [Else, $end]\\lS -> If S·Else S\\l\", color=red"));
+    assert!(dot.contains(" [label=\"Else\"];\n"));
+}
@@ -0,0 +1,273 @@
+use semasia::*;
+
+/// The ambiguous-expressions example in GLR mode: precedence still solves its conflicts, so the
+/// GLR parser follows a single derivation
+#[grammar(glr = true)]
+mod annotated {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type E = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Id = usize;
+
+    #[token("+")]
+    #[derive(Clone)]
+    pub struct Plus;
+
+    #[token("-")]
+    #[derive(Clone)]
+    pub struct Minus;
+
+    #[token("*")]
+    #[derive(Clone)]
+    pub struct Times;
+
+    #[token("/")]
+    #[derive(Clone)]
+    pub struct Division;
+
+    #[token("^")]
+    #[derive(Clone)]
+    pub struct Power;
+
+    #[token("(")]
+    #[derive(Clone)]
+    pub struct OpenPar;
+
+    #[token(")")]
+    #[derive(Clone)]
+    pub struct ClosePar;
+
+    #[precedence = 0]
+    #[left_associative]
+    production!(P1, E -> (E, Plus, E), |(e1, _, e2)| e1 + e2);
+
+    #[precedence = 0]
+    #[left_associative]
+    production!(P2, E -> (E, Minus, E), |(e1, _, e2)| e1 - e2);
+
+    #[precedence = 1]
+    #[left_associative]
+    production!(P3, E -> (E, Times, E), |(e1, _, e2)| e1 * e2);
+
+    #[precedence = 1]
+    #[left_associative]
+    production!(P4, E -> (E, Division, E), |(e1, _, e2)| e1 / e2);
+
+    #[precedence = 2]
+    #[right_associative]
+    production!(P5, E -> (E, Power, E), |(e1, _, e2)| e1.pow(e2 as u32));
+
+    #[precedence = 3]
+    production!(P6, E -> (OpenPar, E, ClosePar), |(_, e, _)| e);
+
+    #[precedence = 3]
+    production!(P7, E -> Id);
+}
+
+/// The same grammar without annotations: every derivation is built, and `merge_e` keeps the one
+/// precedence and associativity would have picked
+#[grammar(glr = true)]
+mod merged {
+    use super::*;
+
+    #[derive(Clone, Debug)]
+    pub enum Expr {
+        Num(usize),
+        Paren(Box<Expr>),
+        Binary(char, Box<Expr>, Box<Expr>),
+    }
+
+    impl Expr {
+        fn binary(op: char, e1: Expr, e2: Expr) -> Self {
+            Self::Binary(op, Box::new(e1), Box::new(e2))
+        }
+
+        pub fn eval(&self) -> usize {
+            match self {
+                Self::Num(n) => *n,
+                Self::Paren(e) => e.eval(),
+                Self::Binary('+', e1, e2) => e1.eval() + e2.eval(),
+                Self::Binary('-', e1, e2) => e1.eval() - e2.eval(),
+                Self::Binary('*', e1, e2) => e1.eval() * e2.eval(),
+                Self::Binary('/', e1, e2) => e1.eval() / e2.eval(),
+                Self::Binary(_, e1, e2) => e1.eval().pow(e2.eval() as u32),
+            }
+        }
+
+        fn precedence(&self) -> usize {
+            match self {
+                Self::Binary('+' | '-', ..) => 0,
+                Self::Binary('*' | '/', ..) => 1,
+                Self::Binary(..) => 2,
+                Self::Num(_) | Self::Paren(_) => 3,
+            }
+        }
+    }
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type E = Expr;
+
+    #[token(regex = r"\d+")]
+    pub type Id = usize;
+
+    #[token("+")]
+    #[derive(Clone)]
+    pub struct Plus;
+
+    #[token("-")]
+    #[derive(Clone)]
+    pub struct Minus;
+
+    #[token("*")]
+    #[derive(Clone)]
+    pub struct Times;
+
+    #[token("/")]
+    #[derive(Clone)]
+    pub struct Division;
+
+    #[token("^")]
+    #[derive(Clone)]
+    pub struct Power;
+
+    #[token("(")]
+    #[derive(Clone)]
+    pub struct OpenPar;
+
+    #[token(")")]
+    #[derive(Clone)]
+    pub struct ClosePar;
+
+    production!(P1, E -> (E, Plus, E), |(e1, _, e2)| Expr::binary('+', e1, e2));
+    production!(P2, E -> (E, Minus, E), |(e1, _, e2)| Expr::binary('-', e1, e2));
+    production!(P3, E -> (E, Times, E), |(e1, _, e2)| Expr::binary('*', e1, e2));
+    production!(P4, E -> (E, Division, E), |(e1, _, e2)| Expr::binary('/', e1, e2));
+    production!(P5, E -> (E, Power, E), |(e1, _, e2)| Expr::binary('^', e1, e2));
+    production!(P6, E -> (OpenPar, E, ClosePar), |(_, e, _)| Expr::Paren(Box::new(e)));
+    production!(P7, E -> Id, |id| Expr::Num(id));
+
+    /// The loosest operator goes at the root, operators of the same precedence nest on the left,
+    /// except for the right associative power
+    #[merge]
+    fn merge_e(first: E, second: E) -> E {
+        let nests_left = |e: &Expr| match e {
+            Expr::Binary(_, left, _) => left.precedence() == e.precedence(),
+            _ => false,
+        };
+        match first.precedence().cmp(&second.precedence()) {
+            std::cmp::Ordering::Less => first,
+            std::cmp::Ordering::Greater => second,
+            std::cmp::Ordering::Equal if nests_left(&first) == (first.precedence() != 2) => first,
+            std::cmp::Ordering::Equal => second,
+        }
+    }
+}
+
+/// Ambiguous without a merge function
+#[grammar(glr = true)]
+mod unmerged {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type E = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Id = usize;
+
+    #[token("+")]
+    #[derive(Clone)]
+    pub struct Plus;
+
+    production!(P1, E -> (E, Plus, E), |(e1, _, e2)| e1 + e2);
+    production!(P2, E -> Id);
+}
+
+/// Every bracketing of a sequence is a derivation, counted by merging
+#[grammar(glr = true)]
+mod sequences {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type E = u128;
+
+    #[token("a")]
+    #[derive(Clone)]
+    pub struct A;
+
+    production!(P1, E -> (E, E), |(e1, e2)| e1 * e2);
+    production!(P2, E -> A, |_| 1);
+
+    #[merge]
+    fn merge_e(first: E, second: E) -> E {
+        first + second
+    }
+}
+
+const EXPRESSIONS: [(&str, usize); 6] = [
+    ("5^2-1", 24),
+    ("2^3^2", 512),
+    ("10-2-3", 5),
+    ("2+3*4", 14),
+    ("(2+3)*4", 20),
+    ("100/10/5+2*3^2", 20),
+];
+
+#[test]
+fn glr_follows_precedence() {
+    for (input, expected) in EXPRESSIONS {
+        assert_eq!(
+            annotated::Parser::lex_parse(input).unwrap(),
+            expected,
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn glr_merges_derivations() {
+    for (input, expected) in EXPRESSIONS {
+        assert_eq!(
+            merged::Parser::lex_parse(input).unwrap().eval(),
+            expected,
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn glr_reports_ambiguities_without_merge() {
+    assert_eq!(unmerged::Parser::lex_parse("1+2").unwrap(), 3);
+    let err = unmerged::Parser::lex_parse("1+2+3").unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "ParseError: ambiguous E, there is no #[merge] function to choose between its derivations"
+    );
+}
+
+#[test]
+fn glr_reports_syntax_errors() {
+    let err = merged::Parser::lex_parse("1+*2").unwrap_err();
+    assert!(
+        err.to_string()
+            .starts_with("ParseError: unexpected Times at token 2")
+    );
+    assert!(merged::Parser::lex_parse("(1+2").is_err());
+}
+
+#[test]
+fn glr_shares_derivations_of_highly_ambiguous_input() {
+    // A sequence of n + 1 symbols has Catalan(n) bracketings
+    let length = 60;
+    let catalan = (0..length - 1).fold(1u128, |catalan, n| catalan * (4 * n + 2) / (n + 2));
+    assert_eq!(
+        sequences::Parser::lex_parse(&"a".repeat(length as usize)).unwrap(),
+        catalan
+    );
+}
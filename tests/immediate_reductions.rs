@@ -0,0 +1,131 @@
+use semasia::*;
+
+/// Statements printed by their semantic action, which has to run as soon as the `;` closing the
+/// statement is shifted. The empty program is reduced before any token is read
+#[grammar]
+mod statements {
+    use super::*;
+
+    #[context]
+    pub type Log = std::rc::Rc<std::cell::RefCell<Vec<String>>>;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type Program = usize;
+
+    #[non_terminal]
+    pub struct Statement;
+
+    #[token("print")]
+    pub struct Print;
+
+    #[token(regex = r"\d+")]
+    pub type Num = usize;
+
+    #[token(";")]
+    pub struct Semicolon;
+
+    production!(More, Program -> (Program, Statement), |(count, _)| count + 1);
+    production!(Empty, Program -> (), |log, _| {
+        log.borrow_mut().push("start".to_string());
+        0
+    });
+    production!(PrintNum, Statement -> (Print, Num, Semicolon), |log, (_, num, _)| {
+        log.borrow_mut().push(format!("print {num}"));
+        Statement
+    });
+}
+
+use statements::*;
+
+use parser::results::ParseTokenError;
+
+#[test]
+fn statements_are_reduced_before_the_next_token_is_read() {
+    let log = Log::default();
+    let tokens = [
+        Token::Print(Print),
+        Token::Num(1),
+        Token::Semicolon(Semicolon),
+        Token::Print(Print),
+        Token::Num(2),
+        Token::Semicolon(Semicolon),
+    ];
+    let tokens = tokens.into_iter().inspect(|token| {
+        log.borrow_mut().push(format!("read {token}"));
+    });
+    assert_eq!(Parser::parse_with_ctx(log.clone(), tokens).unwrap(), 2);
+    assert_eq!(
+        *log.borrow(),
+        [
+            "start",
+            "read Print",
+            "read Num",
+            "read Semicolon",
+            "print 1",
+            "read Print",
+            "read Num",
+            "read Semicolon",
+            "print 2",
+        ]
+    );
+}
+
+#[test]
+fn the_start_state_reduces_before_any_token_is_pushed() {
+    let log = Log::default();
+    let parser = Parser::start(log.clone());
+    assert_eq!(*log.borrow(), ["start"]);
+    assert_eq!(parser.finish().unwrap(), 0);
+}
+
+#[test]
+fn errors_are_still_detected() {
+    assert!(Parser::lex_parse_default_ctx("print 1; print;").is_err());
+    assert!(Parser::lex_parse_default_ctx("print 1 print 2;").is_err());
+    assert_eq!(
+        Parser::lex_parse_default_ctx("print 1; print 2;").unwrap(),
+        2
+    );
+}
+
+#[test]
+fn tokens_are_parsed_as_they_are_pushed() {
+    let log = Log::default();
+    let mut parser = Parser::start(log.clone());
+    parser.push(Token::Print(Print)).unwrap();
+    parser.push(Token::Num(1)).unwrap();
+    assert_eq!(*log.borrow(), ["start"]);
+    parser.push(Token::Semicolon(Semicolon)).unwrap();
+    assert_eq!(*log.borrow(), ["start", "print 1"]);
+    parser.push(Token::Print(Print)).unwrap();
+    parser.push(Token::Num(2)).unwrap();
+    parser.push(Token::Semicolon(Semicolon)).unwrap();
+    assert_eq!(parser.finish().unwrap(), 2);
+    assert_eq!(*log.borrow(), ["start", "print 1", "print 2"]);
+}
+
+#[test]
+fn failing_tokens_are_given_back() {
+    let mut parser = Parser::start(Log::default());
+    parser.push(Token::Print(Print)).unwrap();
+    assert!(matches!(
+        parser.push(Token::Semicolon(Semicolon)),
+        Err(ParseTokenError::ActionNotFound {
+            leftover_token: Token::Semicolon(_)
+        })
+    ));
+    parser.push(Token::Num(1)).unwrap();
+    parser.push(Token::Semicolon(Semicolon)).unwrap();
+    assert_eq!(parser.finish().unwrap(), 1);
+}
+
+#[test]
+fn unfinished_input_can_be_resumed() {
+    let mut parser = Parser::start(Log::default());
+    parser.push(Token::Print(Print)).unwrap();
+    let err = parser.finish().unwrap_err();
+    assert!(err.leftover_token().is_none());
+    let parser = err.resume_with(Token::Num(3)).unwrap();
+    assert_eq!(parser.resume([Token::Semicolon(Semicolon)]).unwrap(), 1);
+}
@@ -0,0 +1,61 @@
+use semasia::*;
+
+/// Precedence declared on the productions: `^` binds tighter than `*`, which binds tighter than
+/// `+` and `-`
+#[grammar]
+mod arithmetic {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type E = i64;
+
+    #[token(regex = r"\d+")]
+    pub type Num = i64;
+
+    #[token("+")]
+    pub struct Plus;
+
+    #[token("-")]
+    pub struct Minus;
+
+    #[token("*")]
+    pub struct Times;
+
+    #[token("^")]
+    pub struct Power;
+
+    #[precedence = 0]
+    #[left_associative]
+    production!(Add, E -> (E, Plus, E), |(e1, _, e2)| e1 + e2);
+
+    #[precedence = 0]
+    #[left_associative]
+    production!(Sub, E -> (E, Minus, E), |(e1, _, e2)| e1 - e2);
+
+    #[precedence = 1]
+    #[left_associative]
+    production!(Mul, E -> (E, Times, E), |(e1, _, e2)| e1 * e2);
+
+    #[precedence(2)]
+    #[right_associative]
+    production!(Pow, E -> (E, Power, E), |(e1, _, e2)| e1.pow(e2 as u32));
+
+    production!(Lit, E -> Num);
+}
+
+#[test]
+fn right_associative_productions_nest_on_the_right() {
+    assert_eq!(arithmetic::Parser::lex_parse("2^3^2").unwrap(), 512);
+}
+
+#[test]
+fn left_associative_productions_nest_on_the_left() {
+    assert_eq!(arithmetic::Parser::lex_parse("10-2-3").unwrap(), 5);
+}
+
+#[test]
+fn higher_precedence_productions_are_reduced_first() {
+    assert_eq!(arithmetic::Parser::lex_parse("1+2*3").unwrap(), 7);
+    assert_eq!(arithmetic::Parser::lex_parse("2*3^2").unwrap(), 18);
+}
@@ -0,0 +1,55 @@
+use semasia::*;
+
+/// The same non-terminal before `c` and `d`: LALR(1) merges the states after the error token, so
+/// a token can follow the error token and still fail once it is reduced
+#[grammar]
+mod contexts {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type S = &'static str;
+
+    #[non_terminal]
+    pub struct E;
+
+    #[token("a")]
+    pub struct A;
+
+    #[token("b")]
+    pub struct B;
+
+    #[token("c")]
+    pub struct C;
+
+    #[token("d")]
+    pub struct D;
+
+    #[error_token]
+    pub struct Invalid;
+
+    production!(AEc, S -> (A, E, C), |_| "a e c");
+    production!(BEd, S -> (B, E, D), |_| "b e d");
+    production!(ErrE, E -> Invalid, |_| E);
+}
+
+use contexts::*;
+
+impl From<parser::ErrorRecovery<NonTerminal, Token>> for Invalid {
+    fn from(_: parser::ErrorRecovery<NonTerminal, Token>) -> Self {
+        Invalid
+    }
+}
+
+#[test]
+fn tokens_dropped_after_a_recovery_are_kept() {
+    let mut parser = Parser::start(());
+    parser.push(Token::A(A)).unwrap();
+    parser.push(Token::B(B)).unwrap();
+    assert!(parser.take_dropped_tokens().is_empty());
+    parser.push(Token::D(D)).unwrap();
+    assert!(matches!(parser.take_dropped_tokens()[..], [Token::D(_)]));
+    parser.push(Token::C(C)).unwrap();
+    assert!(parser.take_dropped_tokens().is_empty());
+    assert_eq!(parser.finish().unwrap(), "a e c");
+}
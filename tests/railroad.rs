@@ -0,0 +1,44 @@
+use semasia::*;
+
+/// Repetitions and alternatives of `ebnf!`, drawn in a railroad diagram per non-terminal
+#[grammar(railroad = "target/semasia/railroad")]
+mod abcs {
+    use semasia::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub struct S(pub Vec<A>, pub usize);
+
+    #[token("a")]
+    pub struct A;
+
+    #[token("c")]
+    pub struct C;
+
+    #[token("d")]
+    pub struct D;
+
+    ebnf!(P0, S -> (A*, CorD { C, D }), |(a, c_or_d)| {
+        S(a, match c_or_d { CorD::C(_) => 0, CorD::D(_) => 1 })
+    });
+}
+
+#[test]
+fn railroad_diagram_folds_ebnf_helpers() {
+    let Ok(abcs::S(a, c_or_d)) = abcs::Parser::lex_parse("aad") else {
+        panic!("aad rejected");
+    };
+    assert_eq!((a.len(), c_or_d), (2, 1));
+    let directory = concat!(env!("CARGO_MANIFEST_DIR"), "/target/semasia/railroad");
+    let svg = std::fs::read_to_string(format!("{directory}/S.svg")).unwrap();
+    assert!(svg.starts_with("<svg "));
+    for terminal in ["a", "c", "d"] {
+        assert!(svg.contains(&format!(">{terminal}</text>")), "{terminal}");
+    }
+    assert!(!svg.contains("__P0"));
+    // The directory is shared with earlier builds, only the helpers must be absent from it
+    for entry in std::fs::read_dir(directory).unwrap() {
+        let file = entry.unwrap().file_name().into_string().unwrap();
+        assert!(!file.starts_with("__"), "{file}");
+    }
+}
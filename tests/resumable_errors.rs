@@ -0,0 +1,112 @@
+use semasia::*;
+
+#[grammar]
+mod arithmetic {
+    use super::*;
+
+    #[start_symbol]
+    #[non_terminal]
+    pub type Expression = usize;
+
+    #[non_terminal]
+    pub type Term = usize;
+
+    #[non_terminal]
+    pub type Factor = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Number = usize;
+
+    #[token("+")]
+    pub struct Plus;
+
+    #[token("*")]
+    pub struct Times;
+
+    #[token("(")]
+    pub struct OpenPar;
+
+    #[token(")")]
+    pub struct ClosedPar;
+
+    production!(Addition, Expression -> (Expression, Plus, Term), |(e, _, t)| e + t);
+    production!(NoAddition, Expression -> Term);
+    production!(Multiplication, Term -> (Term, Times, Factor), |(t, _, f)| t * f);
+    production!(NoMultiplication, Term -> Factor);
+    production!(Parenthesis, Factor -> (OpenPar, Expression, ClosedPar), |(_, e, _)| e);
+    production!(ActualNumber, Factor -> Number);
+}
+
+use arithmetic::*;
+
+#[test]
+fn leftover_token_is_given_back() {
+    let tokens = [
+        Token::Number(1),
+        Token::Times(Times),
+        Token::Times(Times),
+        Token::Number(2),
+    ];
+    let err = Parser::parse(tokens.into_iter()).unwrap_err();
+    assert!(matches!(err.leftover_token(), Some(Token::Times(_))));
+    assert!(err.leftover_non_terminal().is_none());
+}
+
+#[test]
+fn skipped_tokens_are_dropped() {
+    let mut tokens = [
+        Token::Number(1),
+        Token::Plus(Plus),
+        Token::Plus(Plus),
+        Token::Number(2),
+        Token::Times(Times),
+        Token::Number(3),
+        Token::Plus(Plus),
+        Token::Number(4),
+    ]
+    .into_iter();
+    let err = Parser::parse(tokens.by_ref()).unwrap_err();
+    let parser = err.skip().unwrap();
+    assert_eq!(parser.resume(tokens).unwrap(), 11);
+}
+
+#[test]
+fn tokens_are_inserted_before_the_leftover_one() {
+    let mut tokens = [
+        Token::Number(1),
+        Token::Number(2),
+        Token::Plus(Plus),
+        Token::Number(3),
+        Token::Times(Times),
+        Token::Number(4),
+        Token::Plus(Plus),
+        Token::Number(5),
+    ]
+    .into_iter();
+    let err = Parser::parse(tokens.by_ref()).unwrap_err();
+    let parser = err.resume_with(Token::Times(Times)).unwrap();
+    assert_eq!(parser.resume(tokens).unwrap(), 19);
+}
+
+#[test]
+fn missing_end_of_input_tokens_are_inserted() {
+    let err = Parser::lex_parse("(1 + 2").unwrap_err();
+    let parser::results::LexParseError::ParseError(err) = err else {
+        panic!("unexpected lex error");
+    };
+    assert!(err.leftover_token().is_none());
+    let parser = err.resume_with(Token::ClosedPar(ClosedPar)).unwrap();
+    assert_eq!(parser.resume([]).unwrap(), 3);
+}
+
+#[test]
+fn failing_resumptions_give_back_the_error() {
+    let err = Parser::lex_parse("1 + + * 2").unwrap_err();
+    let parser::results::LexParseError::ParseError(err) = err else {
+        panic!("unexpected lex error");
+    };
+    let err = err.skip().unwrap_err();
+    assert_eq!(err.span(), Some(6..7));
+    let parser = err.skip().unwrap();
+    assert_eq!(parser.resume([]).unwrap(), 3);
+}
@@ -0,0 +1,69 @@
+use semasia::*;
+
+/// Precedence declared on the operator tokens only: each binary production takes the precedence
+/// of its operator, and unary minus binds tighter than every binary operator through
+/// `#[prec(UMinus)]`
+#[grammar]
+mod arithmetic {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type E = i64;
+
+    #[token(regex = r"\d+")]
+    pub type Num = i64;
+
+    #[token("+", precedence = 0, assoc = "left")]
+    pub struct Plus;
+
+    #[token("-", precedence = 0, assoc = "left")]
+    pub struct Minus;
+
+    #[token("*", precedence = 1, assoc = "left")]
+    pub struct Times;
+
+    #[token("^", precedence = 3, assoc = "right")]
+    pub struct Power;
+
+    #[token("~", precedence = 2)]
+    pub struct UMinus;
+
+    production!(Add, E -> (E, Plus, E), |(e1, _, e2)| e1 + e2);
+    production!(Sub, E -> (E, Minus, E), |(e1, _, e2)| e1 - e2);
+    production!(Mul, E -> (E, Times, E), |(e1, _, e2)| e1 * e2);
+    production!(Pow, E -> (E, Power, E), |(e1, _, e2)| e1.pow(e2 as u32));
+
+    #[prec(UMinus)]
+    production!(Neg, E -> (Minus, E), |(_, e)| -e);
+
+    production!(Lit, E -> Num);
+}
+
+#[test]
+fn productions_take_the_precedence_of_their_last_token() {
+    for (input, expected) in [
+        ("1+2*3", 7),
+        ("2*3+1", 7),
+        ("10-2-3", 5),
+        ("2^3^2", 512),
+        ("2*3^2", 18),
+    ] {
+        assert_eq!(
+            arithmetic::Parser::lex_parse(input).unwrap(),
+            expected,
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn prec_overrides_the_last_token() {
+    for (input, expected) in [("-2*3", -6), ("-2+3", 1), ("1--2", 3), ("-2^2", -4)] {
+        assert_eq!(
+            arithmetic::Parser::lex_parse(input).unwrap(),
+            expected,
+            "{input}"
+        );
+    }
+}
@@ -0,0 +1,23 @@
+use semasia::*;
+
+#[grammar]
+mod arithmetic {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type E = i64;
+
+    #[token(regex = r"\d+")]
+    pub type Num = i64;
+
+    #[token("-")]
+    pub struct Minus;
+
+    #[left_associative]
+    production!(Neg, E -> (Minus, E), |(_, e)| -e);
+
+    production!(Lit, E -> Num);
+}
+
+fn main() {}
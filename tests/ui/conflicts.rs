@@ -0,0 +1,25 @@
+use semasia::*;
+
+#[grammar]
+mod dangling_else {
+    use super::*;
+
+    #[non_terminal]
+    #[start_symbol]
+    pub type S = String;
+
+    #[token("if")]
+    pub struct If;
+
+    #[token("else")]
+    pub struct Else;
+
+    #[token("x")]
+    pub struct X;
+
+    production!(P1, S -> (If, S), |(_, s)| format!("if({s})"));
+    production!(P2, S -> (If, S, Else, S), |(_, s1, _, s2)| format!("if({s1}, {s2})"));
+    production!(P3, S -> X, |_| "x".to_string());
+}
+
+fn main() {}
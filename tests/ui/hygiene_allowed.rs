@@ -0,0 +1,32 @@
+#![deny(deprecated)]
+
+use semasia::*;
+
+#[grammar]
+mod useless_symbols {
+    use super::*;
+
+    #[start_symbol]
+    #[non_terminal]
+    pub type Sum = usize;
+
+    #[non_terminal]
+    #[allow_unreachable]
+    #[allow_no_productions]
+    pub type Product = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Num = usize;
+
+    #[token("+")]
+    pub struct Plus;
+
+    #[token("*")]
+    #[allow_unused]
+    pub struct Times;
+
+    production!(Addition, Sum -> (Sum, Plus, Num), |(sum, _, num)| sum + num);
+    production!(Single, Sum -> Num);
+}
+
+fn main() {}
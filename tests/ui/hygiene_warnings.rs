@@ -0,0 +1,29 @@
+#![deny(deprecated)]
+
+use semasia::*;
+
+#[grammar]
+mod useless_symbols {
+    use super::*;
+
+    #[start_symbol]
+    #[non_terminal]
+    pub type Sum = usize;
+
+    #[non_terminal]
+    pub type Product = usize;
+
+    #[token(regex = r"\d+")]
+    pub type Num = usize;
+
+    #[token("+")]
+    pub struct Plus;
+
+    #[token("*")]
+    pub struct Times;
+
+    production!(Addition, Sum -> (Sum, Plus, Num), |(sum, _, num)| sum + num);
+    production!(Single, Sum -> Num);
+}
+
+fn main() {}
@@ -0,0 +1,92 @@
+use semasia::*;
+
+/// Arithmetic with a type per level, converted into each other by the unit productions, with
+/// each table encoding
+macro_rules! arithmetic {
+    ($name:ident, $($options:tt)*) => {
+        #[grammar($($options)*)]
+        mod $name {
+            use super::*;
+
+            #[start_symbol]
+            #[non_terminal]
+            pub type Expression = i64;
+
+            #[non_terminal]
+            pub struct Term(i64);
+
+            #[non_terminal]
+            pub struct Factor(i64);
+
+            impl From<Term> for i64 {
+                fn from(Term(value): Term) -> Self {
+                    value
+                }
+            }
+
+            impl From<Factor> for Term {
+                fn from(Factor(value): Factor) -> Self {
+                    Term(value)
+                }
+            }
+
+            #[token(regex = r"\d+")]
+            pub type Number = i64;
+
+            #[token("+")]
+            pub struct Plus;
+
+            #[token("*")]
+            pub struct Times;
+
+            #[token("(")]
+            pub struct OpenPar;
+
+            #[token(")")]
+            pub struct ClosedPar;
+
+            production!(Addition, Expression -> (Expression, Plus, Term), |(e, _, Term(t))| e + t);
+            production!(NoAddition, Expression -> Term);
+            production!(Multiplication, Term -> (Term, Times, Factor), |(Term(t), _, Factor(f))| Term(t * f));
+            production!(NoMultiplication, Term -> Factor);
+            production!(Parenthesis, Factor -> (OpenPar, Expression, ClosedPar), |(_, e, _)| Factor(e));
+            production!(ActualNumber, Factor -> Number, |n| Factor(n));
+        }
+    };
+}
+
+arithmetic!(kept, bypass_unit_productions = false);
+arithmetic!(bypassed, bypass_unit_productions = true);
+arithmetic!(
+    bypassed_compressed,
+    bypass_unit_productions = true,
+    tables = "compressed"
+);
+
+#[test]
+fn bypassed_unit_productions_convert_the_values() {
+    for input in ["1", "(1 + 2) * 3 + 4", "((7))", "2 * 3 * (4 + 5 * 6)"] {
+        let expected = kept::Parser::lex_parse(input).unwrap();
+        assert_eq!(
+            bypassed::Parser::lex_parse(input).unwrap(),
+            expected,
+            "{input}"
+        );
+        assert_eq!(
+            bypassed_compressed::Parser::lex_parse(input).unwrap(),
+            expected,
+            "{input}"
+        );
+    }
+}
+
+#[test]
+fn bypassed_unit_productions_keep_errors() {
+    for input in ["", "1 +", "(1 + 2", "1 2", "* 3", "1 + )"] {
+        assert!(bypassed::Parser::lex_parse(input).is_err(), "{input}");
+        assert!(
+            bypassed_compressed::Parser::lex_parse(input).is_err(),
+            "{input}"
+        );
+    }
+}